@@ -0,0 +1,81 @@
+use cfg::{EarleyParser, Grammar};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// The day19 part 2 loops with single character rules, so that every split
+// point between rules 8 and 11 has to be tried.
+fn recursive_grammar() -> Grammar {
+    Grammar::parse(
+        &mut [
+            "0: 8 11",
+            "8: 42 | 42 8",
+            "11: 42 31 | 42 11 31",
+            "42: \"a\"",
+            "31: \"b\"",
+        ]
+        .iter(),
+    )
+    .unwrap()
+}
+
+fn non_recursive_grammar() -> Grammar {
+    Grammar::parse(
+        &mut [
+            "0: 4 1 5",
+            "1: 2 3 | 3 2",
+            "2: 4 4 | 5 5",
+            "3: 4 5 | 5 4",
+            "4: \"a\"",
+            "5: \"b\"",
+        ]
+        .iter(),
+    )
+    .unwrap()
+}
+
+// All strings over a and b of the given length, one per line.
+fn all_messages(length: u32) -> Vec<String> {
+    (0..1 << length)
+        .map(|bits: u32| {
+            (0..length)
+                .map(|i| if bits >> i & 1 == 0 { 'a' } else { 'b' })
+                .collect()
+        })
+        .collect()
+}
+
+fn bench_compiled(c: &mut Criterion) {
+    let grammar = non_recursive_grammar();
+    let parser = EarleyParser::new(&grammar);
+    let compiled = grammar.to_dfa().unwrap();
+    let messages = all_messages(12);
+    let mut group = c.benchmark_group("day19_non_recursive");
+    group.bench_function("packrat", |b| {
+        b.iter(|| messages.iter().filter(|m| grammar.accepts(m)).count())
+    });
+    group.bench_function("earley", |b| {
+        b.iter(|| messages.iter().filter(|m| parser.accepts(m)).count())
+    });
+    group.bench_function("dfa", |b| {
+        b.iter(|| messages.iter().filter(|m| compiled.accepts(m)).count())
+    });
+    group.finish();
+}
+
+fn bench_matching(c: &mut Criterion) {
+    let grammar = recursive_grammar();
+    let parser = EarleyParser::new(&grammar);
+    let mut group = c.benchmark_group("day19_matching");
+    for &n in &[50, 200, 500] {
+        let input = "a".repeat(2 * n) + &"b".repeat(n);
+        group.bench_with_input(BenchmarkId::new("packrat", n), &input, |b, input| {
+            b.iter(|| assert!(grammar.accepts(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("earley", n), &input, |b, input| {
+            b.iter(|| assert!(parser.accepts(input)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_matching, bench_compiled);
+criterion_main!(benches);
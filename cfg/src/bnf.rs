@@ -0,0 +1,323 @@
+#[cfg(test)]
+use super::EarleyParser;
+use super::{parse_escaped_char, Grammar, GrammarParseError, ProductionRule};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, multispace0, none_of, one_of, satisfy},
+    combinator::{map, not, opt, recognize},
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    IResult,
+};
+use parsing::parse_full;
+use std::collections::HashMap;
+
+// Grammar in BNF with the usual EBNF extensions, before lowering it into
+// production rules.
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Terminal(String),
+    Nonterminal(String),
+    OneOf(Alternatives),
+    Repeat(Box<Expr>),
+    RepeatAtLeastOnce(Box<Expr>),
+    Optional(Box<Expr>),
+}
+
+// Sequences of items, one per alternative.
+type Alternatives = Vec<Vec<Expr>>;
+
+fn ws<'a, O>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    preceded(multispace0, parser)
+}
+
+fn parse_terminal(input: &str) -> IResult<&str, String> {
+    let quoted = |quote| {
+        delimited(
+            char(quote),
+            many0(alt((
+                parse_escaped_char,
+                none_of(if quote == '"' { "\"\\" } else { "'\\" }),
+            ))),
+            char(quote),
+        )
+    };
+    map(alt((quoted('"'), quoted('\''))), |chars| {
+        chars.into_iter().collect()
+    })(input)
+}
+
+// Nonterminals are either enclosed in angle brackets or bare identifiers.
+fn parse_name(input: &str) -> IResult<&str, &str> {
+    let identifier = recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic()),
+        many0(satisfy(|c| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '-'
+        })),
+    ));
+    alt((
+        delimited(char('<'), take_while1(|c| c != '>'), char('>')),
+        identifier,
+    ))(input)
+}
+
+fn parse_item(input: &str) -> IResult<&str, Expr> {
+    // A name followed by `::=` starts the next definition.
+    let nonterminal = map(terminated(parse_name, not(ws(tag("::=")))), |name| {
+        Expr::Nonterminal(String::from(name))
+    });
+    let group = |open, close| {
+        map(
+            delimited(char(open), parse_alternatives, ws(char(close))),
+            Expr::OneOf,
+        )
+    };
+    let atom = alt((
+        map(parse_terminal, Expr::Terminal),
+        nonterminal,
+        group('(', ')'),
+        map(group('{', '}'), |expr| Expr::Repeat(Box::new(expr))),
+        map(group('[', ']'), |expr| Expr::Optional(Box::new(expr))),
+    ));
+    map(
+        pair(atom, opt(one_of("*+?"))),
+        |(atom, operator)| match operator {
+            Some('*') => Expr::Repeat(Box::new(atom)),
+            Some('+') => Expr::RepeatAtLeastOnce(Box::new(atom)),
+            Some('?') => Expr::Optional(Box::new(atom)),
+            _ => atom,
+        },
+    )(input)
+}
+
+fn parse_alternatives(input: &str) -> IResult<&str, Alternatives> {
+    separated_list1(ws(char('|')), many0(ws(parse_item)))(input)
+}
+
+fn parse_definitions(input: &str) -> IResult<&str, Vec<(&str, Alternatives)>> {
+    terminated(
+        many1(ws(separated_pair(
+            parse_name,
+            tuple((multispace0, tag("::="))),
+            parse_alternatives,
+        ))),
+        multispace0,
+    )(input)
+}
+
+// Assigns rule numbers and creates additional rules for groups, repetitions,
+// and optional parts, so that every rule is a choice between sequences of
+// terminals and references like in the numeric format.
+struct Lowering {
+    ids: HashMap<String, usize>,
+    rules: HashMap<usize, ProductionRule>,
+    next_id: usize,
+}
+
+impl Lowering {
+    fn add_rule(&mut self, rule: ProductionRule) -> ProductionRule {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.rules.insert(id, rule);
+        ProductionRule::Ref(id)
+    }
+
+    fn lower_alternatives(
+        &mut self,
+        alternatives: &[Vec<Expr>],
+    ) -> Result<Vec<ProductionRule>, GrammarParseError> {
+        alternatives
+            .iter()
+            .map(|sequence| {
+                Ok(ProductionRule::Sequence(
+                    sequence
+                        .iter()
+                        .map(|expr| self.lower(expr))
+                        .collect::<Result<_, _>>()?,
+                ))
+            })
+            .collect()
+    }
+
+    fn lower(&mut self, expr: &Expr) -> Result<ProductionRule, GrammarParseError> {
+        use ProductionRule::*;
+        match expr {
+            Expr::Terminal(terminal) => Ok(Terminal(terminal.clone())),
+            Expr::Nonterminal(name) => self
+                .ids
+                .get(name)
+                .map(|&id| Ref(id))
+                .ok_or_else(|| GrammarParseError::UndefinedNonterminal(name.clone())),
+            Expr::OneOf(alternatives) => {
+                let alternatives = self.lower_alternatives(alternatives)?;
+                Ok(self.add_rule(OneOf(alternatives)))
+            }
+            Expr::Repeat(inner) | Expr::RepeatAtLeastOnce(inner) => {
+                let item = self.lower(inner)?;
+                let id = self.next_id;
+                self.next_id += 1;
+                let base_case = match expr {
+                    Expr::Repeat(_) => vec![],
+                    _ => vec![item.clone()],
+                };
+                self.rules.insert(
+                    id,
+                    OneOf(vec![Sequence(vec![item, Ref(id)]), Sequence(base_case)]),
+                );
+                Ok(Ref(id))
+            }
+            Expr::Optional(expr) => {
+                let item = self.lower(expr)?;
+                Ok(self.add_rule(OneOf(vec![Sequence(vec![item]), Sequence(vec![])])))
+            }
+        }
+    }
+}
+
+impl Grammar {
+    // Parses a grammar in BNF like `<a> ::= "x" <b> | "y"`, where nonterminals
+    // may also be written without angle brackets. Supported EBNF extensions
+    // are grouping with parentheses, repetition with `{ }` or the postfix
+    // operators `*` and `+`, and optional parts with `[ ]` or `?`. The first
+    // definition is the root. Returns the numbers assigned to the
+    // nonterminals along with the grammar.
+    pub fn from_bnf(input: &str) -> Result<(Self, HashMap<String, usize>), GrammarParseError> {
+        let definitions = parse_full(parse_definitions, input)?;
+        let mut lowering = Lowering {
+            ids: HashMap::new(),
+            rules: HashMap::new(),
+            next_id: 0,
+        };
+        for (name, _) in &definitions {
+            if !lowering.ids.contains_key(*name) {
+                lowering.ids.insert(String::from(*name), lowering.next_id);
+                lowering.next_id += 1;
+            }
+        }
+        let mut bodies: HashMap<usize, Vec<ProductionRule>> = HashMap::new();
+        // Repeated definitions of a nonterminal add further alternatives.
+        for (name, alternatives) in &definitions {
+            let lowered = lowering.lower_alternatives(alternatives)?;
+            bodies
+                .entry(lowering.ids[*name])
+                .or_default()
+                .extend(lowered);
+        }
+        for (id, alternatives) in bodies {
+            lowering
+                .rules
+                .insert(id, ProductionRule::OneOf(alternatives));
+        }
+        Ok((
+            Self {
+                rules: lowering.rules,
+                root: 0,
+            },
+            lowering.ids,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ProductionRule::*;
+
+    #[test]
+    fn test_parse_definitions() {
+        let (_, definitions) =
+            parse_definitions("<a> ::= \"x\" b | 'y'\n  | \nb ::= (\"z\")* [a]\n").unwrap();
+        assert_eq!(
+            definitions,
+            vec![
+                (
+                    "a",
+                    vec![
+                        vec![
+                            Expr::Terminal(String::from("x")),
+                            Expr::Nonterminal(String::from("b"))
+                        ],
+                        vec![Expr::Terminal(String::from("y"))],
+                        vec![],
+                    ]
+                ),
+                (
+                    "b",
+                    vec![vec![
+                        Expr::Repeat(Box::new(Expr::OneOf(vec![vec![Expr::Terminal(
+                            String::from("z")
+                        )]]))),
+                        Expr::Optional(Box::new(Expr::OneOf(vec![vec![Expr::Nonterminal(
+                            String::from("a")
+                        )]]))),
+                    ]]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_bnf() {
+        let (grammar, ids) = Grammar::from_bnf("<s> ::= <t> \"b\"\n<t> ::= \"a\"+").unwrap();
+        assert_eq!(ids["s"], 0);
+        assert_eq!(ids["t"], 1);
+        assert_eq!(
+            grammar.rule(0),
+            Some(&OneOf(vec![Sequence(vec![
+                Ref(1),
+                Terminal(String::from("b"))
+            ])]))
+        );
+        assert_eq!(
+            grammar.rule(2),
+            Some(&OneOf(vec![
+                Sequence(vec![Terminal(String::from("a")), Ref(2)]),
+                Sequence(vec![Terminal(String::from("a"))]),
+            ]))
+        );
+        for (input, expected) in &[("ab", true), ("aaab", true), ("b", false), ("aba", false)] {
+            assert_eq!(grammar.accepts(input), *expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_from_bnf_ebnf_operators() {
+        let (grammar, _) = Grammar::from_bnf(
+            "
+            list ::= '[' [ item { ',' item } ] ']'
+            item ::= digit+ ('.' digit+)? | list
+            digit ::= '0' | '1' | '2'
+            ",
+        )
+        .unwrap();
+        for (input, expected) in &[
+            ("[]", true),
+            ("[1]", true),
+            ("[10,2.12,[]]", true),
+            ("[1,]", false),
+            ("[1.]", false),
+            ("[3]", false),
+        ] {
+            assert_eq!(grammar.accepts(input), *expected, "{}", input);
+            assert_eq!(
+                EarleyParser::new(&grammar).accepts(input),
+                *expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_bnf_errors() {
+        assert!(matches!(
+            Grammar::from_bnf("a ::= b"),
+            Err(GrammarParseError::UndefinedNonterminal(name)) if name == "b"
+        ));
+        assert!(Grammar::from_bnf("a ::= 'x' )").is_err());
+        assert!(Grammar::from_bnf("a = 'x'").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use super::Grammar;
+use std::error::Error;
+use std::fs;
+
+// Command line arguments shared by the programs checking messages against a
+// grammar.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub overrides: Vec<String>,
+    pub root: Option<usize>,
+    pub n_threads: Option<usize>,
+}
+
+// Rule overrides are given as `--override "8: 42 8 | 42"` or read line by line
+// from a file with `--overrides path`. Both can be repeated. `--root N` checks
+// the messages against rule N instead of 0. `--threads N` sets the number of
+// threads with the parallel feature.
+pub fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, Box<dyn Error>> {
+    let mut args = args;
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        if !parse_arg(&mut parsed, &arg, &mut args)? {
+            return Err(format!("unknown argument '{}'", arg).into());
+        }
+    }
+    Ok(parsed)
+}
+
+// Parses a single argument of parse_args, taking its value from args, so that
+// programs can accept further arguments. Returns whether the argument is
+// known.
+pub fn parse_arg(
+    parsed: &mut Args,
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<bool, Box<dyn Error>> {
+    match arg {
+        "--root" => {
+            parsed.root = Some(
+                args.next()
+                    .ok_or("--root requires a rule number")?
+                    .parse()?,
+            )
+        }
+        "--threads" => {
+            parsed.n_threads = Some(args.next().ok_or("--threads requires a number")?.parse()?)
+        }
+        "--override" => parsed
+            .overrides
+            .push(args.next().ok_or("--override requires a rule")?),
+        "--overrides" => {
+            let path = args.next().ok_or("--overrides requires a file")?;
+            parsed.overrides.extend(
+                fs::read_to_string(path)?
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(String::from),
+            );
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+// Checks the messages against another rule than 0 if requested.
+pub fn select_root(grammar: Grammar, root: Option<usize>) -> Result<Grammar, Box<dyn Error>> {
+    match root {
+        Some(root) if grammar.rule(root).is_none() => {
+            Err(format!("root rule {} is not defined", root).into())
+        }
+        Some(root) => Ok(grammar.with_root(root)),
+        None => Ok(grammar),
+    }
+}
+
+#[cfg(feature = "parallel")]
+pub fn set_num_threads(n_threads: usize) -> Result<(), Box<dyn Error>> {
+    Ok(super::set_num_threads(n_threads)?)
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn set_num_threads(_n_threads: usize) -> Result<(), Box<dyn Error>> {
+    Err("--threads requires the parallel feature".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|&arg| String::from(arg))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(args(&[
+                "--override",
+                "8: 42 8 | 42",
+                "--threads",
+                "4",
+                "--override",
+                "0: 8",
+                "--root",
+                "42",
+            ]))
+            .unwrap(),
+            Args {
+                overrides: vec![String::from("8: 42 8 | 42"), String::from("0: 8")],
+                root: Some(42),
+                n_threads: Some(4),
+            }
+        );
+        assert!(parse_args(args(&["--override"])).is_err());
+        assert!(parse_args(args(&["--root"])).is_err());
+        assert!(parse_args(args(&["--threads", "x"])).is_err());
+        assert!(parse_args(args(&["--derivations"])).is_err());
+    }
+
+    #[test]
+    fn test_select_root() {
+        let grammar = || Grammar::parse(&mut ["0: 1 1", "1: \"a\""].iter()).unwrap();
+        assert!(select_root(grammar(), None).is_ok());
+        assert!(select_root(grammar(), Some(1)).is_ok());
+        assert!(select_root(grammar(), Some(2)).is_err());
+    }
+}
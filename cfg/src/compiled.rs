@@ -0,0 +1,217 @@
+use super::{Grammar, ProductionRule};
+use regex_automata::{dense, DenseDFA, DFA};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, PartialEq)]
+pub enum CompileError {
+    RecursiveRule(usize),
+    Automaton(String),
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::RecursiveRule(id) => write!(f, "Rule {} is recursive.", id),
+            Self::Automaton(err) => write!(f, "Cannot build automaton: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+// Matches no input at all, used for references to missing rules. The byte
+// 0xFF never occurs in UTF-8 and empty classes are not supported.
+const NOTHING: &str = r"(?-u:\xFF)";
+
+struct RegexBuilder<'a> {
+    grammar: &'a Grammar,
+    patterns: HashMap<usize, String>,
+    in_progress: HashSet<usize>,
+}
+
+impl<'a> RegexBuilder<'a> {
+    fn rule_pattern(&mut self, id: usize) -> Result<String, CompileError> {
+        if let Some(pattern) = self.patterns.get(&id) {
+            return Ok(pattern.clone());
+        }
+        if !self.in_progress.insert(id) {
+            return Err(CompileError::RecursiveRule(id));
+        }
+        let pattern = match self.grammar.rules.get(&id) {
+            Some(rule) => self.pattern(rule)?,
+            None => String::from(NOTHING),
+        };
+        self.in_progress.remove(&id);
+        self.patterns.insert(id, pattern.clone());
+        Ok(pattern)
+    }
+
+    fn pattern(&mut self, rule: &ProductionRule) -> Result<String, CompileError> {
+        use ProductionRule::*;
+        Ok(match rule {
+            Terminal(terminal) => regex_syntax::escape(terminal),
+            Class(ranges) => format!(
+                "[{}]",
+                ranges
+                    .iter()
+                    .map(|&(lb, ub)| if lb == ub {
+                        regex_syntax::escape(&lb.to_string())
+                    } else {
+                        format!(
+                            "{}-{}",
+                            regex_syntax::escape(&lb.to_string()),
+                            regex_syntax::escape(&ub.to_string())
+                        )
+                    })
+                    .collect::<String>()
+            ),
+            AnyChar => String::from("(?s:.)"),
+            Sequence(children) => children
+                .iter()
+                .map(|child| self.pattern(child))
+                .collect::<Result<Vec<_>, _>>()?
+                .concat(),
+            OneOf(children) if children.len() == 1 => self.pattern(&children[0])?,
+            OneOf(children) => format!(
+                "(?:{})",
+                children
+                    .iter()
+                    .map(|child| self.pattern(child))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join("|")
+            ),
+            Ref(id) => self.rule_pattern(*id)?,
+        })
+    }
+}
+
+// A grammar without recursion describes a regular language and can be matched
+// by a deterministic finite automaton in a single pass over the input.
+pub struct CompiledGrammar {
+    dfa: DenseDFA<Vec<usize>, usize>,
+}
+
+impl CompiledGrammar {
+    pub fn accepts(&self, input: &str) -> bool {
+        let mut state = self.dfa.start_state();
+        for &byte in input.as_bytes() {
+            state = self.dfa.next_state(state, byte);
+            if self.dfa.is_dead_state(state) {
+                return false;
+            }
+        }
+        self.dfa.is_match_state(state)
+    }
+}
+
+impl Grammar {
+    // The returned pattern matches exactly the strings the grammar accepts, but
+    // is not anchored.
+    pub fn to_regex(&self) -> Result<String, CompileError> {
+        RegexBuilder {
+            grammar: self,
+            patterns: HashMap::new(),
+            in_progress: HashSet::new(),
+        }
+        .rule_pattern(self.root)
+    }
+
+    // Longest match semantics keep the automaton going after an alternative
+    // matched a prefix of the input, so that `accepts` sees whether the whole
+    // input matches. The default leftmost-first semantics stop at the first
+    // alternative that matches.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn to_dfa(&self) -> Result<CompiledGrammar, CompileError> {
+        let dfa = dense::Builder::new()
+            .anchored(true)
+            .longest_match(true)
+            .allow_invalid_utf8(true)
+            .build(&self.to_regex()?)
+            .map_err(|err| CompileError::Automaton(err.to_string()))?;
+        Ok(CompiledGrammar { dfa })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(lines: &[&str]) -> Grammar {
+        Grammar::parse(&mut lines.iter()).unwrap()
+    }
+
+    #[test]
+    fn test_to_regex() {
+        let grammar = grammar(&["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""]);
+        assert_eq!(grammar.to_regex().unwrap(), "a(?:ab|ba)");
+    }
+
+    #[test]
+    fn test_to_regex_recursive() {
+        let grammar = grammar(&["0: 1", "1: \"a\" | \"a\" 2", "2: 1"]);
+        assert_eq!(grammar.to_regex(), Err(CompileError::RecursiveRule(1)));
+        assert!(grammar.to_dfa().is_err());
+    }
+
+    #[test]
+    fn test_compiled_accepts() {
+        let grammar = grammar(&[
+            "0: 4 1 5",
+            "1: 2 3 | 3 2",
+            "2: 4 4 | 5 5",
+            "3: 4 5 | 5 4",
+            "4: \"a\"",
+            "5: \"b\"",
+        ]);
+        let compiled = grammar.to_dfa().unwrap();
+        for input in &["ababbb", "bababa", "abbbab", "aaabbb", "aaaabbb", "", "a"] {
+            assert_eq!(compiled.accepts(input), grammar.accepts(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_compiled_alternative_is_prefix() {
+        for lines in &[
+            &["0: \"a\" | \"a\" \"b\""][..],
+            &["0: 1 2", "1: \"a\" | \"a\" \"b\"", "2: \"b\" | \"c\""][..],
+        ] {
+            let grammar = grammar(lines);
+            let compiled = grammar.to_dfa().unwrap();
+            for input in &["a", "ab", "abb", "abc", "ac", "abbb"] {
+                assert_eq!(compiled.accepts(input), grammar.accepts(input), "{}", input);
+            }
+        }
+        assert!(grammar(&["0: \"a\" | \"a\" \"b\""])
+            .to_dfa()
+            .unwrap()
+            .accepts("ab"));
+    }
+
+    #[test]
+    fn test_compiled_missing_rule() {
+        let compiled = grammar(&["0: 1 | 2", "2: \"b\""]).to_dfa().unwrap();
+        assert!(compiled.accepts("b"));
+        assert!(!compiled.accepts(""));
+        assert!(!compiled.accepts("bb"));
+    }
+
+    #[test]
+    fn test_compiled_terminals() {
+        let grammar = grammar(&[r#"0: "a.b" [0-9\-] . 1"#, r#"1: "\n" | [x-z]"#]);
+        assert_eq!(
+            grammar.to_regex().unwrap(),
+            "a\\.b[0-9\\-](?s:.)(?:\n|[x-z])"
+        );
+        let compiled = grammar.to_dfa().unwrap();
+        for input in &["a.b1!\n", "a.b-äy", "a.b1!", "axb1!y", "a.b1\n\n"] {
+            assert_eq!(
+                compiled.accepts(input),
+                grammar.accepts(input),
+                "{:?}",
+                input
+            );
+        }
+        assert!(compiled.accepts("a.b-äy"));
+    }
+}
@@ -0,0 +1,305 @@
+use super::{Grammar, ProductionRule};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseTree {
+    Terminal(String),
+    Rule(usize, Vec<ParseTree>),
+}
+
+impl Display for ParseTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Terminal(terminal) => write!(f, "{:?}", terminal),
+            Self::Rule(id, children) => {
+                write!(f, "({}", id)?;
+                for child in children {
+                    write!(f, " {}", child)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DerivationError {
+    LeftRecursiveRule(usize),
+}
+
+impl Display for DerivationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::LeftRecursiveRule(id) => write!(f, "Rule {} is left-recursive.", id),
+        }
+    }
+}
+
+impl std::error::Error for DerivationError {}
+
+impl ProductionRule {
+    // Whether the rule can match the empty input, given the rules known to do
+    // so.
+    fn is_nullable(&self, nullable: &HashSet<usize>) -> bool {
+        use ProductionRule::*;
+        match self {
+            Terminal(terminal) => terminal.is_empty(),
+            Class(_) | AnyChar => false,
+            OneOf(children) => children.iter().any(|child| child.is_nullable(nullable)),
+            Sequence(children) => children.iter().all(|child| child.is_nullable(nullable)),
+            Ref(id) => nullable.contains(id),
+        }
+    }
+
+    // Pushes the rules that are referenced before any input is matched.
+    fn leftmost_refs(&self, nullable: &HashSet<usize>, refs: &mut Vec<usize>) {
+        use ProductionRule::*;
+        match self {
+            Terminal(_) | Class(_) | AnyChar => {}
+            OneOf(children) => {
+                for child in children {
+                    child.leftmost_refs(nullable, refs);
+                }
+            }
+            Sequence(children) => {
+                for child in children {
+                    child.leftmost_refs(nullable, refs);
+                    if !child.is_nullable(nullable) {
+                        break;
+                    }
+                }
+            }
+            Ref(id) => refs.push(*id),
+        }
+    }
+
+    fn refs(&self, refs: &mut Vec<usize>) {
+        use ProductionRule::*;
+        match self {
+            Terminal(_) | Class(_) | AnyChar => {}
+            OneOf(children) | Sequence(children) => {
+                for child in children {
+                    child.refs(refs);
+                }
+            }
+            Ref(id) => refs.push(*id),
+        }
+    }
+}
+
+impl Grammar {
+    // Rules that can match the empty input, iterated to a fixed point.
+    fn nullable_rules(&self) -> HashSet<usize> {
+        let mut nullable = HashSet::new();
+        loop {
+            let n_nullable = nullable.len();
+            for (&id, rule) in &self.rules {
+                if rule.is_nullable(&nullable) {
+                    nullable.insert(id);
+                }
+            }
+            if nullable.len() == n_nullable {
+                return nullable;
+            }
+        }
+    }
+
+    // Rules used when matching the root rule, in the order they are found.
+    fn reachable_rules(&self) -> Vec<usize> {
+        let mut reachable = vec![self.root];
+        let mut i = 0;
+        while let Some(&id) = reachable.get(i) {
+            let mut refs = vec![];
+            if let Some(rule) = self.rules.get(&id) {
+                rule.refs(&mut refs);
+            }
+            for id in refs {
+                if !reachable.contains(&id) {
+                    reachable.push(id);
+                }
+            }
+            i += 1;
+        }
+        reachable
+    }
+
+    fn find_left_cycle(
+        &self,
+        id: usize,
+        nullable: &HashSet<usize>,
+        in_progress: &mut HashSet<usize>,
+        finished: &mut HashSet<usize>,
+    ) -> Option<usize> {
+        if finished.contains(&id) {
+            return None;
+        }
+        if !in_progress.insert(id) {
+            return Some(id);
+        }
+        let mut refs = vec![];
+        if let Some(rule) = self.rules.get(&id) {
+            rule.leftmost_refs(nullable, &mut refs);
+        }
+        let cycle = refs
+            .into_iter()
+            .find_map(|next| self.find_left_cycle(next, nullable, in_progress, finished));
+        in_progress.remove(&id);
+        finished.insert(id);
+        cycle
+    }
+
+    // A rule used by the root rule that can reference itself before matching
+    // any input, directly or through other rules. Derivations of such a
+    // grammar would never end.
+    pub fn left_recursive_rule(&self) -> Option<usize> {
+        let nullable = self.nullable_rules();
+        let mut finished = HashSet::new();
+        self.reachable_rules()
+            .into_iter()
+            .find_map(|id| self.find_left_cycle(id, &nullable, &mut HashSet::new(), &mut finished))
+    }
+
+    // Returns every way the rule can match a prefix of the input as the
+    // derived subtrees together with the remaining input.
+    fn derive<'a>(&self, rule: &ProductionRule, input: &'a str) -> Vec<(Vec<ParseTree>, &'a str)> {
+        use ProductionRule::*;
+        match rule {
+            Terminal(_) | Class(_) | AnyChar => match rule.match_terminal(input) {
+                Some(n) => vec![(vec![ParseTree::Terminal(input[..n].into())], &input[n..])],
+                None => vec![],
+            },
+            Sequence(children) => children
+                .iter()
+                .fold(vec![(vec![], input)], |partials, child| {
+                    partials
+                        .into_iter()
+                        .flat_map(|(trees, remainder)| {
+                            self.derive(child, remainder).into_iter().map(
+                                move |(child_trees, remainder)| {
+                                    let mut trees = trees.clone();
+                                    trees.extend(child_trees);
+                                    (trees, remainder)
+                                },
+                            )
+                        })
+                        .collect()
+                }),
+            OneOf(children) => children
+                .iter()
+                .flat_map(|child| self.derive(child, input))
+                .collect(),
+            Ref(id) => match self.rules.get(id) {
+                Some(rule) => self
+                    .derive(rule, input)
+                    .into_iter()
+                    .map(|(trees, remainder)| (vec![ParseTree::Rule(*id, trees)], remainder))
+                    .collect(),
+                None => vec![],
+            },
+        }
+    }
+
+    // All derivations of the whole input from the root rule. Left-recursive
+    // grammars are refused, as their derivations would never end. Every
+    // derivation is listed, so highly ambiguous input takes exponential time.
+    pub fn derivations(&self, input: &str) -> Result<Vec<ParseTree>, DerivationError> {
+        if let Some(id) = self.left_recursive_rule() {
+            return Err(DerivationError::LeftRecursiveRule(id));
+        }
+        Ok(self
+            .derive(&ProductionRule::Ref(self.root), input)
+            .into_iter()
+            .filter(|(_, remainder)| remainder.is_empty())
+            .flat_map(|(trees, _)| trees)
+            .collect())
+    }
+
+    pub fn is_ambiguous(&self, input: &str) -> Result<bool, DerivationError> {
+        Ok(self.derivations(input)?.len() > 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(lines: &[&str]) -> Grammar {
+        Grammar::parse(&mut lines.iter()).unwrap()
+    }
+
+    #[test]
+    fn test_derivations() {
+        let grammar = grammar(&["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""]);
+        assert_eq!(
+            grammar.derivations("aba"),
+            Ok(vec![ParseTree::Rule(
+                0,
+                vec![
+                    ParseTree::Rule(1, vec![ParseTree::Terminal("a".into())]),
+                    ParseTree::Rule(
+                        2,
+                        vec![
+                            ParseTree::Rule(3, vec![ParseTree::Terminal("b".into())]),
+                            ParseTree::Rule(1, vec![ParseTree::Terminal("a".into())]),
+                        ]
+                    ),
+                ]
+            )])
+        );
+        assert_eq!(grammar.derivations("abb"), Ok(vec![]));
+        assert_eq!(grammar.is_ambiguous("aba"), Ok(false));
+    }
+
+    #[test]
+    fn test_derivations_of_classes() {
+        let grammar = grammar(&["0: [a-c] ."]);
+        assert_eq!(
+            grammar.derivations("bä"),
+            Ok(vec![ParseTree::Rule(
+                0,
+                vec![
+                    ParseTree::Terminal("b".into()),
+                    ParseTree::Terminal("ä".into())
+                ]
+            )])
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_split_point() {
+        let grammar = grammar(&["0: 1 1", "1: 2 | 2 2", "2: \"a\""]);
+        assert_eq!(grammar.is_ambiguous("aa"), Ok(false));
+        assert_eq!(grammar.is_ambiguous("aaa"), Ok(true));
+        let derivations: Vec<String> = grammar
+            .derivations("aaa")
+            .unwrap()
+            .iter()
+            .map(ParseTree::to_string)
+            .collect();
+        assert_eq!(
+            derivations,
+            vec![
+                r#"(0 (1 (2 "a")) (1 (2 "a") (2 "a")))"#,
+                r#"(0 (1 (2 "a") (2 "a")) (1 (2 "a")))"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_left_recursive_rule() {
+        let left_recursive = grammar(&["0: 1 2", "1: \"a\"", "2: 1 | 2 1"]);
+        assert_eq!(left_recursive.left_recursive_rule(), Some(2));
+        assert_eq!(
+            left_recursive.derivations("aa"),
+            Err(DerivationError::LeftRecursiveRule(2))
+        );
+
+        let through_empty_rule = grammar(&["0: 1 0 2 | 2", "1: \"\"", "2: \"a\""]);
+        assert_eq!(through_empty_rule.left_recursive_rule(), Some(0));
+
+        let right_recursive = grammar(&["0: 1 | 1 0", "1: \"a\"", "2: 2 1"]);
+        assert_eq!(right_recursive.left_recursive_rule(), None);
+        assert_eq!(right_recursive.derivations("aa").map(|d| d.len()), Ok(1));
+    }
+}
@@ -0,0 +1,253 @@
+use super::{Grammar, ProductionRule};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Symbol {
+    Terminal(ProductionRule),
+    NonTerminal(usize),
+}
+
+// A grammar flattened into productions of plain symbol sequences. Nested
+// alternatives and sequences are replaced by fresh non-terminals numbered
+// after the largest rule id.
+#[derive(Debug)]
+pub struct EarleyParser {
+    productions: Vec<(usize, Vec<Symbol>)>,
+    by_lhs: HashMap<usize, Vec<usize>>,
+    nullable: HashSet<usize>,
+    root: usize,
+}
+
+// Production index, position of the dot within the production, and input
+// position at which the production was predicted.
+type Item = (usize, usize, usize);
+
+impl EarleyParser {
+    pub fn new(grammar: &Grammar) -> Self {
+        let mut parser = Self {
+            productions: vec![],
+            by_lhs: HashMap::new(),
+            nullable: HashSet::new(),
+            root: grammar.root,
+        };
+        let mut next_id = grammar.rules.keys().max().map_or(0, |id| id + 1);
+        for (&id, rule) in &grammar.rules {
+            for alternative in parser.alternatives(rule, &mut next_id) {
+                parser.add_production(id, alternative);
+            }
+        }
+        parser.nullable = parser.find_nullable();
+        parser
+    }
+
+    fn add_production(&mut self, lhs: usize, symbols: Vec<Symbol>) {
+        self.by_lhs
+            .entry(lhs)
+            .or_default()
+            .push(self.productions.len());
+        self.productions.push((lhs, symbols));
+    }
+
+    fn alternatives(&mut self, rule: &ProductionRule, next_id: &mut usize) -> Vec<Vec<Symbol>> {
+        use ProductionRule::*;
+        match rule {
+            OneOf(children) => children
+                .iter()
+                .flat_map(|child| self.alternatives(child, next_id))
+                .collect(),
+            Sequence(children) => vec![children
+                .iter()
+                .map(|child| self.symbol(child, next_id))
+                .collect()],
+            Terminal(_) | Class(_) | AnyChar | Ref(_) => vec![vec![self.symbol(rule, next_id)]],
+        }
+    }
+
+    fn symbol(&mut self, rule: &ProductionRule, next_id: &mut usize) -> Symbol {
+        match rule {
+            rule if rule.is_terminal() => Symbol::Terminal(rule.clone()),
+            ProductionRule::Ref(id) => Symbol::NonTerminal(*id),
+            _ => {
+                let id = *next_id;
+                *next_id += 1;
+                for alternative in self.alternatives(rule, next_id) {
+                    self.add_production(id, alternative);
+                }
+                Symbol::NonTerminal(id)
+            }
+        }
+    }
+
+    fn find_nullable(&self) -> HashSet<usize> {
+        let mut nullable = HashSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (lhs, symbols) in &self.productions {
+                if !nullable.contains(lhs)
+                    && symbols.iter().all(|symbol| match symbol {
+                        Symbol::Terminal(terminal) => terminal.match_terminal("") == Some(0),
+                        Symbol::NonTerminal(id) => nullable.contains(id),
+                    })
+                {
+                    nullable.insert(*lhs);
+                    changed = true;
+                }
+            }
+        }
+        nullable
+    }
+
+    // Earley sets are indexed by byte offset, so that terminals of any length
+    // can be scanned. Nullable non-terminals are stepped over right away when
+    // predicted (Aycock & Horspool), which makes completion of empty
+    // productions within the same set unnecessary.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn accepts(&self, input: &str) -> bool {
+        let n = input.len();
+        let mut sets: Vec<Vec<Item>> = vec![vec![]; n + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); n + 1];
+        let mut add = |sets: &mut Vec<Vec<Item>>, position: usize, item: Item| {
+            if seen[position].insert(item) {
+                sets[position].push(item);
+            }
+        };
+
+        for &production in self.by_lhs.get(&self.root).into_iter().flatten() {
+            add(&mut sets, 0, (production, 0, 0));
+        }
+        for position in 0..=n {
+            let mut i = 0;
+            while i < sets[position].len() {
+                let (production, dot, origin) = sets[position][i];
+                i += 1;
+                let (lhs, symbols) = &self.productions[production];
+                match symbols.get(dot) {
+                    Some(Symbol::NonTerminal(id)) => {
+                        for &predicted in self.by_lhs.get(id).into_iter().flatten() {
+                            add(&mut sets, position, (predicted, 0, position));
+                        }
+                        if self.nullable.contains(id) {
+                            add(&mut sets, position, (production, dot + 1, origin));
+                        }
+                    }
+                    Some(Symbol::Terminal(terminal)) => {
+                        if let Some(n) = terminal.match_terminal(&input[position..]) {
+                            add(&mut sets, position + n, (production, dot + 1, origin));
+                        }
+                    }
+                    None => {
+                        let mut j = 0;
+                        while j < sets[origin].len() {
+                            let (waiting, waiting_dot, waiting_origin) = sets[origin][j];
+                            j += 1;
+                            if self.productions[waiting].1.get(waiting_dot)
+                                == Some(&Symbol::NonTerminal(*lhs))
+                            {
+                                add(
+                                    &mut sets,
+                                    position,
+                                    (waiting, waiting_dot + 1, waiting_origin),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        sets[n].iter().any(|&(production, dot, origin)| {
+            let (lhs, symbols) = &self.productions[production];
+            origin == 0 && *lhs == self.root && dot == symbols.len()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn parser(lines: &[&str]) -> EarleyParser {
+        EarleyParser::new(&Grammar::parse(&mut lines.iter()).unwrap())
+    }
+
+    #[test]
+    fn test_accepts_non_recursive_grammar() {
+        let parser = parser(&["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""]);
+        assert!(parser.accepts("aab"));
+        assert!(parser.accepts("aba"));
+        assert!(!parser.accepts("aaa"));
+        assert!(!parser.accepts("ab"));
+        assert!(!parser.accepts("abab"));
+    }
+
+    #[test]
+    fn test_accepts_left_and_right_recursion() {
+        for rule in &["0: 1 | 0 1", "0: 1 | 1 0"] {
+            let parser = parser(&[rule, "1: \"a\""]);
+            assert!(parser.accepts("a"));
+            assert!(parser.accepts("aaaaaaa"));
+            assert!(!parser.accepts(""));
+            assert!(!parser.accepts("aab"));
+        }
+    }
+
+    #[test]
+    fn test_accepts_balanced() {
+        let parser = parser(&["0: 1 0 2 | 1 2", "1: \"a\"", "2: \"b\""]);
+        assert!(parser.accepts("ab"));
+        assert!(parser.accepts("aaabbb"));
+        assert!(!parser.accepts("aabbb"));
+        assert!(!parser.accepts("abab"));
+    }
+
+    #[test]
+    fn test_accepts_ambiguous_grammar() {
+        let parser = parser(&["0: 0 0 | 1", "1: \"a\""]);
+        assert!(parser.accepts("aaaaa"));
+        assert!(!parser.accepts("aaba"));
+    }
+
+    #[test]
+    fn test_accepts_nullable_and_nested_rules() {
+        let mut grammar = Grammar::parse(&mut ["0: 1 2 1", "2: \"b\""].iter()).unwrap();
+        grammar.set_rule(
+            1,
+            ProductionRule::OneOf(vec![
+                ProductionRule::Sequence(vec![]),
+                ProductionRule::Sequence(vec![
+                    ProductionRule::Terminal("a".into()),
+                    ProductionRule::OneOf(vec![
+                        ProductionRule::Terminal("c".into()),
+                        ProductionRule::Sequence(vec![]),
+                    ]),
+                ]),
+            ]),
+        );
+        let parser = EarleyParser::new(&grammar);
+        for accepted in &["b", "ab", "ba", "acbac", "acb"] {
+            assert!(parser.accepts(accepted), "{}", accepted);
+        }
+        for rejected in &["", "c", "aab", "bb"] {
+            assert!(!parser.accepts(rejected), "{}", rejected);
+        }
+    }
+
+    #[test]
+    fn test_missing_rule_matches_nothing() {
+        let mut grammar = Grammar::parse(&mut ["0: 1 | 2", "2: \"b\""].iter()).unwrap();
+        assert!(EarleyParser::new(&grammar).accepts("b"));
+        grammar.set_rule(2, ProductionRule::try_from("3").unwrap());
+        assert!(!EarleyParser::new(&grammar).accepts("b"));
+    }
+
+    #[test]
+    fn test_accepts_terminals() {
+        let parser = parser(&[r#"0: "ab" [0-9] . 1"#, r#"1: "\n" | [x-z] 1"#]);
+        assert!(parser.accepts("ab1!\n"));
+        assert!(parser.accepts("ab9äyzx\n"));
+        assert!(!parser.accepts("ab1!"));
+        assert!(!parser.accepts("abx!\n"));
+    }
+}
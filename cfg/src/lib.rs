@@ -0,0 +1,599 @@
+use nom::{
+    branch::alt,
+    character::complete::{anychar, char, digit1, none_of, space0, space1},
+    combinator::map,
+    combinator::{map_res, opt, value, verify},
+    error::ErrorKind,
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, tuple},
+    IResult,
+};
+use parsing::{parse_full, Parsable};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+mod bnf;
+pub mod cli;
+mod compiled;
+mod derivation;
+mod earley;
+
+pub use compiled::{CompileError, CompiledGrammar};
+pub use derivation::{DerivationError, ParseTree};
+pub use earley::EarleyParser;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProductionRule {
+    Terminal(String),
+    // Inclusive character ranges, matching a single character.
+    Class(Vec<(char, char)>),
+    AnyChar,
+    OneOf(Vec<ProductionRule>),
+    Sequence(Vec<ProductionRule>),
+    Ref(usize),
+}
+
+// Possible remainders after matching a rule, keyed by the rule number and
+// the length of the remaining input. Within a single input, the length
+// identifies the remaining suffix. Every rule reference looks up the memo,
+// the lookups that had to insert an entry are the misses.
+#[derive(Default)]
+struct Memo<'a> {
+    remainders: HashMap<(usize, usize), Vec<&'a str>>,
+    lookups: usize,
+    // Entries still being computed and whether they have been looked up in
+    // the meantime, which only happens for left recursion.
+    in_progress: HashMap<(usize, usize), bool>,
+    // Keys in the order of insertion, to find the entries computed while
+    // another entry was in progress.
+    inserted: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Grammar {
+    rules: HashMap<usize, ProductionRule>,
+    root: usize,
+}
+
+impl ProductionRule {
+    // Number of bytes matched at the start of the input by a terminal, class,
+    // or wildcard. Returns `None` for any other kind of rule.
+    fn match_terminal(&self, input: &str) -> Option<usize> {
+        match self {
+            Self::Terminal(terminal) if input.starts_with(terminal.as_str()) => {
+                Some(terminal.len())
+            }
+            Self::Class(ranges) => input
+                .chars()
+                .next()
+                .filter(|c| ranges.iter().any(|(lb, ub)| (lb..=ub).contains(&c)))
+                .map(char::len_utf8),
+            Self::AnyChar => input.chars().next().map(char::len_utf8),
+            _ => None,
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        matches!(self, Self::Terminal(_) | Self::Class(_) | Self::AnyChar)
+    }
+}
+
+// Escapes are a backslash followed by any character, `\n` and `\t` denote a
+// newline and a tab.
+fn parse_escaped_char(input: &str) -> IResult<&str, char> {
+    preceded(
+        char('\\'),
+        alt((value('\n', char('n')), value('\t', char('t')), anychar)),
+    )(input)
+}
+
+fn parse_class_range(input: &str) -> IResult<&str, (char, char)> {
+    let class_char = |input| alt((parse_escaped_char, none_of("]-\\")))(input);
+    verify(
+        map(
+            pair(class_char, opt(preceded(char('-'), class_char))),
+            |(lb, ub)| (lb, ub.unwrap_or(lb)),
+        ),
+        |(lb, ub)| lb <= ub,
+    )(input)
+}
+
+impl Parsable<ProductionRule> for ProductionRule {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        let terminal = map(
+            delimited(
+                char('"'),
+                many0(alt((parse_escaped_char, none_of("\"\\")))),
+                char('"'),
+            ),
+            |chars| Self::Terminal(chars.into_iter().collect()),
+        );
+        let class = map(
+            delimited(char('['), many1(parse_class_range), char(']')),
+            Self::Class,
+        );
+        let any_char = value(Self::AnyChar, char('.'));
+        let reference = map(map_res(digit1, |num: &str| num.parse()), |token| {
+            Self::Ref(token)
+        });
+        let sequence = map(
+            separated_list1(space1, alt((terminal, class, any_char, reference))),
+            Self::Sequence,
+        );
+        map(
+            separated_list1(tuple((space0, char('|'), space0)), sequence),
+            Self::OneOf,
+        )(input)
+    }
+}
+
+#[derive(Debug)]
+pub enum GrammarParseError {
+    RuleParseError,
+    ExtraCharacters,
+    UndefinedNonterminal(String),
+}
+
+impl Display for GrammarParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::RuleParseError => f.write_str("Error while parsing rule.")?,
+            Self::ExtraCharacters => f.write_str("Extra characters after rule.")?,
+            Self::UndefinedNonterminal(name) => write!(f, "Undefined nonterminal '{}'.", name)?,
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GrammarParseError {}
+
+impl<'a> From<nom::Err<nom::error::Error<&'a str>>> for GrammarParseError {
+    fn from(err: nom::Err<nom::error::Error<&'a str>>) -> Self {
+        match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) if err.code == ErrorKind::Eof => {
+                Self::ExtraCharacters
+            }
+            _ => Self::RuleParseError,
+        }
+    }
+}
+
+impl TryFrom<&str> for ProductionRule {
+    type Error = GrammarParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(parse_full(<Self as Parsable<Self>>::parse, value)?)
+    }
+}
+
+impl Grammar {
+    fn parse_rule(input: &str) -> IResult<&str, (usize, ProductionRule)> {
+        let key = map_res(digit1, |num: &str| num.parse());
+        let separator = tuple((char(':'), space0));
+        separated_pair(key, separator, ProductionRule::parse)(input)
+    }
+
+    pub fn parse(
+        lines: &mut impl Iterator<Item = impl AsRef<str>>,
+    ) -> Result<Self, GrammarParseError> {
+        Ok(Self {
+            rules: lines
+                .map(|line| {
+                    parse_full(Self::parse_rule, line.as_ref()).map_err(GrammarParseError::from)
+                })
+                .collect::<Result<HashMap<_, _>, _>>()?,
+            root: 0,
+        })
+    }
+
+    // Rules in the given lines replace rules with the same number.
+    pub fn override_rules(
+        &mut self,
+        lines: &mut impl Iterator<Item = impl AsRef<str>>,
+    ) -> Result<(), GrammarParseError> {
+        let overrides = Self::parse(lines)?;
+        self.rules.extend(overrides.rules);
+        Ok(())
+    }
+
+    // Pushes every possible remainder of the input after matching the rule.
+    fn rule_accepts<'a>(
+        &self,
+        rule: &ProductionRule,
+        input: &'a str,
+        memo: &mut Memo<'a>,
+        remainders: &mut Vec<&'a str>,
+    ) {
+        use ProductionRule::*;
+        match rule {
+            Terminal(_) | Class(_) | AnyChar => {
+                if let Some(n) = rule.match_terminal(input) {
+                    remainders.push(&input[n..]);
+                }
+            }
+            Sequence(children) => self.sequence_accepts(children, input, memo, remainders),
+            OneOf(children) => {
+                for child in children {
+                    self.rule_accepts(child, input, memo, remainders);
+                }
+            }
+            Ref(referenced_rule) => {
+                let key = (*referenced_rule, input.len());
+                memo.lookups += 1;
+                if let Some(looked_up) = memo.in_progress.get_mut(&key) {
+                    *looked_up = true;
+                } else if !memo.remainders.contains_key(&key) {
+                    self.ref_accepts(*referenced_rule, input, memo);
+                }
+                remainders.extend_from_slice(&memo.remainders[&key]);
+            }
+        }
+    }
+
+    // Computes the memo entry of a rule reference. A left-recursive rule looks
+    // up its own entry while it is in progress. The entry starts out empty
+    // and the rule is matched again with the remainders found so far until
+    // they stop growing, which ends as there are only so many remainders.
+    // Entries for the same input computed in the meantime may be based on an
+    // incomplete entry and are dropped before matching again.
+    fn ref_accepts<'a>(&self, id: usize, input: &'a str, memo: &mut Memo<'a>) {
+        let key = (id, input.len());
+        memo.remainders.insert(key, vec![]);
+        memo.inserted.push(key);
+        let child_rule = match self.rules.get(&id) {
+            Some(child_rule) => child_rule,
+            None => return,
+        };
+        let start = memo.inserted.len();
+        loop {
+            memo.in_progress.insert(key, false);
+            let mut matched = vec![];
+            self.rule_accepts(child_rule, input, memo, &mut matched);
+            matched.sort_unstable_by_key(|remainder| remainder.len());
+            matched.dedup();
+            let looked_up = memo.in_progress.remove(&key) == Some(true);
+            if !looked_up || matched == memo.remainders[&key] {
+                memo.remainders.insert(key, matched);
+                return;
+            }
+            memo.remainders.insert(key, matched);
+            for stale in memo.inserted.drain(start..) {
+                if stale.1 == input.len() {
+                    memo.remainders.remove(&stale);
+                }
+            }
+        }
+    }
+
+    fn sequence_accepts<'a>(
+        &self,
+        rules: &[ProductionRule],
+        input: &'a str,
+        memo: &mut Memo<'a>,
+        remainders: &mut Vec<&'a str>,
+    ) {
+        match rules {
+            [] => remainders.push(input),
+            [rule] => self.rule_accepts(rule, input, memo, remainders),
+            [first, rest @ ..] => {
+                let mut intermediate = vec![];
+                self.rule_accepts(first, input, memo, &mut intermediate);
+                for remainder in intermediate {
+                    self.sequence_accepts(rest, remainder, memo, remainders);
+                }
+            }
+        }
+    }
+
+    // Makes the grammar accept the language of another rule, which helps to
+    // find out which part of a grammar rejects a message. Matchers created
+    // from the grammar afterwards use the new root.
+    pub fn with_root(self, root: usize) -> Self {
+        Self { root, ..self }
+    }
+
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    pub fn rule(&self, id: usize) -> Option<&ProductionRule> {
+        self.rules.get(&id)
+    }
+
+    pub fn set_rule(&mut self, id: usize, rule: ProductionRule) -> Option<ProductionRule> {
+        self.rules.insert(id, rule)
+    }
+
+    pub fn remove_rule(&mut self, id: usize) -> Option<ProductionRule> {
+        self.rules.remove(&id)
+    }
+
+    // Spans cover whole messages, the recursive matching itself is too fine
+    // grained to trace. The memo hits and misses are recorded on the span
+    // instead.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "rule_accepts", skip_all, fields(memo_hits, memo_misses))
+    )]
+    pub fn accepts(&self, input: &str) -> bool {
+        let mut memo = Memo::default();
+        let mut remainders = vec![];
+        self.rule_accepts(
+            &ProductionRule::Ref(self.root),
+            input,
+            &mut memo,
+            &mut remainders,
+        );
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            span.record("memo_hits", memo.lookups - memo.remainders.len());
+            span.record("memo_misses", memo.remainders.len());
+        }
+        remainders.iter().any(|remainder| remainder.is_empty())
+    }
+}
+
+// Sets the number of threads used for the parallel operations. Has to be
+// called before the first of them runs, otherwise rayon picks the number of
+// threads from the RAYON_NUM_THREADS environment variable or the number of
+// cores.
+#[cfg(feature = "parallel")]
+pub fn set_num_threads(n_threads: usize) -> Result<(), rayon::ThreadPoolBuildError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(n_threads)
+        .build_global()
+}
+
+// Counts the messages accepted by a matcher. Matchers only need shared
+// access to the grammar, so with the `parallel` feature the messages are
+// checked on all cores.
+#[cfg(feature = "parallel")]
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn count_accepted<T: AsRef<str> + Sync>(
+    messages: &[T],
+    accepts: impl Fn(&str) -> bool + Sync,
+) -> usize {
+    use rayon::prelude::*;
+    messages
+        .par_iter()
+        .filter(|message| accepts(message.as_ref()))
+        .count()
+}
+
+#[cfg(not(feature = "parallel"))]
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn count_accepted<T: AsRef<str> + Sync>(
+    messages: &[T],
+    accepts: impl Fn(&str) -> bool + Sync,
+) -> usize {
+    messages
+        .iter()
+        .filter(|message| accepts(message.as_ref()))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_productiion_rule() {
+        let (_, rule) = ProductionRule::parse("1 3 | 3 \"x\"").unwrap();
+        assert_eq!(
+            rule,
+            ProductionRule::OneOf(vec![
+                ProductionRule::Sequence(vec![ProductionRule::Ref(1), ProductionRule::Ref(3)]),
+                ProductionRule::Sequence(vec![
+                    ProductionRule::Ref(3),
+                    ProductionRule::Terminal("x".into()),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_grammar_rule() {
+        let (_, rule) = Grammar::parse_rule("0: 3 \"b\"").unwrap();
+        assert_eq!(
+            rule,
+            (
+                0,
+                ProductionRule::OneOf(vec![ProductionRule::Sequence(vec![
+                    ProductionRule::Ref(3),
+                    ProductionRule::Terminal("b".into())
+                ])])
+            )
+        )
+    }
+
+    #[test]
+    fn test_parse_grammar_lines() {
+        let grammar = Grammar::parse(&mut ["0: 1", "1: \"b\""].iter()).unwrap();
+        assert_eq!(
+            grammar,
+            Grammar {
+                root: 0,
+                rules: vec![
+                    (
+                        0,
+                        ProductionRule::OneOf(vec![ProductionRule::Sequence(vec![
+                            ProductionRule::Ref(1),
+                        ])])
+                    ),
+                    (
+                        1,
+                        ProductionRule::OneOf(vec![ProductionRule::Sequence(vec![
+                            ProductionRule::Terminal("b".into()),
+                        ])])
+                    )
+                ]
+                .into_iter()
+                .collect()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_grammar_lines_errors() {
+        assert!(matches!(
+            Grammar::parse(&mut ["0: 1", "x: 2"].iter()),
+            Err(GrammarParseError::RuleParseError)
+        ));
+        assert!(matches!(
+            Grammar::parse(&mut ["0: 1 |"].iter()),
+            Err(GrammarParseError::ExtraCharacters)
+        ));
+    }
+
+    #[test]
+    fn test_grammar_accepts() {
+        let grammar =
+            Grammar::parse(&mut ["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""].iter()).unwrap();
+        assert!(grammar.accepts("aab"));
+        assert!(grammar.accepts("aba"));
+        assert!(!grammar.accepts("aaa"));
+        assert!(!grammar.accepts("baa"));
+        assert!(!grammar.accepts("bab"));
+        assert!(!grammar.accepts("bba"));
+        assert!(!grammar.accepts("bbb"));
+    }
+
+    #[test]
+    fn test_grammar_with_root() {
+        let grammar =
+            Grammar::parse(&mut ["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""].iter())
+                .unwrap()
+                .with_root(2);
+        assert_eq!(grammar.root(), 2);
+        assert!(grammar.accepts("ab"));
+        assert!(grammar.accepts("ba"));
+        assert!(!grammar.accepts("aab"));
+        assert!(EarleyParser::new(&grammar).accepts("ba"));
+        assert!(grammar.to_dfa().unwrap().accepts("ab"));
+        assert!(!grammar.with_root(4).accepts(""));
+    }
+
+    #[test]
+    fn test_production_rule_try_from() {
+        assert_eq!(
+            ProductionRule::try_from("42 | 42 8").unwrap(),
+            ProductionRule::OneOf(vec![
+                ProductionRule::Sequence(vec![ProductionRule::Ref(42)]),
+                ProductionRule::Sequence(vec![ProductionRule::Ref(42), ProductionRule::Ref(8)]),
+            ])
+        );
+        assert!(matches!(
+            ProductionRule::try_from("42 |"),
+            Err(GrammarParseError::ExtraCharacters)
+        ));
+    }
+
+    #[test]
+    fn test_grammar_set_rule() {
+        let mut grammar = Grammar::parse(&mut ["0: 1", "1: \"a\""].iter()).unwrap();
+        assert!(grammar.accepts("a"));
+        assert!(!grammar.accepts("aa"));
+        let previous = grammar.set_rule(0, ProductionRule::try_from("1 | 1 0").unwrap());
+        assert_eq!(previous, ProductionRule::try_from("1").ok());
+        assert!(grammar.accepts("aa"));
+        assert!(grammar.accepts("aaaa"));
+        grammar.remove_rule(1);
+        assert_eq!(grammar.rule(1), None);
+        assert!(!grammar.accepts("a"));
+    }
+
+    #[test]
+    fn test_grammar_override_rules() {
+        let mut grammar = Grammar::parse(&mut ["0: 1", "1: \"a\""].iter()).unwrap();
+        grammar
+            .override_rules(&mut ["0: 1 1", "2: \"b\""].iter())
+            .unwrap();
+        assert!(grammar.accepts("aa"));
+        assert_eq!(
+            grammar.rule(2),
+            ProductionRule::try_from("\"b\"").ok().as_ref()
+        );
+        assert!(grammar.override_rules(&mut ["0 1"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_grammar_accepts_long_recursive_input() {
+        let grammar = Grammar::parse(
+            &mut [
+                "0: 8 11",
+                "8: 42 | 42 8",
+                "11: 42 31 | 42 11 31",
+                "42: \"a\" | \"b\" \"a\"",
+                "31: \"b\"",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        let accepted = "a".repeat(40) + &"ba".repeat(10) + &"b".repeat(30);
+        assert!(grammar.accepts(&accepted));
+        assert!(!grammar.accepts(&(accepted + &"b".repeat(20))));
+    }
+
+    #[test]
+    fn test_grammar_accepts_left_recursion() {
+        let grammar = Grammar::parse(
+            &mut [
+                "0: 1 | 0 2",
+                "1: 3 | 1 \"b\"",
+                "2: \"+\" 1",
+                "3: \"a\" | 4 \"c\"",
+                "4: 3 | \"\"",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert!(grammar.accepts("a"));
+        assert!(grammar.accepts("abb+a+cb"));
+        assert!(grammar.accepts("accc+ab"));
+        assert!(!grammar.accepts("ab+"));
+        assert!(!grammar.accepts("+a"));
+        assert!(!grammar.accepts("ba"));
+    }
+
+    #[test]
+    fn test_parse_terminals() {
+        assert_eq!(
+            ProductionRule::try_from(r#""a b\"\\c" [a-cx\]] ."#).unwrap(),
+            ProductionRule::OneOf(vec![ProductionRule::Sequence(vec![
+                ProductionRule::Terminal("a b\"\\c".into()),
+                ProductionRule::Class(vec![('a', 'c'), ('x', 'x'), (']', ']')]),
+                ProductionRule::AnyChar,
+            ])])
+        );
+        assert_eq!(
+            ProductionRule::try_from(r#""""#).unwrap(),
+            ProductionRule::OneOf(vec![ProductionRule::Sequence(vec![
+                ProductionRule::Terminal(String::new())
+            ])])
+        );
+        assert!(ProductionRule::try_from("[c-a]").is_err());
+        assert!(ProductionRule::try_from("[]").is_err());
+        assert!(ProductionRule::try_from(r#""abc"#).is_err());
+    }
+
+    #[test]
+    fn test_grammar_accepts_terminals() {
+        let grammar =
+            Grammar::parse(&mut [r#"0: "ab" [0-9] . 1"#, r#"1: "\n" | [x-z]"#].iter()).unwrap();
+        assert!(grammar.accepts("ab1!\n"));
+        assert!(grammar.accepts("ab9äy"));
+        assert!(!grammar.accepts("ab1!"));
+        assert!(!grammar.accepts("abx!y"));
+        assert!(!grammar.accepts("a1!y"));
+    }
+
+    #[test]
+    fn test_count_accepted() {
+        let grammar = Grammar::parse(&mut ["0: 1 | 1 0", "1: \"a\""].iter()).unwrap();
+        let messages = ["a", "b", "aaa", "", "aab"];
+        assert_eq!(count_accepted(&messages, |m| grammar.accepts(m)), 2);
+    }
+}
@@ -1,34 +1,8 @@
+use seating::{GridPos, Rule, SeatingAutomaton, SeatingRule};
 use std::convert::TryFrom;
-use std::io::{self, BufRead};
 use std::mem;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-enum GridPos {
-    Floor,
-    Seat(bool),
-}
-
-impl GridPos {
-    fn is_occupied(&self) -> bool {
-        match self {
-            Self::Floor => false,
-            Self::Seat(is_occupied) => *is_occupied,
-        }
-    }
-}
-
-impl TryFrom<char> for GridPos {
-    type Error = &'static str;
-
-    fn try_from(c: char) -> Result<Self, &'static str> {
-        match c {
-            '.' => Ok(GridPos::Floor),
-            'L' => Ok(GridPos::Seat(false)),
-            '#' => Ok(GridPos::Seat(true)),
-            _ => Err("Invalid seat marker."),
-        }
-    }
-}
+const PUZZLE_RULE: SeatingRule = SeatingRule { tolerance: 4 };
 
 #[derive(Debug, PartialEq)]
 struct FerryCellularAutomaton {
@@ -62,34 +36,6 @@ impl FerryCellularAutomaton {
         })
     }
 
-    pub fn advance(&mut self) {
-        for (i, seat) in self.state.iter().enumerate() {
-            let n_occupied_neighbours = self
-                .neighbours(self.idx2pos(i))
-                .iter()
-                .filter(|&&neighbour| self.state[self.pos2idx(neighbour)].is_occupied())
-                .count();
-            self.state_buffer[i] = match (seat, n_occupied_neighbours) {
-                (GridPos::Seat(false), 0) => GridPos::Seat(true),
-                (GridPos::Seat(true), n_occupied_neighbours) if n_occupied_neighbours >= 4 => {
-                    GridPos::Seat(false)
-                }
-                (seat, _) => *seat,
-            };
-        }
-        mem::swap(&mut self.state, &mut self.state_buffer);
-    }
-
-    pub fn advance_to_stable_state(&mut self) {
-        while self.state != self.state_buffer {
-            self.advance();
-        }
-    }
-
-    pub fn iter_seats(&self) -> impl Iterator<Item = &GridPos> {
-        self.state.iter()
-    }
-
     fn neighbours(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
         let (x, y) = pos;
         let mut neighbours = Vec::with_capacity(8);
@@ -129,20 +75,47 @@ impl FerryCellularAutomaton {
     }
 }
 
-fn main() {
-    let stdin = io::stdin();
-    let lines_iter = stdin.lock().lines().map(Result::unwrap);
-    let mut automaton = FerryCellularAutomaton::parse(lines_iter).unwrap();
-    automaton.advance_to_stable_state();
-    println!(
-        "Occupied seats: {}",
-        automaton.iter_seats().filter(|s| s.is_occupied()).count()
-    );
+impl SeatingAutomaton for FerryCellularAutomaton {
+    fn advance(&mut self, rule: &dyn Rule) {
+        for (i, seat) in self.state.iter().enumerate() {
+            let n_occupied_neighbours = self
+                .neighbours(self.idx2pos(i))
+                .iter()
+                .filter(|&&neighbour| self.state[self.pos2idx(neighbour)].is_occupied())
+                .count();
+            self.state_buffer[i] = rule.next(*seat, n_occupied_neighbours);
+        }
+        mem::swap(&mut self.state, &mut self.state_buffer);
+    }
+
+    fn is_stable(&self) -> bool {
+        self.state == self.state_buffer
+    }
+
+    fn seats(&self) -> &[GridPos] {
+        &self.state
+    }
+
+    fn n_columns(&self) -> usize {
+        self.n_columns
+    }
+
+    fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    seating::cli::main(
+        |lines| FerryCellularAutomaton::parse(lines.iter()),
+        PUZZLE_RULE,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use seating::{NotStableError, RuleSet, DEFAULT_MAX_GENERATIONS};
 
     static STARTING_STATE: [&str; 10] = [
         "L.LL.LL.LL",
@@ -202,7 +175,7 @@ mod tests {
         )
         .unwrap();
         for _ in 0..3 {
-            input.advance();
+            input.advance(&PUZZLE_RULE);
         }
         assert_eq!(input.state, expected.state);
     }
@@ -226,8 +199,39 @@ mod tests {
             .iter(),
         )
         .unwrap();
-        input.advance_to_stable_state();
+        assert_eq!(
+            input.advance_to_stable_state(&PUZZLE_RULE, DEFAULT_MAX_GENERATIONS),
+            Ok(())
+        );
         assert_eq!(input.state, expected.state);
-        assert_eq!(input.iter_seats().filter(|s| s.is_occupied()).count(), 37);
+        assert_eq!(input.n_occupied(), 37);
+    }
+
+    #[test]
+    fn test_advance_to_stable_state_observed() {
+        let mut input = FerryCellularAutomaton::parse(STARTING_STATE.iter()).unwrap();
+        let mut n_occupied = vec![];
+        let result: Result<(), NotStableError> = input.advance_to_stable_state_observed(
+            &PUZZLE_RULE,
+            DEFAULT_MAX_GENERATIONS,
+            &mut |automaton| {
+                n_occupied.push(automaton.n_occupied());
+                Ok(())
+            },
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(n_occupied, vec![0, 71, 20, 51, 30, 37, 37]);
+    }
+
+    #[test]
+    fn test_advance_to_stable_state_gives_up() {
+        let mut input = FerryCellularAutomaton::parse(STARTING_STATE.iter()).unwrap();
+        let blinking: RuleSet = "empty -> occupied; occupied -> empty".parse().unwrap();
+        assert_eq!(
+            input.advance_to_stable_state(&blinking, 10),
+            Err(NotStableError {
+                max_generations: 10
+            })
+        );
     }
 }
@@ -1,6 +1,7 @@
 use std::convert::TryFrom;
 use std::io::{self, BufRead};
 use std::num::ParseIntError;
+use vec2::Vec2;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum CardinalDirection {
@@ -11,13 +12,14 @@ enum CardinalDirection {
 }
 
 impl CardinalDirection {
-    fn as_cartesian_vector(&self, scale: i64) -> (i64, i64) {
-        match self {
-            Self::North => (0, scale),
-            Self::South => (0, -scale),
-            Self::East => (scale, 0),
-            Self::West => (-scale, 0),
-        }
+    fn as_cartesian_vector(&self, scale: i64) -> Vec2 {
+        let unit = match self {
+            Self::North => Vec2::NORTH,
+            Self::South => Vec2::SOUTH,
+            Self::East => Vec2::EAST,
+            Self::West => Vec2::WEST,
+        };
+        unit * scale
     }
 }
 
@@ -28,16 +30,15 @@ enum RotationDirection {
 }
 
 impl RotationDirection {
-    fn rotate90(&self, heading: CardinalDirection) -> CardinalDirection {
-        match (self, heading) {
-            (Self::Left, CardinalDirection::North) => CardinalDirection::West,
-            (Self::Left, CardinalDirection::South) => CardinalDirection::East,
-            (Self::Left, CardinalDirection::East) => CardinalDirection::North,
-            (Self::Left, CardinalDirection::West) => CardinalDirection::South,
-            (Self::Right, CardinalDirection::North) => CardinalDirection::East,
-            (Self::Right, CardinalDirection::South) => CardinalDirection::West,
-            (Self::Right, CardinalDirection::East) => CardinalDirection::South,
-            (Self::Right, CardinalDirection::West) => CardinalDirection::North,
+    // Counter-clockwise quarter turns corresponding to the rotation.
+    fn quarter_turns(&self, degrees: u32) -> i32 {
+        if !degrees.is_multiple_of(90) {
+            panic!("Unsupported rotation.");
+        }
+        let quarter_turns = (degrees / 90 % 4) as i32;
+        match self {
+            Self::Left => quarter_turns,
+            Self::Right => -quarter_turns,
         }
     }
 }
@@ -54,9 +55,7 @@ impl TryFrom<&str> for Action {
 
     fn try_from(input: &str) -> Result<Self, Self::Error> {
         let (action, value) = input.split_at(1);
-        let value = value
-            .parse()
-            .or_else(|err| Err(ActionParseError::InvalidValue(err)))?;
+        let value = value.parse().map_err(ActionParseError::InvalidValue)?;
         match action {
             "N" => Ok(Action::Move(CardinalDirection::North, value)),
             "S" => Ok(Action::Move(CardinalDirection::South, value)),
@@ -77,48 +76,34 @@ enum ActionParseError {
 }
 
 struct Ship {
-    position: (i64, i64),
-    heading: CardinalDirection,
+    position: Vec2,
+    heading: Vec2,
 }
 
 impl Ship {
     fn new() -> Self {
         Self {
-            position: (0, 0),
-            heading: CardinalDirection::East,
+            position: Vec2::ZERO,
+            heading: Vec2::EAST,
         }
     }
 
     fn excute_action(&mut self, action: Action) {
         match action {
             Action::Move(direction, distance) => {
-                let move_vector = direction.as_cartesian_vector(distance.into());
-                self.position = (
-                    self.position.0 + move_vector.0,
-                    self.position.1 + move_vector.1,
-                );
+                self.position += direction.as_cartesian_vector(distance.into());
             }
             Action::MoveForward(distance) => {
-                let move_vector = self.heading.as_cartesian_vector(distance.into());
-                self.position = (
-                    self.position.0 + move_vector.0,
-                    self.position.1 + move_vector.1,
-                );
+                self.position += self.heading * i64::from(distance);
             }
             Action::Turn(rotation, amount) => {
-                if amount % 90 != 0 {
-                    panic!("Unsupported rotation.");
-                }
-                let times = (amount / 90) % 4;
-                for _ in 0..times {
-                    self.heading = rotation.rotate90(self.heading);
-                }
+                self.heading = self.heading.rotate(rotation.quarter_turns(amount));
             }
         }
     }
 
     fn manhatten_dist(&self) -> i64 {
-        self.position.0.abs() + self.position.1.abs()
+        self.position.manhattan_norm()
     }
 }
 
@@ -155,7 +140,7 @@ mod tests {
 
     #[test]
     fn test_ship_navigation() {
-        let actions = vec!["F10", "N3", "F7", "R90", "F11"];
+        let actions = ["F10", "N3", "F7", "R90", "F11"];
         let mut ship = Ship::new();
         actions.iter().for_each(|&action| {
             ship.excute_action(Action::try_from(action).unwrap());
@@ -0,0 +1,13 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day12"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(run("example.txt", &[]), "Manhatten distance: 25\n");
+}
@@ -1,90 +1,52 @@
-use std::collections::HashMap;
-use std::convert::TryFrom;
+use decoder::program::{self, ProgramParseError};
+use decoder::{Address, Mask, Value, DEFAULT_WIDTH, MAX_WIDTH};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::{Display, Formatter};
 use std::io::{self, BufRead};
-use std::num::ParseIntError;
 
-type Address = u64;
-type Value = u64;
+type OpCode = program::OpCode<Mask>;
 
-#[derive(Debug, PartialEq)]
-struct Mask {
-    zero_mask: Value,
-    one_mask: Value,
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    writes: usize,
+    distinct_addresses: usize,
+    // Writes replacing a value that is still in memory.
+    overwritten_addresses: usize,
+    decoder_switches: usize,
 }
 
-impl Default for Mask {
-    fn default() -> Self {
-        Mask {
-            zero_mask: u64::MAX,
-            one_mask: 0,
-        }
-    }
-}
-
-impl Mask {
-    fn apply(&self, value: Value) -> Value {
-        (value & self.zero_mask) | self.one_mask
-    }
-}
-
-impl TryFrom<&str> for Mask {
-    type Error = ParseIntError;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let zero_mask = value.replace('X', "1");
-        let zero_mask = Value::from_str_radix(&zero_mask, 2)?;
-        let one_mask = value.replace('X', "0");
-        let one_mask = Value::from_str_radix(&one_mask, 2)?;
-        Ok(Self {
-            zero_mask,
-            one_mask,
-        })
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "writes: {}", self.writes)?;
+        writeln!(f, "distinct addresses: {}", self.distinct_addresses)?;
+        writeln!(f, "overwritten addresses: {}", self.overwritten_addresses)?;
+        write!(f, "decoder switches: {}", self.decoder_switches)
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum OpCode {
-    SetMask(Mask),
-    SetMem(Address, Value),
+#[derive(Default)]
+struct StatsRecorder {
+    stats: Stats,
+    written_addresses: HashSet<Address>,
 }
 
-enum OpCodeParseError {
-    InvalidStatement,
-    ParseIntError(ParseIntError),
-}
-
-impl From<ParseIntError> for OpCodeParseError {
-    fn from(error: ParseIntError) -> Self {
-        Self::ParseIntError(error)
-    }
-}
-
-impl OpCode {
-    pub fn parse_statement(input: &str) -> Result<OpCode, String> {
-        use nom::{
-            character::complete::{alpha1, alphanumeric1, char, digit1, multispace0},
-            combinator::{eof, map_res, opt},
-            sequence::{delimited, tuple},
-        };
-        let address = delimited(char('['), digit1, char(']'));
-        let assignment = delimited(multispace0, char('='), multispace0);
-        let grammar = tuple((alpha1, opt(address), assignment, alphanumeric1, eof));
-        let mut parser = map_res(grammar, |(keyword, address, _, value, _)| {
-            match (keyword, address) {
-                ("mask", None) => Ok(OpCode::SetMask(Mask::try_from(value)?)),
-                ("mem", Some(address)) => Ok(OpCode::SetMem(address.parse()?, value.parse()?)),
-                _ => Err(OpCodeParseError::InvalidStatement),
-            }
-        });
-        parser(input)
-            .map(|(_, op_code)| op_code)
-            .map_err(|err: nom::Err<(&str, _)>| format!("{}", err))
+impl StatsRecorder {
+    fn record_write(&mut self, address: Address, overwritten: bool) {
+        self.stats.writes += 1;
+        if overwritten {
+            self.stats.overwritten_addresses += 1;
+        }
+        self.written_addresses.insert(address);
+        self.stats.distinct_addresses = self.written_addresses.len();
     }
 }
 
 struct ComputerSystem {
     current_mask: Mask,
     mem: HashMap<u64, u64>,
+    // Only kept with --stats to not slow down normal runs.
+    stats: Option<StatsRecorder>,
 }
 
 impl ComputerSystem {
@@ -92,39 +54,119 @@ impl ComputerSystem {
         Self {
             current_mask: Mask::default(),
             mem: HashMap::new(),
+            stats: None,
         }
     }
 
+    fn with_stats() -> Self {
+        Self {
+            stats: Some(StatsRecorder::default()),
+            ..Self::new()
+        }
+    }
+
+    fn stats(&self) -> Option<&Stats> {
+        self.stats.as_ref().map(|recorder| &recorder.stats)
+    }
+
     fn execute(&mut self, operation: OpCode) {
         match operation {
-            OpCode::SetMask(mask) => self.current_mask = mask,
+            OpCode::SetMask(mask) => {
+                self.current_mask = mask;
+                if let Some(recorder) = &mut self.stats {
+                    recorder.stats.decoder_switches += 1;
+                }
+            }
             OpCode::SetMem(address, value) => {
-                self.mem.insert(address, self.current_mask.apply(value));
+                let overwritten = self
+                    .mem
+                    .insert(address, self.current_mask.apply(value))
+                    .is_some();
+                if let Some(recorder) = &mut self.stats {
+                    recorder.record_write(address, overwritten);
+                }
+            }
+            OpCode::AddMem(address, value) => {
+                let entry = self.mem.entry(address).or_insert(0);
+                *entry = entry.wrapping_add(self.current_mask.apply(value));
+                if let Some(recorder) = &mut self.stats {
+                    recorder.record_write(address, false);
+                }
             }
+            OpCode::Clear => self.mem.clear(),
         }
     }
+
+    // Wraps around on overflow like the add statements.
+    fn memory_sum(&self) -> Value {
+        self.mem
+            .values()
+            .fold(0, |sum, &value| sum.wrapping_add(value))
+    }
 }
 
-fn run_program(program: impl Iterator<Item = impl AsRef<str>>) -> Result<Value, String> {
-    let mut computer = ComputerSystem::new();
-    for statement in program {
-        let op_code = OpCode::parse_statement(statement.as_ref())?;
-        computer.execute(op_code);
+fn run_program(
+    computer: &mut ComputerSystem,
+    program: impl Iterator<Item = impl AsRef<str>>,
+    mask_width: u32,
+) -> Result<Value, ProgramParseError> {
+    for (i, statement) in program.enumerate() {
+        let op_code = OpCode::parse_statement(statement.as_ref(), mask_width).map_err(|error| {
+            ProgramParseError {
+                line: i + 1,
+                statement: String::from(statement.as_ref()),
+                error,
+            }
+        })?;
+        if let Some(op_code) = op_code {
+            computer.execute(op_code);
+        }
     }
-    Ok(computer.mem.values().sum())
+    Ok(computer.memory_sum())
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut print_stats = false;
+    let mut mask_width = DEFAULT_WIDTH;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mask-width" => {
+                mask_width = args
+                    .next()
+                    .ok_or("--mask-width requires a number of bits.")?
+                    .parse()?;
+                if mask_width == 0 || mask_width > MAX_WIDTH {
+                    return Err(format!("--mask-width must be between 1 and {}.", MAX_WIDTH).into());
+                }
+            }
+            "--stats" => print_stats = true,
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+
     let stdin = io::stdin();
-    println!(
-        "{}",
-        run_program(stdin.lock().lines().map(Result::unwrap)).unwrap()
-    );
+    let mut computer = if print_stats {
+        ComputerSystem::with_stats()
+    } else {
+        ComputerSystem::new()
+    };
+    let sum = run_program(
+        &mut computer,
+        stdin.lock().lines().map(Result::unwrap),
+        mask_width,
+    )?;
+    println!("{}", sum);
+    if let Some(stats) = computer.stats() {
+        println!("{}", stats);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use decoder::program::OpCodeParseError;
 
     static PROGRAM: [&str; 4] = [
         "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X",
@@ -134,26 +176,98 @@ mod tests {
     ];
 
     #[test]
-    fn test_opcode_parse_set_mask_statement() {
-        let opcode =
-            OpCode::parse_statement("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX").unwrap();
+    fn test_program_parse_error_reports_line() {
+        let program = ["mem[8] = 11", "mask = XXX2", "mem[8] = 0"];
         assert_eq!(
-            opcode,
-            OpCode::SetMask(Mask {
-                one_mask: 0,
-                zero_mask: 0x0fffffffff
+            run_program(&mut ComputerSystem::new(), program.iter(), DEFAULT_WIDTH),
+            Err(ProgramParseError {
+                line: 2,
+                statement: String::from("mask = XXX2"),
+                error: OpCodeParseError::new("XXX2", "mask of 0, 1, and X"),
             })
         );
     }
 
     #[test]
-    fn test_opcode_parse_set_mem_statement() {
-        let opcode = OpCode::parse_statement("mem[42] = 23").unwrap();
-        assert_eq!(opcode, OpCode::SetMem(42, 23));
+    fn test_program() {
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), PROGRAM.iter(), DEFAULT_WIDTH).unwrap(),
+            165
+        );
     }
 
     #[test]
-    fn test_program() {
-        assert_eq!(run_program(PROGRAM.iter()).unwrap(), 165);
+    fn test_memory_sum_wraps_around() {
+        let mask = format!("mask = {}", "X".repeat(64));
+        let program = [
+            mask.as_str(),
+            "mem[0] = 18446744073709551615",
+            "mem[1] = 18446744073709551615",
+        ];
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), 64).unwrap(),
+            u64::MAX - 1
+        );
+    }
+
+    #[test]
+    fn test_program_with_mask_width() {
+        let program = ["mask = X1X0", "mem[8] = 11", "mem[7] = 1"];
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), 4).unwrap(),
+            14 + 4
+        );
+        let error = run_program(&mut ComputerSystem::new(), PROGRAM.iter(), 4).unwrap_err();
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn test_extended_program() {
+        let program = [
+            "# part 1 example with extensions",
+            "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X",
+            "mem[8] = 11",
+            "",
+            "add[8] = 1 # 73 + 64",
+            "clear",
+            "mem[7] = 101",
+        ];
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), DEFAULT_WIDTH).unwrap(),
+            101
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut computer = ComputerSystem::with_stats();
+        let program = [
+            PROGRAM[0],
+            PROGRAM[1],
+            PROGRAM[2],
+            PROGRAM[3],
+            "clear",
+            "add[8] = 1",
+        ];
+        assert_eq!(
+            run_program(&mut computer, program.iter(), DEFAULT_WIDTH).unwrap(),
+            65
+        );
+        assert_eq!(
+            computer.stats(),
+            Some(&Stats {
+                writes: 4,
+                distinct_addresses: 2,
+                overwritten_addresses: 1,
+                decoder_switches: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_stats_are_off_by_default() {
+        let mut computer = ComputerSystem::new();
+        run_program(&mut computer, PROGRAM.iter(), DEFAULT_WIDTH).unwrap();
+        assert_eq!(computer.stats(), None);
     }
 }
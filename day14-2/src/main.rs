@@ -1,178 +1,279 @@
-use std::collections::HashMap;
-use std::convert::TryFrom;
+use decoder::program::{self, ProgramParseError};
+use decoder::{decode_write, Address, AddressSet, Decoder, Value, DEFAULT_WIDTH, MAX_WIDTH};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::{Display, Formatter};
 use std::io::{self, BufRead};
-use std::num::ParseIntError;
 
-type Address = u64;
-type Value = u64;
+type OpCode = program::OpCode<Decoder>;
 
-#[derive(Debug, PartialEq)]
-struct Decoder {
-    one_mask: Address,
-    fluctuating_bits: Vec<usize>,
+trait Computer {
+    fn execute(&mut self, operation: OpCode);
+    fn memory_sum(&self) -> Value;
 }
 
-impl Default for Decoder {
-    fn default() -> Self {
-        Decoder {
-            fluctuating_bits: vec![],
-            one_mask: 0,
-        }
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    writes: usize,
+    distinct_addresses: usize,
+    // Writes replacing a value that is still in memory.
+    overwritten_addresses: usize,
+    decoder_switches: usize,
+    // Number of addresses the decoder expanded the memory statements to.
+    expanded_addresses: usize,
+    max_expanded_addresses: usize,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "writes: {}", self.writes)?;
+        writeln!(f, "distinct addresses: {}", self.distinct_addresses)?;
+        writeln!(f, "overwritten addresses: {}", self.overwritten_addresses)?;
+        writeln!(f, "decoder switches: {}", self.decoder_switches)?;
+        write!(
+            f,
+            "expanded addresses: {} (max {} per statement)",
+            self.expanded_addresses, self.max_expanded_addresses
+        )
     }
 }
 
-struct AddressIterator<'a> {
-    base_address: Address,
-    fluctuating_bits: &'a Vec<usize>,
-    fluctuating_mask: Address,
-    state: usize,
+#[derive(Default)]
+struct StatsRecorder {
+    stats: Stats,
+    written_addresses: HashSet<Address>,
 }
 
-impl<'a> AddressIterator<'a> {
-    fn new(address: Address, decoder: &'a Decoder) -> Self {
-        Self {
-            base_address: address | decoder.one_mask,
-            fluctuating_bits: &decoder.fluctuating_bits,
-            fluctuating_mask: Self::bits2mask(&decoder.fluctuating_bits, usize::MAX),
-            state: 0,
+impl StatsRecorder {
+    fn record_write(&mut self, address: Address, overwritten: bool) {
+        self.stats.writes += 1;
+        if overwritten {
+            self.stats.overwritten_addresses += 1;
         }
+        self.written_addresses.insert(address);
+        self.stats.distinct_addresses = self.written_addresses.len();
     }
 
-    fn bits2mask(bits: &Vec<usize>, bit_selector: usize) -> Address {
-        bits.iter().enumerate().fold(0, |mask, (i, bit)| {
-            if bit_selector & (1 << i) != 0 {
-                mask | (1 << bit)
-            } else {
-                mask
-            }
-        })
+    fn record_expansion(&mut self, n_addresses: usize) {
+        self.stats.expanded_addresses += n_addresses;
+        self.stats.max_expanded_addresses = self.stats.max_expanded_addresses.max(n_addresses);
     }
 }
 
-impl<'a> Iterator for AddressIterator<'a> {
-    type Item = Address;
+struct ComputerSystem {
+    current_decoder: Decoder,
+    mem: HashMap<u64, u64>,
+    // Only kept with --stats to not slow down normal runs.
+    stats: Option<StatsRecorder>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.state < (1 << self.fluctuating_bits.len()) {
-            let mask = Self::bits2mask(self.fluctuating_bits, self.state);
-            self.state += 1;
-            Some((self.base_address & !self.fluctuating_mask) | mask)
-        } else {
-            None
+impl ComputerSystem {
+    fn new() -> Self {
+        Self {
+            current_decoder: Decoder::default(),
+            mem: HashMap::new(),
+            stats: None,
         }
     }
-}
 
-impl Decoder {
-    fn iter_addresses<'a>(&'a self, base_address: Address) -> AddressIterator<'a> {
-        AddressIterator::new(base_address, self)
+    fn with_stats() -> Self {
+        Self {
+            stats: Some(StatsRecorder::default()),
+            ..Self::new()
+        }
     }
-}
 
-impl TryFrom<&str> for Decoder {
-    type Error = ParseIntError;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let one_mask = value.replace('X', "0");
-        let one_mask = Value::from_str_radix(&one_mask, 2)?;
-        Ok(Self {
-            one_mask,
-            fluctuating_bits: value
-                .as_bytes()
-                .iter()
-                .rev()
-                .enumerate()
-                .filter_map(|(i, &c)| if c == b'X' { Some(i) } else { None })
-                .collect(),
-        })
+    fn stats(&self) -> Option<&Stats> {
+        self.stats.as_ref().map(|recorder| &recorder.stats)
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum OpCode {
-    SetDecoder(Decoder),
-    SetMem(Address, Value),
-}
-
-enum OpCodeParseError {
-    InvalidStatement,
-    ParseIntError(ParseIntError),
-}
-
-impl From<ParseIntError> for OpCodeParseError {
-    fn from(error: ParseIntError) -> Self {
-        Self::ParseIntError(error)
+impl Computer for ComputerSystem {
+    fn execute(&mut self, operation: OpCode) {
+        match operation {
+            OpCode::SetMask(decoder) => {
+                self.current_decoder = decoder;
+                if let Some(recorder) = &mut self.stats {
+                    recorder.stats.decoder_switches += 1;
+                }
+            }
+            OpCode::SetMem(base_address, value) => {
+                let mut n_writes = 0;
+                for (address, value) in decode_write(&self.current_decoder, base_address, value) {
+                    let overwritten = self.mem.insert(address, value).is_some();
+                    if let Some(recorder) = &mut self.stats {
+                        recorder.record_write(address, overwritten);
+                    }
+                    n_writes += 1;
+                }
+                if let Some(recorder) = &mut self.stats {
+                    recorder.record_expansion(n_writes);
+                }
+            }
+            OpCode::AddMem(base_address, value) => {
+                let mut n_writes = 0;
+                for (address, value) in decode_write(&self.current_decoder, base_address, value) {
+                    let entry = self.mem.entry(address).or_insert(0);
+                    *entry = entry.wrapping_add(value);
+                    if let Some(recorder) = &mut self.stats {
+                        recorder.record_write(address, false);
+                    }
+                    n_writes += 1;
+                }
+                if let Some(recorder) = &mut self.stats {
+                    recorder.record_expansion(n_writes);
+                }
+            }
+            OpCode::Clear => self.mem.clear(),
+        }
     }
-}
 
-impl OpCode {
-    pub fn parse_statement(input: &str) -> Result<OpCode, String> {
-        use nom::{
-            character::complete::{alpha1, alphanumeric1, char, digit1, multispace0},
-            combinator::{eof, map_res, opt},
-            sequence::{delimited, tuple},
-        };
-        let address = delimited(char('['), digit1, char(']'));
-        let assignment = delimited(multispace0, char('='), multispace0);
-        let grammar = tuple((alpha1, opt(address), assignment, alphanumeric1, eof));
-        let mut parser = map_res(grammar, |(keyword, address, _, value, _)| {
-            match (keyword, address) {
-                ("mask", None) => Ok(OpCode::SetDecoder(Decoder::try_from(value)?)),
-                ("mem", Some(address)) => Ok(OpCode::SetMem(address.parse()?, value.parse()?)),
-                _ => Err(OpCodeParseError::InvalidStatement),
-            }
-        });
-        parser(input)
-            .map(|(_, op_code)| op_code)
-            .map_err(|err: nom::Err<(&str, _)>| format!("{}", err))
+    // Wraps around on overflow like the add statements.
+    fn memory_sum(&self) -> Value {
+        self.mem
+            .values()
+            .fold(0, |sum, &value| sum.wrapping_add(value))
     }
 }
 
-struct ComputerSystem {
+// Never materializes the memory. Writes are kept as signed weights on address
+// sets; overwriting a set cancels the weights of all overlapping sets on their
+// intersection (inclusion-exclusion), so the sum is computed from set sizes.
+struct SymbolicComputerSystem {
     current_decoder: Decoder,
-    mem: HashMap<u64, u64>,
+    weights: HashMap<AddressSet, i128>,
 }
 
-impl ComputerSystem {
+impl SymbolicComputerSystem {
     fn new() -> Self {
         Self {
             current_decoder: Decoder::default(),
-            mem: HashMap::new(),
+            weights: HashMap::new(),
+        }
+    }
+
+    fn add_weight(&mut self, address_set: AddressSet, weight: i128) {
+        let entry = self.weights.entry(address_set).or_insert(0);
+        *entry += weight;
+        if *entry == 0 {
+            self.weights.remove(&address_set);
         }
     }
 
+    fn cancel(&mut self, address_set: &AddressSet) {
+        let overlaps: Vec<(AddressSet, i128)> = self
+            .weights
+            .iter()
+            .filter_map(|(other, &weight)| {
+                other
+                    .intersection(address_set)
+                    .map(|intersection| (intersection, -weight))
+            })
+            .collect();
+        for (intersection, weight) in overlaps {
+            self.add_weight(intersection, weight);
+        }
+    }
+}
+
+impl Computer for SymbolicComputerSystem {
     fn execute(&mut self, operation: OpCode) {
         match operation {
-            OpCode::SetDecoder(decoder) => self.current_decoder = decoder,
+            OpCode::SetMask(decoder) => self.current_decoder = decoder,
             OpCode::SetMem(base_address, value) => {
-                for address in self.current_decoder.iter_addresses(base_address) {
-                    self.mem.insert(address, value);
-                }
+                let address_set = self.current_decoder.address_set(base_address);
+                self.cancel(&address_set);
+                self.add_weight(address_set, value.into());
+            }
+            OpCode::AddMem(base_address, value) => {
+                let address_set = self.current_decoder.address_set(base_address);
+                self.add_weight(address_set, value.into());
             }
+            OpCode::Clear => self.weights.clear(),
         }
     }
+
+    // Computed modulo 2^128 and truncated, which gives the same result as
+    // wrapping around at 2^64 like the add statements.
+    fn memory_sum(&self) -> Value {
+        self.weights
+            .iter()
+            .fold(0i128, |sum, (address_set, weight)| {
+                sum.wrapping_add(weight.wrapping_mul(address_set.n_addresses() as i128))
+            }) as Value
+    }
 }
 
-fn run_program(program: impl Iterator<Item = impl AsRef<str>>) -> Result<Value, String> {
-    let mut computer = ComputerSystem::new();
-    for statement in program {
-        let op_code = OpCode::parse_statement(statement.as_ref())?;
-        computer.execute(op_code);
+fn run_program(
+    computer: &mut impl Computer,
+    program: impl Iterator<Item = impl AsRef<str>>,
+    mask_width: u32,
+) -> Result<Value, ProgramParseError> {
+    for (i, statement) in program.enumerate() {
+        let op_code = OpCode::parse_statement(statement.as_ref(), mask_width).map_err(|error| {
+            ProgramParseError {
+                line: i + 1,
+                statement: String::from(statement.as_ref()),
+                error,
+            }
+        })?;
+        if let Some(op_code) = op_code {
+            computer.execute(op_code);
+        }
     }
-    Ok(computer.mem.values().sum())
+    Ok(computer.memory_sum())
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut symbolic = false;
+    let mut print_stats = false;
+    let mut mask_width = DEFAULT_WIDTH;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mask-width" => {
+                mask_width = args
+                    .next()
+                    .ok_or("--mask-width requires a number of bits.")?
+                    .parse()?;
+                if mask_width == 0 || mask_width > MAX_WIDTH {
+                    return Err(format!("--mask-width must be between 1 and {}.", MAX_WIDTH).into());
+                }
+            }
+            "--symbolic" => symbolic = true,
+            "--stats" => print_stats = true,
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+    if symbolic && print_stats {
+        return Err("--stats cannot be used with --symbolic.".into());
+    }
+
     let stdin = io::stdin();
-    println!(
-        "{}",
-        run_program(stdin.lock().lines().map(Result::unwrap)).unwrap()
-    );
+    let program = stdin.lock().lines().map(Result::unwrap);
+    if symbolic {
+        println!(
+            "{}",
+            run_program(&mut SymbolicComputerSystem::new(), program, mask_width)?
+        );
+    } else {
+        let mut computer = if print_stats {
+            ComputerSystem::with_stats()
+        } else {
+            ComputerSystem::new()
+        };
+        println!("{}", run_program(&mut computer, program, mask_width)?);
+        if let Some(stats) = computer.stats() {
+            println!("{}", stats);
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use decoder::program::OpCodeParseError;
 
     static PROGRAM: [&str; 4] = [
         "mask = 000000000000000000000000000000X1001X",
@@ -182,26 +283,179 @@ mod tests {
     ];
 
     #[test]
-    fn test_opcode_parse_set_mask_statement() {
-        let opcode =
-            OpCode::parse_statement("mask = 000000000000000000000000000000X0XX11").unwrap();
+    fn test_program_parse_error_reports_line() {
+        let program = ["mem[8] = 11", "mask = 0XX2", "mem[8] = 0"];
         assert_eq!(
-            opcode,
-            OpCode::SetDecoder(Decoder {
-                one_mask: 0b11,
-                fluctuating_bits: vec![2, 3, 5],
+            run_program(&mut ComputerSystem::new(), program.iter(), DEFAULT_WIDTH),
+            Err(ProgramParseError {
+                line: 2,
+                statement: String::from("mask = 0XX2"),
+                error: OpCodeParseError::new("0XX2", "mask of 0, 1, and X"),
             })
         );
     }
 
     #[test]
-    fn test_opcode_parse_set_mem_statement() {
-        let opcode = OpCode::parse_statement("mem[42] = 23").unwrap();
-        assert_eq!(opcode, OpCode::SetMem(42, 23));
+    fn test_program() {
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), PROGRAM.iter(), DEFAULT_WIDTH).unwrap(),
+            208
+        );
     }
 
     #[test]
-    fn test_program() {
-        assert_eq!(run_program(PROGRAM.iter()).unwrap(), 208);
+    fn test_memory_sum_wraps_around() {
+        let mask = format!("mask = {}", "0".repeat(64));
+        let program = [
+            mask.as_str(),
+            "mem[0] = 18446744073709551615",
+            "mem[1] = 18446744073709551615",
+        ];
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), 64).unwrap(),
+            u64::MAX - 1
+        );
+        assert_eq!(
+            run_program(&mut SymbolicComputerSystem::new(), program.iter(), 64).unwrap(),
+            u64::MAX - 1
+        );
+    }
+
+    #[test]
+    fn test_all_64_bits_floating() {
+        let mask = format!("mask = {}", "X".repeat(64));
+        let program = [mask.as_str(), "mem[0] = 3"];
+        assert_eq!(
+            run_program(&mut SymbolicComputerSystem::new(), program.iter(), 64).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_program_with_mask_width() {
+        let program = ["mask = 0X1X", "mem[8] = 3", "mem[1] = 5"];
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), 4).unwrap(),
+            run_program(&mut SymbolicComputerSystem::new(), program.iter(), 4).unwrap()
+        );
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), 4).unwrap(),
+            3 * 4 + 5 * 4
+        );
+        let error = run_program(&mut ComputerSystem::new(), PROGRAM.iter(), 4).unwrap_err();
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn test_extended_program() {
+        let program = [
+            "# part 2 example with extensions",
+            "mask = 00000000000000000000000000000000X0XX",
+            "mem[26] = 1",
+            "",
+            "add[26] = 2 # writes 3 to 8 addresses",
+            "clear",
+            "add[16] = 5",
+        ];
+        assert_eq!(
+            run_program(&mut ComputerSystem::new(), program.iter(), DEFAULT_WIDTH).unwrap(),
+            40
+        );
+    }
+
+    #[test]
+    fn test_symbolic_program() {
+        assert_eq!(
+            run_program(
+                &mut SymbolicComputerSystem::new(),
+                PROGRAM.iter(),
+                DEFAULT_WIDTH
+            )
+            .unwrap(),
+            208
+        );
+    }
+
+    #[test]
+    fn test_symbolic_matches_memory_map_on_overlapping_writes() {
+        let program = [
+            "mask = 0000000000000000000000000000XXXXXXXX",
+            "mem[0] = 7",
+            "mask = 00000000000000000000000000001X0X1XX0",
+            "mem[3] = 11",
+            "add[64] = 5",
+            "mask = 0000000000000000000000000000X1X0X0X1",
+            "mem[0] = 13",
+            "mask = 00000000000000000000000000000000XXXX",
+            "add[16] = 2",
+            "mem[128] = 1",
+        ];
+        assert_eq!(
+            run_program(
+                &mut SymbolicComputerSystem::new(),
+                program.iter(),
+                DEFAULT_WIDTH
+            )
+            .unwrap(),
+            run_program(&mut ComputerSystem::new(), program.iter(), DEFAULT_WIDTH).unwrap()
+        );
+        assert_eq!(
+            run_program(
+                &mut SymbolicComputerSystem::new(),
+                program[..6].iter(),
+                DEFAULT_WIDTH
+            )
+            .unwrap(),
+            run_program(
+                &mut ComputerSystem::new(),
+                program[..6].iter(),
+                DEFAULT_WIDTH
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_symbolic_extended_program() {
+        let program = [
+            "mask = 00000000000000000000000000000000X0XX",
+            "mem[26] = 1",
+            "add[26] = 2",
+            "clear",
+            "add[16] = 5",
+        ];
+        assert_eq!(
+            run_program(
+                &mut SymbolicComputerSystem::new(),
+                program.iter(),
+                DEFAULT_WIDTH
+            )
+            .unwrap(),
+            40
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut computer = ComputerSystem::with_stats();
+        run_program(&mut computer, PROGRAM.iter(), DEFAULT_WIDTH).unwrap();
+        assert_eq!(
+            computer.stats(),
+            Some(&Stats {
+                writes: 12,
+                distinct_addresses: 10,
+                overwritten_addresses: 2,
+                decoder_switches: 2,
+                expanded_addresses: 12,
+                max_expanded_addresses: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_stats_are_off_by_default() {
+        let mut computer = ComputerSystem::new();
+        run_program(&mut computer, PROGRAM.iter(), DEFAULT_WIDTH).unwrap();
+        assert_eq!(computer.stats(), None);
     }
 }
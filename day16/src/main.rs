@@ -1,38 +1,73 @@
+use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{alphanumeric1, char, digit1, line_ending, space1},
-    combinator::{eof, map, map_res, recognize},
+    combinator::{eof, map, map_res, not, recognize},
     multi::{many1, separated_list0},
-    sequence::{separated_pair, terminated, tuple},
+    sequence::{preceded, separated_pair, terminated, tuple},
     IResult,
 };
-use std::io::{self, Read};
+use serde::Serialize;
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+#[cfg(feature = "profile")]
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+#[cfg(feature = "profile")]
+use tracing_subscriber::prelude::*;
 
-use graph::DirectedGraph;
-
-trait Parsable<T> {
-    fn parse(input: &str) -> IResult<&str, T>;
-}
+use graph::{hopcroft_karp, DirectedGraph};
+use parsing::{dump_intermediate, parse_full, section, Parsable};
 
 type Value = u64;
 
-impl Parsable<Value> for Value {
-    fn parse(input: &str) -> IResult<&str, Value> {
-        map_res(digit1, str::parse::<Value>)(input)
-    }
+fn parse_value(input: &str) -> IResult<&str, Value> {
+    map_res(digit1, str::parse::<Value>)(input)
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
 struct InclusiveRange(Value, Value);
 
+// Accepts closed ranges "a-b", open-ended ranges ">=a" and "<=b", and single
+// values.
 impl Parsable<InclusiveRange> for InclusiveRange {
     fn parse(input: &str) -> IResult<&str, InclusiveRange> {
-        map(
-            separated_pair(Value::parse, char('-'), Value::parse),
-            |(lb, ub)| InclusiveRange(lb, ub),
-        )(input)
+        alt((
+            map(preceded(tag(">="), parse_value), |lb| {
+                InclusiveRange(lb, Value::MAX)
+            }),
+            map(preceded(tag("<="), parse_value), |ub| {
+                InclusiveRange(Value::MIN, ub)
+            }),
+            map(
+                separated_pair(parse_value, char('-'), parse_value),
+                |(lb, ub)| InclusiveRange(lb, ub),
+            ),
+            map(terminated(parse_value, not(char('-'))), |value| {
+                InclusiveRange(value, value)
+            }),
+        ))(input)
+    }
+}
+
+impl Display for InclusiveRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.0 == self.1 {
+            write!(f, "{}", self.0)
+        } else if self.1 == Value::MAX {
+            write!(f, ">={}", self.0)
+        } else if self.0 == Value::MIN {
+            write!(f, "<={}", self.1)
+        } else {
+            write!(f, "{}-{}", self.0, self.1)
+        }
     }
 }
 
@@ -46,9 +81,19 @@ impl InclusiveRange {
     fn contains(&self, value: &Value) -> bool {
         self.0 <= *value && *value <= self.1
     }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let lb = max(self.0, other.0);
+        let ub = min(self.1, other.1);
+        if lb <= ub {
+            Some(InclusiveRange(lb, ub))
+        } else {
+            None
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize)]
 struct Rule {
     field: String,
     valid_ranges: Vec<InclusiveRange>,
@@ -77,57 +122,164 @@ impl Rule {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(transparent)]
 struct Ticket {
     values: Vec<Value>,
 }
 
 impl Parsable<Ticket> for Ticket {
     fn parse(input: &str) -> IResult<&str, Ticket> {
-        map(separated_list0(char(','), Value::parse), |values| Ticket {
+        map(separated_list0(char(','), parse_value), |values| Ticket {
             values,
         })(input)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
 struct Notes {
     rules: Vec<Rule>,
     my_ticket: Ticket,
     nearby_tickets: Vec<Ticket>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Section {
+    Rules,
+    YourTicket,
+    NearbyTickets,
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Rules => f.write_str("rules"),
+            Self::YourTicket => f.write_str("your ticket"),
+            Self::NearbyTickets => f.write_str("nearby tickets"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct NotesParseError {
+    section: Section,
+    line: usize,
+    remainder: String,
+}
+
+impl NotesParseError {
+    fn new(input: &str, section: Section, err: nom::Err<nom::error::Error<&str>>) -> Self {
+        let remainder = match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err.input,
+            nom::Err::Incomplete(_) => "",
+        };
+        let offset = input.len() - remainder.len();
+        Self {
+            section,
+            line: input[..offset].matches('\n').count() + 1,
+            remainder: String::from(remainder),
+        }
+    }
+}
+
+impl Display for NotesParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Invalid {} section in line {}, could not parse '{}'.",
+            self.section,
+            self.line,
+            self.remainder.lines().next().unwrap_or("")
+        )
+    }
+}
+
+impl std::error::Error for NotesParseError {}
+
+// No field is left for the rule once the other rules are assigned.
+#[derive(Debug, PartialEq)]
+struct AssignmentError {
+    rule: String,
+}
+
+impl Display for AssignmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "No valid assignment of rules to fields, no field is left for '{}'.",
+            self.rule
+        )
+    }
+}
+
+impl std::error::Error for AssignmentError {}
+
+fn parse_rules_section(input: &str) -> IResult<&str, Vec<Rule>> {
+    section(separated_list0(line_ending, Rule::parse))(input)
+}
+
+fn parse_your_ticket_section(input: &str) -> IResult<&str, Ticket> {
+    section(preceded(
+        tuple((tag("your ticket:"), line_ending)),
+        Ticket::parse,
+    ))(input)
+}
+
+fn parse_nearby_tickets_section(input: &str) -> IResult<&str, Vec<Ticket>> {
+    map(
+        terminated(
+            section(preceded(
+                tuple((tag("nearby tickets:"), line_ending)),
+                separated_list0(line_ending, Ticket::parse),
+            )),
+            eof,
+        ),
+        |tickets| {
+            tickets
+                .into_iter()
+                .filter(|t| !t.values.is_empty())
+                .collect()
+        },
+    )(input)
+}
+
 impl Parsable<Notes> for Notes {
     fn parse(input: &str) -> IResult<&str, Notes> {
         let grammar = tuple((
-            terminated(
-                separated_list0(line_ending, Rule::parse),
-                tuple((line_ending, line_ending)),
-            ),
-            tuple((tag("your ticket:"), line_ending)),
-            terminated(Ticket::parse, tuple((line_ending, line_ending))),
-            tuple((tag("nearby tickets:"), line_ending)),
-            separated_list0(line_ending, Ticket::parse),
-            eof,
+            parse_rules_section,
+            parse_your_ticket_section,
+            parse_nearby_tickets_section,
         ));
-        map(grammar, |(rules, _, my_ticket, _, nearby_tickets, _)| {
-            Notes {
-                rules,
-                my_ticket,
-                nearby_tickets: nearby_tickets
-                    .into_iter()
-                    .filter(|t| t.values.len() > 0)
-                    .collect(),
-            }
+        map(grammar, |(rules, my_ticket, nearby_tickets)| Notes {
+            rules,
+            my_ticket,
+            nearby_tickets,
         })(input)
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-enum Node<'a> {
-    Rule(&'a Rule),
-    Field(usize),
-    Other(&'a str),
+impl TryFrom<&str> for Notes {
+    type Error = NotesParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (remainder, rules) = parse_rules_section(input)
+            .map_err(|err| NotesParseError::new(input, Section::Rules, err))?;
+        let (remainder, my_ticket) = parse_your_ticket_section(remainder)
+            .map_err(|err| NotesParseError::new(input, Section::YourTicket, err))?;
+        let (_, nearby_tickets) = parse_nearby_tickets_section(remainder)
+            .map_err(|err| NotesParseError::new(input, Section::NearbyTickets, err))?;
+        Ok(Notes {
+            rules,
+            my_ticket,
+            nearby_tickets,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RangeOverlap<'a> {
+    rules: (&'a str, &'a str),
+    overlap: InclusiveRange,
 }
 
 impl Notes {
@@ -135,90 +287,415 @@ impl Notes {
         self.rules.iter().all(|rule| !rule.is_valid(value))
     }
 
-    fn ticket_scanning_error_rate(&self) -> Value {
-        self.nearby_tickets
-            .iter()
-            .flat_map(|ticket| ticket.values.iter())
-            .filter(|&&v| self.is_definitely_invalid_value(v))
-            .sum()
+    fn valid_nearby_tickets(&self) -> impl Iterator<Item = &Ticket> {
+        self.nearby_tickets.iter().filter(move |ticket| {
+            !ticket
+                .values
+                .iter()
+                .any(|&v| self.is_definitely_invalid_value(v))
+        })
     }
 
-    fn find_rules_to_fields_map(&self) -> Vec<usize> {
-        let mut graph: DirectedGraph<Node> = DirectedGraph::new();
-        let start = Rc::new(Node::Other("start"));
-        let end = Rc::new(Node::Other("end"));
-        let rules: Vec<Rc<Node>> = self.rules.iter().map(Node::Rule).map(Rc::new).collect();
-        let fields: Vec<Rc<Node>> = (0..rules.len()).map(Node::Field).map(Rc::new).collect();
+    fn scan(&self) -> TicketScanner<'_> {
+        let mut scanner = TicketScanner::new(&self.rules, &self.my_ticket);
+        for ticket in &self.nearby_tickets {
+            scanner.add_ticket(ticket);
+        }
+        scanner
+    }
 
-        let valid_tickets: Vec<&Ticket> = self
-            .nearby_tickets
+    fn range_overlaps(&self) -> Vec<RangeOverlap<'_>> {
+        self.rules
             .iter()
-            .filter(|ticket| {
-                !ticket
-                    .values
+            .tuple_combinations()
+            .flat_map(|(a, b)| {
+                a.valid_ranges
                     .iter()
-                    .any(|&v| self.is_definitely_invalid_value(v))
+                    .cartesian_product(b.valid_ranges.iter())
+                    .filter_map(move |(range_a, range_b)| {
+                        range_a.intersection(range_b).map(|overlap| RangeOverlap {
+                            rules: (a.field.as_str(), b.field.as_str()),
+                            overlap,
+                        })
+                    })
             })
+            .collect()
+    }
+}
+
+// Folds over the nearby tickets one at a time and only keeps track of which
+// rule is valid for which position, so that the tickets need not be stored.
+struct TicketScanner<'a> {
+    rules: &'a [Rule],
+    my_ticket: &'a Ticket,
+    validity: Vec<bool>,
+    error_rate: Value,
+}
+
+impl<'a> TicketScanner<'a> {
+    fn new(rules: &'a [Rule], my_ticket: &'a Ticket) -> Self {
+        Self {
+            rules,
+            my_ticket,
+            validity: vec![true; rules.len() * my_ticket.values.len()],
+            error_rate: 0,
+        }
+    }
+
+    fn n_fields(&self) -> usize {
+        self.my_ticket.values.len()
+    }
+
+    fn add_ticket(&mut self, ticket: &Ticket) {
+        let invalid_values: Vec<Value> = ticket
+            .values
+            .iter()
+            .copied()
+            .filter(|&v| self.rules.iter().all(|rule| !rule.is_valid(v)))
             .collect();
+        if !invalid_values.is_empty() {
+            self.error_rate += invalid_values.iter().sum::<Value>();
+            return;
+        }
 
-        for node in rules.iter().chain(fields.iter()) {
-            match **node {
-                Node::Rule(rule) => {
-                    graph.add_edge(&start, &node);
-
-                    for field_node in &fields {
-                        if let Node::Field(field) = **field_node {
-                            if valid_tickets.iter().all(|t| rule.is_valid(t.values[field])) {
-                                graph.add_edge(&node, &field_node);
-                            }
-                        } else {
-                            unreachable!()
-                        }
-                    }
-                }
-                Node::Field(_) => {
-                    graph.add_edge(&node, &end);
+        let n_fields = self.n_fields();
+        for (i, rule) in self.rules.iter().enumerate() {
+            for (field, &value) in ticket.values.iter().enumerate().take(n_fields) {
+                if !rule.is_valid(value) {
+                    self.validity[i * n_fields + field] = false;
                 }
-                Node::Other(_) => (),
             }
         }
+    }
 
-        let flow = graph.max_flow(&start, &end);
-        rules
-            .iter()
+    fn candidate_fields(&self) -> Vec<Vec<usize>> {
+        let n_fields = self.n_fields();
+        (0..self.rules.len())
             .map(|rule| {
-                if let Node::Field(field) =
-                    **flow.adjancency.get(rule).unwrap().iter().next().unwrap()
-                {
-                    field
-                } else {
-                    unreachable!()
-                }
+                (0..n_fields)
+                    .filter(|&field| self.validity[rule * n_fields + field])
+                    .collect()
             })
             .collect()
     }
 
-    fn departures_product(&self) -> Value {
-        let rules2fields = self.find_rules_to_fields_map();
-        self.rules
+    fn rules_to_fields_map(&self) -> Result<Vec<usize>, AssignmentError> {
+        hopcroft_karp(&self.candidate_fields(), self.n_fields())
+            .into_iter()
+            .zip(self.rules)
+            .map(|(field, rule)| {
+                field.ok_or_else(|| AssignmentError {
+                    rule: rule.field.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn decoded_ticket(&self) -> Result<BTreeMap<&'a str, Value>, AssignmentError> {
+        let rules2fields = self.rules_to_fields_map()?;
+        Ok(self
+            .rules
+            .iter()
+            .zip(rules2fields)
+            .map(|(rule, field)| (rule.field.as_str(), self.my_ticket.values[field]))
+            .collect())
+    }
+
+    // A rule could be assigned to another field under a different maximum
+    // matching if it lies on an alternating path, i.e. a sequence of rules
+    // that could each switch to the field of the next one, that is a cycle or
+    // ends in a rule that could switch to a field without a rule. Returns these
+    // rules in the order of the notes.
+    fn ambiguous_rules(&self) -> Result<Vec<&'a str>, AssignmentError> {
+        let candidate_fields = self.candidate_fields();
+        let rules2fields = self.rules_to_fields_map()?;
+        let mut fields2rules = vec![None; self.n_fields()];
+        for (rule, &field) in rules2fields.iter().enumerate() {
+            fields2rules[field] = Some(rule);
+        }
+
+        // Nodes for the rules and one more for the fields without a rule.
+        let nodes: Vec<Rc<usize>> = (0..=self.rules.len()).map(Rc::new).collect();
+        let free_field = &nodes[self.rules.len()];
+        let mut switch_graph = DirectedGraph::new();
+        for (rule, candidates) in candidate_fields.iter().enumerate() {
+            for &field in candidates.iter().filter(|&&f| f != rules2fields[rule]) {
+                let to = fields2rules[field].map_or(free_field, |other| &nodes[other]);
+                switch_graph.add_edge(&nodes[rule], to);
+            }
+        }
+
+        Ok(nodes[..self.rules.len()]
             .iter()
-            .enumerate()
-            .filter(|(_, r)| r.field.starts_with("departure"))
-            .map(|(i, _)| self.my_ticket.values[rules2fields[i]])
-            .fold(1, |acc, value| acc * value)
+            .filter(|&from| {
+                switch_graph.dfs(from, free_field).is_some()
+                    || switch_graph.adjancency.get(from).is_some_and(|targets| {
+                        targets
+                            .iter()
+                            .any(|to| switch_graph.dfs(to, from).is_some())
+                    })
+            })
+            .map(|rule| self.rules[**rule].field.as_str())
+            .collect())
+    }
+
+    fn field_names(&self) -> Result<Vec<&'a str>, AssignmentError> {
+        let mut field_names = vec![""; self.n_fields()];
+        for (rule, field) in self.rules.iter().zip(self.rules_to_fields_map()?) {
+            field_names[field] = &rule.field;
+        }
+        Ok(field_names)
     }
 }
 
-fn main() {
+fn read_notes_header(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+) -> Result<(Vec<Rule>, Ticket, usize), Box<dyn std::error::Error>> {
+    let mut header = String::new();
+    let mut n_lines = 0;
+    for line in lines {
+        let line = line?;
+        n_lines += 1;
+        if line == "nearby tickets:" {
+            break;
+        }
+        header.push_str(&line);
+        header.push('\n');
+    }
+
+    let (remainder, rules) = parse_rules_section(&header)
+        .map_err(|err| NotesParseError::new(&header, Section::Rules, err))?;
+    let (remainder, my_ticket) = parse_your_ticket_section(remainder)
+        .map_err(|err| NotesParseError::new(&header, Section::YourTicket, err))?;
+    if !remainder.is_empty() {
+        return Err(NotesParseError {
+            section: Section::NearbyTickets,
+            line: n_lines,
+            remainder: String::from(remainder),
+        }
+        .into());
+    }
+    Ok((rules, my_ticket, n_lines))
+}
+
+fn scan_nearby_tickets<'a>(
+    rules: &'a [Rule],
+    my_ticket: &'a Ticket,
+    first_line: usize,
+    lines: impl Iterator<Item = io::Result<String>>,
+) -> Result<TicketScanner<'a>, Box<dyn std::error::Error>> {
+    let mut scanner = TicketScanner::new(rules, my_ticket);
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let ticket = parse_full(Ticket::parse, line.as_str()).map_err(|_| NotesParseError {
+            section: Section::NearbyTickets,
+            line: first_line + i,
+            remainder: line.clone(),
+        })?;
+        scanner.add_ticket(&ticket);
+    }
+    Ok(scanner)
+}
+
+fn departures_product(decoded_ticket: &BTreeMap<&str, Value>) -> Value {
+    decoded_ticket
+        .iter()
+        .filter(|(field, _)| field.starts_with("departure"))
+        .map(|(_, value)| value)
+        .product()
+}
+
+fn print_summary(error_rate: Value, decoded_ticket: &BTreeMap<&str, Value>) {
+    println!("Ticket scanning error rate: {}", error_rate);
+    println!("Departures product: {}", departures_product(decoded_ticket));
+
+    let width = decoded_ticket
+        .keys()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0);
+    println!("Your ticket:");
+    for (name, value) in decoded_ticket {
+        println!("  {:width$} → {}", name, value, width = width);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::Json,
+            _ => Self::Csv,
+        }
+    }
+}
+
+fn csv_quote(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+// Exports the valid nearby tickets with the field names of the assignment
+// found by the scan of the notes.
+fn export_decoded_tickets(
+    notes: &Notes,
+    scanner: &TicketScanner<'_>,
+    writer: &mut impl Write,
+    format: ExportFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let field_names = scanner.field_names()?;
+    match format {
+        ExportFormat::Csv => {
+            let header: Vec<String> = field_names.iter().map(|name| csv_quote(name)).collect();
+            writeln!(writer, "{}", header.join(","))?;
+            for ticket in notes.valid_nearby_tickets() {
+                writeln!(writer, "{}", ticket.values.iter().join(","))?;
+            }
+        }
+        ExportFormat::Json => {
+            let tickets: Vec<serde_json::Map<String, serde_json::Value>> = notes
+                .valid_nearby_tickets()
+                .map(|ticket| {
+                    field_names
+                        .iter()
+                        .map(|name| String::from(*name))
+                        .zip(ticket.values.iter().map(|&v| v.into()))
+                        .collect()
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut *writer, &tickets)?;
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+// Records the spans in chrome://tracing format. The file is completed when
+// the guard is dropped.
+#[cfg(feature = "profile")]
+fn start_profile(path: &str) -> Result<FlushGuard, Box<dyn std::error::Error>> {
+    let (layer, guard) = ChromeLayerBuilder::new().file(path).build();
+    tracing_subscriber::registry().with(layer).init();
+    Ok(guard)
+}
+
+#[cfg(not(feature = "profile"))]
+fn start_profile(_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--profile requires the profile feature.".into())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut export_path = None;
+    let mut dump_dir = None;
+    let mut profile = None;
+    let mut analyze = false;
+    let mut stream = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--analyze" => analyze = true,
+            "--stream" => stream = true,
+            "--export" => {
+                export_path = Some(PathBuf::from(
+                    args.next().ok_or("--export requires a file path.")?,
+                ))
+            }
+            "--dump-intermediate" => {
+                dump_dir = Some(PathBuf::from(
+                    args.next()
+                        .ok_or("--dump-intermediate requires a directory.")?,
+                ))
+            }
+            "--profile" => profile = Some(args.next().ok_or("--profile requires a file.")?),
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+    let _guard = profile.as_deref().map(start_profile).transpose()?;
+
+    if stream {
+        if analyze || export_path.is_some() || dump_dir.is_some() {
+            return Err(
+                "--stream cannot be combined with --analyze, --export, or --dump-intermediate."
+                    .into(),
+            );
+        }
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let (rules, my_ticket, n_header_lines) = read_notes_header(&mut lines)?;
+        let scanner = scan_nearby_tickets(&rules, &my_ticket, n_header_lines + 1, lines)?;
+        print_summary(scanner.error_rate, &scanner.decoded_ticket()?);
+        return Ok(());
+    }
+
     let mut stdin = io::stdin();
     let mut buf = String::new();
-    stdin.read_to_string(&mut buf).unwrap();
-    let (_, notes) = Notes::parse(&buf).unwrap();
-    println!(
-        "Ticket scanning error rate: {}",
-        notes.ticket_scanning_error_rate()
-    );
-    println!("Departures product: {}", notes.departures_product());
+    stdin.read_to_string(&mut buf)?;
+    let notes = {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("parse").entered();
+        Notes::try_from(buf.as_str())?
+    };
+    if let Some(dir) = &dump_dir {
+        dump_intermediate(dir, "notes", &notes)?;
+    }
+    // The scan is shared by all of the reports below.
+    let scanner = {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("solve").entered();
+        let scanner = notes.scan();
+        print_summary(scanner.error_rate, &scanner.decoded_ticket()?);
+        scanner
+    };
+
+    if analyze {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("analyze").entered();
+        println!("Overlapping rule ranges:");
+        for RangeOverlap { rules, overlap } in notes.range_overlaps() {
+            println!("  {} / {}: {}", rules.0, rules.1, overlap);
+        }
+        let ambiguous_rules = scanner.ambiguous_rules()?;
+        if ambiguous_rules.is_empty() {
+            println!("The field assignment is unique.");
+        } else {
+            println!(
+                "The field assignment is ambiguous for the rules: {}",
+                ambiguous_rules.join(", ")
+            );
+        }
+    } else {
+        let ambiguous_rules = scanner.ambiguous_rules()?;
+        if !ambiguous_rules.is_empty() {
+            eprintln!(
+                "Warning: the field assignment is ambiguous for the rules: {}",
+                ambiguous_rules.join(", ")
+            );
+        }
+    }
+
+    if let Some(path) = export_path {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("export").entered();
+        let mut writer = BufWriter::new(File::create(&path)?);
+        export_decoded_tickets(
+            &notes,
+            &scanner,
+            &mut writer,
+            ExportFormat::from_path(&path),
+        )?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -277,7 +754,7 @@ mod tests {
 
     #[test]
     fn test_parse_value() {
-        let (_, value) = Value::parse("123").unwrap();
+        let (_, value) = parse_value("123").unwrap();
         assert_eq!(value, 123);
     }
 
@@ -285,6 +762,40 @@ mod tests {
     fn test_parse_inclusive_range() {
         let (_, range) = InclusiveRange::parse("12-24").unwrap();
         assert_eq!(range, InclusiveRange(12, 24));
+        let (_, range) = InclusiveRange::parse(">=13").unwrap();
+        assert_eq!(range, InclusiveRange(13, Value::MAX));
+        let (_, range) = InclusiveRange::parse("<=5").unwrap();
+        assert_eq!(range, InclusiveRange(0, 5));
+        let (_, range) = InclusiveRange::parse("7").unwrap();
+        assert_eq!(range, InclusiveRange(7, 7));
+    }
+
+    #[test]
+    fn test_display_inclusive_range() {
+        assert_eq!(InclusiveRange(12, 24).to_string(), "12-24");
+        assert_eq!(InclusiveRange(13, Value::MAX).to_string(), ">=13");
+        assert_eq!(InclusiveRange(7, 7).to_string(), "7");
+        assert_eq!(InclusiveRange(0, 5).to_string(), "<=5");
+    }
+
+    #[test]
+    fn test_display_inclusive_range_roundtrip() {
+        for range in &[
+            InclusiveRange(12, 24),
+            InclusiveRange(13, Value::MAX),
+            InclusiveRange(0, 5),
+            InclusiveRange(7, 7),
+            InclusiveRange(0, 0),
+            InclusiveRange(0, Value::MAX),
+        ] {
+            let printed = range.to_string();
+            assert_eq!(
+                InclusiveRange::parse(&printed),
+                Ok(("", range.clone())),
+                "{}",
+                printed
+            );
+        }
     }
 
     #[test]
@@ -297,6 +808,20 @@ mod tests {
                 valid_ranges: vec![(1, 3).into(), (23, 42).into()]
             }
         );
+
+        let (_, rule) = Rule::parse("wagon: 1-3 or 7 or 10-12 or >=50").unwrap();
+        assert_eq!(
+            rule.valid_ranges,
+            vec![
+                (1, 3).into(),
+                (7, 7).into(),
+                (10, 12).into(),
+                (50, Value::MAX).into()
+            ]
+        );
+        for &(value, is_valid) in &[(2, true), (5, false), (7, true), (13, false), (99, true)] {
+            assert_eq!(rule.is_valid(value), is_valid);
+        }
     }
 
     #[test]
@@ -306,8 +831,223 @@ mod tests {
         assert_eq!(parsed_notes, notes());
     }
 
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Notes::try_from(INPUT).unwrap(), notes());
+    }
+
+    #[test]
+    fn test_serialize_notes() {
+        assert_eq!(
+            serde_json::to_value(notes()).unwrap(),
+            serde_json::json!({
+                "rules": [
+                    {"field": "class", "valid_ranges": [[1, 3], [5, 7]]},
+                    {"field": "row", "valid_ranges": [[6, 11], [33, 44]]},
+                    {"field": "seat", "valid_ranges": [[13, 40], [45, 50]]},
+                ],
+                "my_ticket": [7, 1, 14],
+                "nearby_tickets": [[7, 3, 47], [40, 4, 50], [55, 2, 20], [38, 6, 12]],
+            })
+        );
+    }
+
     #[test]
     fn test_ticket_scanning_error_rate() {
-        assert_eq!(notes().ticket_scanning_error_rate(), 71);
+        assert_eq!(notes().scan().error_rate, 71);
+    }
+
+    #[test]
+    fn test_decoded_ticket() {
+        let input = "\
+            class: 0-1 or 4-19\n\
+            row: 0-5 or 8-19\n\
+            seat: 0-13 or 16-19\n\
+            \n\
+            your ticket:\n\
+            11,12,13\n\
+            \n\
+            nearby tickets:\n\
+            3,9,18\n\
+            15,1,5\n\
+            5,14,9";
+        let (_, notes) = Notes::parse(input).unwrap();
+        assert_eq!(
+            notes.scan().decoded_ticket(),
+            Ok(vec![("class", 12), ("row", 11), ("seat", 13)]
+                .into_iter()
+                .collect())
+        );
+    }
+
+    #[test]
+    fn test_export_decoded_tickets_as_csv() {
+        let mut buf = vec![];
+        let notes = notes();
+        export_decoded_tickets(&notes, &notes.scan(), &mut buf, ExportFormat::Csv).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "row,class,seat\n7,3,47\n");
+    }
+
+    #[test]
+    fn test_export_decoded_tickets_as_json() {
+        let mut buf = vec![];
+        let notes = notes();
+        export_decoded_tickets(&notes, &notes.scan(), &mut buf, ExportFormat::Json).unwrap();
+        let exported: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(
+            exported,
+            serde_json::json!([{"row": 7, "class": 3, "seat": 47}])
+        );
+    }
+
+    #[test]
+    fn test_parse_error_in_rules_section() {
+        let input = INPUT.replace("row: 6-11", "row: 6-x");
+        let err = Notes::try_from(input.as_str()).unwrap_err();
+        assert_eq!(err.section, Section::Rules);
+        assert_eq!(err.line, 2);
+        assert!(err.remainder.starts_with("6-x or 33-44\n"));
+    }
+
+    #[test]
+    fn test_parse_error_in_your_ticket_section() {
+        let input = INPUT.replace("your ticket:", "my ticket:");
+        let err = Notes::try_from(input.as_str()).unwrap_err();
+        assert_eq!(err.section, Section::YourTicket);
+        assert_eq!(err.line, 5);
+        assert!(err.remainder.starts_with("my ticket:"));
+    }
+
+    #[test]
+    fn test_parse_error_in_nearby_tickets_section() {
+        let input = INPUT.replace("55,2,20", "55,2;20");
+        let err = Notes::try_from(input.as_str()).unwrap_err();
+        assert_eq!(err.section, Section::NearbyTickets);
+        assert_eq!(err.line, 11);
+        assert_eq!(err.remainder, ";20\n38,6,12");
+    }
+
+    #[test]
+    fn test_range_overlaps() {
+        assert_eq!(
+            notes().range_overlaps(),
+            vec![
+                RangeOverlap {
+                    rules: ("class", "row"),
+                    overlap: InclusiveRange(6, 7),
+                },
+                RangeOverlap {
+                    rules: ("row", "seat"),
+                    overlap: InclusiveRange(33, 40),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_rules() {
+        let input = "\
+            class: 0-1 or 4-19\n\
+            row: 0-5 or 8-19\n\
+            seat: 0-13 or 16-19\n\
+            \n\
+            your ticket:\n\
+            11,12,13\n\
+            \n\
+            nearby tickets:\n\
+            3,9,18\n\
+            15,1,5\n";
+        let (_, notes) = Notes::parse(&format!("{}5,14,9", input)).unwrap();
+        assert_eq!(notes.scan().ambiguous_rules(), Ok(vec![]));
+        let (_, notes) = Notes::parse(input).unwrap();
+        assert_eq!(notes.scan().ambiguous_rules(), Ok(vec!["class", "seat"]));
+    }
+
+    #[test]
+    fn test_more_fields_than_rules() {
+        let input = "\
+            class: 0-1 or 4-19\n\
+            row: 0-5 or 8-19\n\
+            \n\
+            your ticket:\n\
+            11,12,13\n\
+            \n\
+            nearby tickets:\n\
+            3,6,7\n\
+            15,1,5\n\
+            5,14,9";
+        let (_, notes) = Notes::parse(input).unwrap();
+        let scanner = notes.scan();
+        assert_eq!(scanner.decoded_ticket().unwrap()["row"], 11);
+        // class can take either of the fields that no other rule is assigned to.
+        assert_eq!(scanner.ambiguous_rules(), Ok(vec!["class"]));
+    }
+
+    #[test]
+    fn test_no_valid_assignment() {
+        let input = "\
+            class: 0-1\n\
+            row: 0-1\n\
+            seat: 4-5\n\
+            \n\
+            your ticket:\n\
+            1,5,5\n\
+            \n\
+            nearby tickets:\n\
+            1,5,5";
+        let (_, notes) = Notes::parse(input).unwrap();
+        let scanner = notes.scan();
+        assert_eq!(
+            scanner.decoded_ticket(),
+            Err(AssignmentError {
+                rule: String::from("row")
+            })
+        );
+        assert!(scanner.ambiguous_rules().is_err());
+    }
+
+    #[test]
+    fn test_streaming_matches_notes() {
+        let input = "\
+            class: 0-1 or 4-19\n\
+            row: 0-5 or 8-19\n\
+            seat: 0-13 or 16-19\n\
+            \n\
+            your ticket:\n\
+            11,12,13\n\
+            \n\
+            nearby tickets:\n\
+            3,9,18\n\
+            15,1,5\n\
+            5,14,9\n\
+            20,1,5\n";
+        let notes = Notes::try_from(input).unwrap();
+
+        let mut lines = input.as_bytes().lines();
+        let (rules, my_ticket, n_header_lines) = read_notes_header(&mut lines).unwrap();
+        assert_eq!(rules, notes.rules);
+        assert_eq!(my_ticket, notes.my_ticket);
+        assert_eq!(n_header_lines, 8);
+
+        let scanner = scan_nearby_tickets(&rules, &my_ticket, n_header_lines + 1, lines).unwrap();
+        assert_eq!(scanner.error_rate, 20);
+        assert_eq!(scanner.error_rate, notes.scan().error_rate);
+        assert_eq!(scanner.candidate_fields(), notes.scan().candidate_fields());
+        assert_eq!(scanner.decoded_ticket(), notes.scan().decoded_ticket());
+        assert!(scanner.decoded_ticket().is_ok());
+    }
+
+    #[test]
+    fn test_streaming_reports_invalid_ticket_line() {
+        let input = "class: 1-3\n\nyour ticket:\n1\n\nnearby tickets:\n2\nx\n";
+        let mut lines = input.as_bytes().lines();
+        let (rules, my_ticket, n_header_lines) = read_notes_header(&mut lines).unwrap();
+        let err = scan_nearby_tickets(&rules, &my_ticket, n_header_lines + 1, lines)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid nearby tickets section in line 8, could not parse 'x'."
+        );
     }
 }
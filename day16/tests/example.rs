@@ -0,0 +1,27 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day16"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(
+        run("example.txt", &[]),
+        "Ticket scanning error rate: 0\n\
+         Departures product: 1\n\
+         Your ticket:\n  \
+         class → 12\n  \
+         row   → 11\n  \
+         seat  → 13\n"
+    );
+}
+
+#[test]
+fn test_invalid_tickets() {
+    let output = run("invalid_tickets.txt", &[]);
+    assert!(output.starts_with("Ticket scanning error rate: 71\n"));
+}
@@ -0,0 +1,368 @@
+use super::{bounding_box, Idx3};
+use life::{LifeEngine, Rules};
+use std::collections::{HashMap, HashSet};
+
+type NodeId = usize;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// A node of level k is a cube with a side length of 2^k cells. Children are
+// indexed by the bits (x, y, z) of their position, x being the most significant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    Leaf(bool),
+    Branch { level: u32, children: [NodeId; 8] },
+}
+
+fn child_index(x: usize, y: usize, z: usize) -> usize {
+    x << 2 | y << 1 | z
+}
+
+// Octree based implementation of the Hashlife algorithm. Identical sub-cubes
+// are stored only once and the evolution of each node is memoized, so that
+// repetitive patterns can be advanced by an exponential number of steps.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    populations: Vec<usize>,
+    ids: HashMap<Node, NodeId>,
+    empty: Vec<NodeId>,
+    results: HashMap<(NodeId, u32), NodeId>,
+    rules: Rules,
+    root: NodeId,
+    origin: Idx3,
+}
+
+impl HashLife {
+    pub fn new(active: &HashSet<Idx3>, rules: &Rules) -> Self {
+        let mut hashlife = Self {
+            nodes: vec![],
+            populations: vec![],
+            ids: HashMap::new(),
+            empty: vec![],
+            results: HashMap::new(),
+            rules: rules.clone(),
+            root: DEAD,
+            origin: (0, 0, 0),
+        };
+        hashlife.intern(Node::Leaf(false), 0);
+        hashlife.intern(Node::Leaf(true), 1);
+        hashlife.empty.push(DEAD);
+
+        let (lb, ub) = bounding_box(active).unwrap_or(((0, 0, 0), (0, 0, 0)));
+        let extent = (ub.0 - lb.0).max(ub.1 - lb.1).max(ub.2 - lb.2) + 1;
+        let mut level = 1;
+        while (1 << level) < extent {
+            level += 1;
+        }
+        hashlife.origin = lb;
+        hashlife.root = hashlife.build(active, lb, level);
+        hashlife
+    }
+
+    fn intern(&mut self, node: Node, population: usize) -> NodeId {
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.populations.push(population);
+        self.ids.insert(node, id);
+        id
+    }
+
+    fn branch(&mut self, children: [NodeId; 8]) -> NodeId {
+        let level = self.level(children[0]) + 1;
+        let population = children.iter().map(|&c| self.populations[c]).sum();
+        self.intern(Node::Branch { level, children }, population)
+    }
+
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let child = *self.empty.last().unwrap();
+            let empty = self.branch([child; 8]);
+            self.empty.push(empty);
+        }
+        self.empty[level as usize]
+    }
+
+    fn level(&self, id: NodeId) -> u32 {
+        match self.nodes[id] {
+            Node::Leaf(_) => 0,
+            Node::Branch { level, .. } => level,
+        }
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 8] {
+        match self.nodes[id] {
+            Node::Leaf(_) => panic!("Leaf nodes have no children."),
+            Node::Branch { children, .. } => children,
+        }
+    }
+
+    fn build(&mut self, active: &HashSet<Idx3>, origin: Idx3, level: u32) -> NodeId {
+        if level == 0 {
+            return if active.contains(&origin) {
+                ALIVE
+            } else {
+                DEAD
+            };
+        }
+        let half = 1 << (level - 1);
+        let mut children = [DEAD; 8];
+        for (i, child) in children.iter_mut().enumerate() {
+            let offset = (
+                origin.0 + half * (i as i64 >> 2 & 1),
+                origin.1 + half * (i as i64 >> 1 & 1),
+                origin.2 + half * (i as i64 & 1),
+            );
+            *child = self.build(active, offset, level - 1);
+        }
+        self.branch(children)
+    }
+
+    fn grandchildren(&self, id: NodeId) -> [[[NodeId; 4]; 4]; 4] {
+        let mut grid = [[[DEAD; 4]; 4]; 4];
+        let children = self.children(id);
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    let child = children[child_index(x / 2, y / 2, z / 2)];
+                    grid[x][y][z] = self.children(child)[child_index(x % 2, y % 2, z % 2)];
+                }
+            }
+        }
+        grid
+    }
+
+    fn subcube<const N: usize>(
+        &mut self,
+        grid: &[[[NodeId; N]; N]; N],
+        x: usize,
+        y: usize,
+        z: usize,
+    ) -> NodeId {
+        let mut children = [DEAD; 8];
+        for (i, child) in children.iter_mut().enumerate() {
+            *child = grid[x + (i >> 2 & 1)][y + (i >> 1 & 1)][z + (i & 1)];
+        }
+        self.branch(children)
+    }
+
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let grid = self.grandchildren(id);
+        self.subcube(&grid, 1, 1, 1)
+    }
+
+    fn base_step(&mut self, id: NodeId) -> NodeId {
+        let grid = self.grandchildren(id);
+        let is_alive = |x: usize, y: usize, z: usize| grid[x][y][z] == ALIVE;
+        let mut children = [DEAD; 8];
+        for (i, child) in children.iter_mut().enumerate() {
+            let (x, y, z) = (1 + (i >> 2 & 1), 1 + (i >> 1 & 1), 1 + (i & 1));
+            let mut n_neighbours_active = 0;
+            for nx in x - 1..=x + 1 {
+                for ny in y - 1..=y + 1 {
+                    for nz in z - 1..=z + 1 {
+                        if (nx, ny, nz) != (x, y, z) && is_alive(nx, ny, nz) {
+                            n_neighbours_active += 1;
+                        }
+                    }
+                }
+            }
+            if self
+                .rules
+                .is_active_next(is_alive(x, y, z), n_neighbours_active)
+            {
+                *child = ALIVE;
+            }
+        }
+        self.branch(children)
+    }
+
+    // Returns the center cube with half the side length of the node after
+    // 2^step_log steps. Requires step_log <= level - 2.
+    fn result(&mut self, id: NodeId, step_log: u32) -> NodeId {
+        if let Some(&result) = self.results.get(&(id, step_log)) {
+            return result;
+        }
+        let level = self.level(id);
+        let result = if self.populations[id] == 0 {
+            self.empty(level - 1)
+        } else if level == 2 {
+            self.base_step(id)
+        } else {
+            let grid = self.grandchildren(id);
+            let mut intermediate = [[[DEAD; 3]; 3]; 3];
+            for (x, plane) in intermediate.iter_mut().enumerate() {
+                for (y, row) in plane.iter_mut().enumerate() {
+                    for (z, node) in row.iter_mut().enumerate() {
+                        let subcube = self.subcube(&grid, x, y, z);
+                        *node = if step_log == level - 2 {
+                            self.result(subcube, level - 3)
+                        } else {
+                            self.center(subcube)
+                        };
+                    }
+                }
+            }
+            let mut children = [DEAD; 8];
+            for (i, child) in children.iter_mut().enumerate() {
+                let subcube = self.subcube(&intermediate, i >> 2 & 1, i >> 1 & 1, i & 1);
+                *child = self.result(subcube, step_log.min(level - 3));
+            }
+            self.branch(children)
+        };
+        self.results.insert((id, step_log), result);
+        result
+    }
+
+    fn expand(&mut self) {
+        let level = self.level(self.root);
+        let empty = self.empty(level - 1);
+        let root_children = self.children(self.root);
+        let mut children = [DEAD; 8];
+        for (i, child) in children.iter_mut().enumerate() {
+            let mut grandchildren = [empty; 8];
+            grandchildren[7 - i] = root_children[i];
+            *child = self.branch(grandchildren);
+        }
+        self.root = self.branch(children);
+        let half = 1 << (level - 1);
+        self.origin = (
+            self.origin.0 - half,
+            self.origin.1 - half,
+            self.origin.2 - half,
+        );
+    }
+
+    // The pattern has to fit into the central quarter of the root, so that
+    // it cannot grow out of the result within 2^step_log steps.
+    fn step(&mut self, step_log: u32) {
+        while self.level(self.root) < step_log + 3 {
+            self.expand();
+        }
+        loop {
+            let inner = self.center(self.root);
+            let inner = self.center(inner);
+            if self.populations[inner] == self.populations[self.root] {
+                break;
+            }
+            self.expand();
+        }
+        let quarter = 1 << (self.level(self.root) - 2);
+        self.root = self.result(self.root, step_log);
+        self.origin = (
+            self.origin.0 + quarter,
+            self.origin.1 + quarter,
+            self.origin.2 + quarter,
+        );
+    }
+
+    fn set_rules(&mut self, rules: &Rules) {
+        if *rules != self.rules {
+            self.rules = rules.clone();
+            self.results.clear();
+        }
+    }
+
+    fn collect_active(&self, id: NodeId, origin: Idx3, active: &mut HashSet<Idx3>) {
+        if self.populations[id] == 0 {
+            return;
+        }
+        match self.nodes[id] {
+            Node::Leaf(_) => {
+                active.insert(origin);
+            }
+            Node::Branch { level, children } => {
+                let half = 1 << (level - 1);
+                for (i, &child) in children.iter().enumerate() {
+                    let offset = (
+                        origin.0 + half * (i as i64 >> 2 & 1),
+                        origin.1 + half * (i as i64 >> 1 & 1),
+                        origin.2 + half * (i as i64 & 1),
+                    );
+                    self.collect_active(child, offset, active);
+                }
+            }
+        }
+    }
+}
+
+impl LifeEngine for HashLife {
+    type Cell = Idx3;
+
+    fn next_state(mut self, rules: &Rules) -> Self {
+        self.set_rules(rules);
+        self.step(0);
+        self
+    }
+
+    fn advance(mut self, n_cycles: usize, rules: &Rules) -> Self {
+        self.set_rules(rules);
+        for step_log in 0..usize::BITS {
+            if n_cycles >> step_log & 1 == 1 {
+                self.step(step_log);
+            }
+        }
+        self
+    }
+
+    fn active_cells(&self) -> HashSet<Idx3> {
+        let mut active = HashSet::with_capacity(self.n_active());
+        self.collect_active(self.root, self.origin, &mut active);
+        active
+    }
+
+    fn n_active(&self) -> usize {
+        self.populations[self.root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConwayCube;
+    use std::convert::TryFrom;
+
+    static INPUT: &str = "\
+            .#.\n\
+            ..#\n\
+            ###\n";
+
+    #[test]
+    fn test_build_roundtrip() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        let hashlife = HashLife::new(&cube.active, &Rules::default());
+        assert_eq!(hashlife.active_cells(), cube.active);
+    }
+
+    #[test]
+    fn test_single_steps_match_sparse() {
+        let mut sparse = ConwayCube::try_from(INPUT).unwrap();
+        let mut hashlife = HashLife::new(&sparse.active, &Rules::default());
+        for _ in 0..6 {
+            sparse = sparse.next_state(&Rules::default());
+            hashlife = hashlife.next_state(&Rules::default());
+            assert_eq!(hashlife.active_cells(), sparse.active);
+        }
+        assert_eq!(hashlife.n_active(), 112);
+    }
+
+    #[test]
+    fn test_advance_matches_sparse() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        let hashlife = HashLife::new(&cube.active, &Rules::default());
+        let sparse = cube.advance(13, &Rules::default());
+        let hashlife = hashlife.advance(13, &Rules::default());
+        assert_eq!(hashlife.active_cells(), sparse.active);
+    }
+
+    #[test]
+    fn test_advance_stable_pattern_far() {
+        let cube = ConwayCube::try_from("##\n##\n").unwrap();
+        let hashlife = HashLife::new(&cube.active, &Rules::default());
+        let hashlife = hashlife.advance(1 << 40, &Rules::default());
+        assert_eq!(hashlife.active_cells(), cube.active);
+    }
+}
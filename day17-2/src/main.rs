@@ -1,7 +1,11 @@
+use life::{rle, sparse, LifeEngine, Rules};
 use std::cmp::{max, min};
-use std::collections::HashSet;
-use std::convert::{TryFrom, TryInto};
-use std::io::{self, Read};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
 use std::num::TryFromIntError;
 
 type Idx3 = (i64, i64, i64, i64);
@@ -9,8 +13,6 @@ type Idx3 = (i64, i64, i64, i64);
 #[derive(Debug, PartialEq)]
 struct ConwayCube {
     active: HashSet<Idx3>,
-    lower_bounds: Idx3,
-    upper_bounds: Idx3,
 }
 
 #[derive(Debug, PartialEq)]
@@ -19,6 +21,17 @@ enum ConwayCubeParseError {
     OutOfRange(TryFromIntError),
 }
 
+impl Display for ConwayCubeParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::InvalidCharacter(c) => write!(f, "Invalid character '{}' in cube.", c),
+            Self::OutOfRange(err) => write!(f, "Cube is too large: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConwayCubeParseError {}
+
 impl TryFrom<&str> for ConwayCube {
     type Error = ConwayCubeParseError;
 
@@ -44,20 +57,70 @@ impl TryFrom<&str> for ConwayCube {
                 })
             })
             .collect::<Result<HashSet<Idx3>, ConwayCubeParseError>>()?;
-        let bounds = active
-            .iter()
-            .fold(((0, 0, 0, 0), (0, 0, 0, 0)), Self::extend_bounds);
-        Ok(Self {
-            active,
-            lower_bounds: bounds.0,
-            upper_bounds: bounds.1,
-        })
+        Ok(Self { active })
     }
 }
 
+// Names of the coordinates along which the cube is sliced into patterns.
+const SLICE_COORDS: [&str; 2] = ["z", "w"];
+
 impl ConwayCube {
-    fn extend_bounds(bounds: (Idx3, Idx3), p: &Idx3) -> (Idx3, Idx3) {
-        let (lb, ub) = bounds;
+    fn from_rle(input: &str) -> Result<Self, rle::RleParseError> {
+        let active = rle::parse(input, &SLICE_COORDS)?
+            .into_iter()
+            .flat_map(|slice| {
+                let (z, w) = (slice.coords[0], slice.coords[1]);
+                slice.cells.into_iter().map(move |(x, y)| (x, y, z, w))
+            })
+            .collect();
+        Ok(Self { active })
+    }
+
+    fn to_rle(&self) -> String {
+        let mut output = String::new();
+        if let Some((lb, ub)) = bounding_box(&self.active) {
+            let slices: Vec<rle::Slice> = (lb.3..=ub.3)
+                .flat_map(|w| (lb.2..=ub.2).map(move |z| (z, w)))
+                .map(|(z, w)| rle::Slice {
+                    coords: vec![z, w],
+                    cells: self
+                        .active
+                        .iter()
+                        .filter(|p| (p.2, p.3) == (z, w))
+                        .map(|p| (p.0, p.1))
+                        .collect(),
+                })
+                .collect();
+            let shape = (ub.0 - lb.0 + 1, ub.1 - lb.1 + 1);
+            rle::write(&mut output, &slices, &SLICE_COORDS, (lb.0, lb.1), shape)
+                .expect("Writing to a string does not fail.");
+        }
+        output
+    }
+}
+
+fn positions(lower_bounds: Idx3, upper_bounds: Idx3) -> impl Iterator<Item = Idx3> {
+    let (lb, ub) = (lower_bounds, upper_bounds);
+    (lb.0..=ub.0).flat_map(move |x| {
+        (lb.1..=ub.1).flat_map(move |y| {
+            (lb.2..=ub.2).flat_map(move |z| (lb.3..=ub.3).map(move |zz| (x, y, z, zz)))
+        })
+    })
+}
+
+fn neighbours(p: &Idx3) -> impl Iterator<Item = Idx3> {
+    let p = *p;
+    positions(
+        (p.0 - 1, p.1 - 1, p.2 - 1, p.3 - 1),
+        (p.0 + 1, p.1 + 1, p.2 + 1, p.3 + 1),
+    )
+    .filter(move |neighbour| *neighbour != p)
+}
+
+fn bounding_box(active: &HashSet<Idx3>) -> Option<(Idx3, Idx3)> {
+    let mut active = active.iter();
+    let first = *active.next()?;
+    Some(active.fold((first, first), |(lb, ub), p| {
         (
             (
                 min(lb.0, p.0),
@@ -72,72 +135,337 @@ impl ConwayCube {
                 max(ub.3, p.3),
             ),
         )
-    }
+    }))
+}
 
-    fn neighbours(p: &Idx3) -> [Idx3; 80] {
-        (-1..=1)
-            .flat_map(|x| {
-                (-1..=1).flat_map(move |y| {
-                    (-1..=1).flat_map(move |z| {
-                        (-1..=1).map(move |zz| (p.0 + x, p.1 + y, p.2 + z, p.3 + zz))
-                    })
-                })
-            })
-            .filter(|neighbour| neighbour != p)
-            .collect::<Vec<Idx3>>()
-            .try_into()
-            .unwrap()
+fn bounding_box_volume(active: &HashSet<Idx3>) -> i64 {
+    bounding_box(active).map_or(0, |(lb, ub)| {
+        (ub.0 - lb.0 + 1) * (ub.1 - lb.1 + 1) * (ub.2 - lb.2 + 1) * (ub.3 - lb.3 + 1)
+    })
+}
+
+impl LifeEngine for ConwayCube {
+    type Cell = Idx3;
+
+    fn next_state(self, rules: &Rules) -> Self {
+        Self {
+            active: sparse::next_generation(&self.active, neighbours, rules),
+        }
     }
 
-    fn new(capacity: usize) -> Self {
+    fn advance_observed(
+        self,
+        n_cycles: usize,
+        rules: &Rules,
+        observe: &mut dyn FnMut(usize, usize),
+    ) -> Self {
         Self {
-            active: HashSet::with_capacity(capacity),
-            lower_bounds: (0, 0, 0, 0),
-            upper_bounds: (0, 0, 0, 0),
+            active: sparse::advance_observed(
+                self.active,
+                n_cycles,
+                neighbours,
+                rules,
+                &mut |cycle, active| observe(cycle, active.len()),
+            ),
         }
     }
 
-    fn activate(&mut self, p: &Idx3) {
-        self.active.insert(*p);
-        let bounds = Self::extend_bounds((self.lower_bounds, self.upper_bounds), p);
-        self.lower_bounds = bounds.0;
-        self.upper_bounds = bounds.1;
-    }
-
-    fn next_state(self) -> Self {
-        let mut state = ConwayCube::new(self.active.len());
-        for x in self.lower_bounds.0 - 1..=self.upper_bounds.0 + 1 {
-            for y in self.lower_bounds.1 - 1..=self.upper_bounds.1 + 1 {
-                for z in self.lower_bounds.2 - 1..=self.upper_bounds.2 + 1 {
-                    for zz in self.lower_bounds.3 - 1..=self.upper_bounds.3 + 1 {
-                        let p = (x, y, z, zz);
-                        let n_neighbours_active = Self::neighbours(&p)
-                            .iter()
-                            .filter(|p| self.active.contains(p))
-                            .count();
-                        if self.active.contains(&p)
-                            && n_neighbours_active >= 2
-                            && n_neighbours_active <= 3
-                        {
-                            state.activate(&p);
-                        } else if !self.active.contains(&p) && n_neighbours_active == 3 {
-                            state.activate(&p);
-                        }
+    fn active_cells(&self) -> HashSet<Idx3> {
+        self.active.clone()
+    }
+
+    fn n_active(&self) -> usize {
+        self.active.len()
+    }
+}
+
+impl Display for ConwayCube {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if let Some((lb, ub)) = bounding_box(&self.active) {
+            for (zz, z) in (lb.3..=ub.3).flat_map(|zz| (lb.2..=ub.2).map(move |z| (zz, z))) {
+                if (zz, z) > (lb.3, lb.2) {
+                    writeln!(f)?;
+                }
+                writeln!(f, "z={}, w={}", z, zz)?;
+                for x in lb.0..=ub.0 {
+                    for y in lb.1..=ub.1 {
+                        f.write_str(if self.active.contains(&(x, y, z, zz)) {
+                            "#"
+                        } else {
+                            "."
+                        })?;
                     }
+                    writeln!(f)?;
                 }
             }
         }
-        state
+        Ok(())
+    }
+}
+
+// Stores every cell of the bounding box, which avoids hashing for small,
+// densely populated simulations. The box grows by one cell per side per step.
+#[derive(Debug, PartialEq)]
+struct DenseConwayCube {
+    lower_bounds: Idx3,
+    upper_bounds: Idx3,
+    cells: Vec<bool>,
+}
+
+impl From<&ConwayCube> for DenseConwayCube {
+    fn from(cube: &ConwayCube) -> Self {
+        let (lower_bounds, upper_bounds) =
+            bounding_box(&cube.active).unwrap_or(((0, 0, 0, 0), (-1, -1, -1, -1)));
+        let cells = positions(lower_bounds, upper_bounds)
+            .map(|p| cube.active.contains(&p))
+            .collect();
+        Self {
+            lower_bounds,
+            upper_bounds,
+            cells,
+        }
+    }
+}
+
+impl DenseConwayCube {
+    fn index(&self, p: &Idx3) -> Option<usize> {
+        let (lb, ub) = (self.lower_bounds, self.upper_bounds);
+        if p.0 < lb.0
+            || p.0 > ub.0
+            || p.1 < lb.1
+            || p.1 > ub.1
+            || p.2 < lb.2
+            || p.2 > ub.2
+            || p.3 < lb.3
+            || p.3 > ub.3
+        {
+            return None;
+        }
+        let shape = (ub.1 - lb.1 + 1, ub.2 - lb.2 + 1, ub.3 - lb.3 + 1);
+        let idx = (((p.0 - lb.0) * shape.0 + (p.1 - lb.1)) * shape.1 + (p.2 - lb.2)) * shape.2
+            + (p.3 - lb.3);
+        usize::try_from(idx).ok()
+    }
+
+    fn is_active(&self, p: &Idx3) -> bool {
+        self.index(p).is_some_and(|i| self.cells[i])
+    }
+}
+
+impl LifeEngine for DenseConwayCube {
+    type Cell = Idx3;
+
+    fn next_state(self, rules: &Rules) -> Self {
+        let (lb, ub) = (self.lower_bounds, self.upper_bounds);
+        let lower_bounds = (lb.0 - 1, lb.1 - 1, lb.2 - 1, lb.3 - 1);
+        let upper_bounds = (ub.0 + 1, ub.1 + 1, ub.2 + 1, ub.3 + 1);
+        let cells = positions(lower_bounds, upper_bounds)
+            .map(|p| {
+                let n_neighbours_active = neighbours(&p).filter(|n| self.is_active(n)).count();
+                rules.is_active_next(self.is_active(&p), n_neighbours_active)
+            })
+            .collect();
+        Self {
+            lower_bounds,
+            upper_bounds,
+            cells,
+        }
     }
+
+    fn active_cells(&self) -> HashSet<Idx3> {
+        positions(self.lower_bounds, self.upper_bounds)
+            .zip(self.cells.iter())
+            .filter(|(_, &active)| active)
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    fn n_active(&self) -> usize {
+        self.cells.iter().filter(|&&active| active).count()
+    }
+}
+
+fn run<E: LifeEngine<Cell = Idx3>>(
+    engine: E,
+    n_cycles: usize,
+    rules: &Rules,
+    show_slices: bool,
+) -> usize {
+    let show = |engine: &E, heading: &str| {
+        if show_slices {
+            let cube = ConwayCube {
+                active: engine.active_cells(),
+            };
+            println!("{}:\n\n{}", heading, cube);
+        }
+    };
+    show(&engine, "Before any cycles");
+    (1..=n_cycles)
+        .fold(engine, |engine, cycle| {
+            let engine = engine.next_state(rules);
+            let plural = if cycle == 1 { "" } else { "s" };
+            show(&engine, &format!("After {} cycle{}", cycle, plural));
+            engine
+        })
+        .n_active()
 }
 
-fn main() {
+// Cells are translated so that the bounding box starts at the origin, so that
+// patterns moving through space are recognized as repeating, too.
+fn normalized_state(active: &HashSet<Idx3>) -> Vec<Idx3> {
+    let mut state: Vec<Idx3> = match bounding_box(active) {
+        Some((lb, _)) => active
+            .iter()
+            .map(|p| (p.0 - lb.0, p.1 - lb.1, p.2 - lb.2, p.3 - lb.3))
+            .collect(),
+        None => vec![],
+    };
+    state.sort_unstable();
+    state
+}
+
+#[derive(Debug, PartialEq)]
+struct Cycle {
+    start: usize,
+    period: usize,
+}
+
+fn find_cycle<E: LifeEngine<Cell = Idx3>>(
+    engine: E,
+    max_cycles: usize,
+    rules: &Rules,
+) -> Option<Cycle> {
+    let mut seen = HashMap::new();
+    let mut engine = engine;
+    for step in 0..=max_cycles {
+        if let Some(start) = seen.insert(normalized_state(&engine.active_cells()), step) {
+            return Some(Cycle {
+                start,
+                period: step - start,
+            });
+        }
+        engine = engine.next_state(rules);
+    }
+    None
+}
+
+// Writes the number of active cells and the volume of their bounding box for
+// each generation including the initial one. Returns the final number of
+// active cells.
+fn write_population_csv<E: LifeEngine<Cell = Idx3>>(
+    engine: E,
+    n_cycles: usize,
+    rules: &Rules,
+    out: &mut impl Write,
+) -> io::Result<usize> {
+    writeln!(out, "generation,active,volume")?;
+    let mut engine = engine;
+    for generation in 0..=n_cycles {
+        if generation > 0 {
+            engine = engine.next_state(rules);
+        }
+        let active = engine.active_cells();
+        writeln!(
+            out,
+            "{},{},{}",
+            generation,
+            active.len(),
+            bounding_box_volume(&active)
+        )?;
+    }
+    Ok(engine.n_active())
+}
+
+fn final_state<E: LifeEngine<Cell = Idx3>>(
+    engine: E,
+    n_cycles: usize,
+    rules: &Rules,
+) -> ConwayCube {
+    let engine = (0..n_cycles).fold(engine, |engine, _| engine.next_state(rules));
+    ConwayCube {
+        active: engine.active_cells(),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut n_cycles = 6;
+    let mut rules = Rules::default();
+    let mut dense = false;
+    let mut show_slices = false;
+    let mut detect_cycle = false;
+    let mut rle_input = false;
+    let mut export_rle = false;
+    let mut csv_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cycles" => n_cycles = args.next().ok_or("--cycles requires a number.")?.parse()?,
+            "--rules" => {
+                rules = Rules::try_from(args.next().ok_or("--rules requires rules.")?.as_str())?
+            }
+            "--dense" => dense = true,
+            "--show-slices" => show_slices = true,
+            "--find-cycle" => detect_cycle = true,
+            "--rle" => rle_input = true,
+            "--export-rle" => export_rle = true,
+            "--csv" => csv_path = Some(args.next().ok_or("--csv requires a path.")?),
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+
     let stdin = io::stdin();
     let mut buf = String::new();
-    stdin.lock().read_to_string(&mut buf).unwrap();
-    let cube = ConwayCube::try_from(buf.as_str()).unwrap();
-    let cube = (0..6).fold(cube, |cube, _| cube.next_state());
-    println!("{}", cube.active.len());
+    stdin.lock().read_to_string(&mut buf)?;
+    let cube = if rle_input {
+        ConwayCube::from_rle(&buf)?
+    } else {
+        ConwayCube::try_from(buf.as_str())?
+    };
+    if detect_cycle {
+        let cycle = if dense {
+            find_cycle(DenseConwayCube::from(&cube), n_cycles, &rules)
+        } else {
+            find_cycle(cube, n_cycles, &rules)
+        };
+        match cycle {
+            Some(Cycle { start, period }) => println!(
+                "State repeats with period {} after {} cycles.",
+                period, start
+            ),
+            None => println!("No repetition within {} cycles.", n_cycles),
+        }
+        return Ok(());
+    }
+
+    if export_rle {
+        let state = if dense {
+            final_state(DenseConwayCube::from(&cube), n_cycles, &rules)
+        } else {
+            final_state(cube, n_cycles, &rules)
+        };
+        print!("{}", state.to_rle());
+        return Ok(());
+    }
+
+    if let Some(path) = csv_path {
+        let mut out = BufWriter::new(File::create(path)?);
+        let n_active = if dense {
+            write_population_csv(DenseConwayCube::from(&cube), n_cycles, &rules, &mut out)?
+        } else {
+            write_population_csv(cube, n_cycles, &rules, &mut out)?
+        };
+        out.flush()?;
+        println!("{}", n_active);
+        return Ok(());
+    }
+
+    let n_active = if dense {
+        run(DenseConwayCube::from(&cube), n_cycles, &rules, show_slices)
+    } else {
+        run(cube, n_cycles, &rules, show_slices)
+    };
+    println!("{}", n_active);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -167,8 +495,6 @@ mod tests {
                     .iter()
                     .copied()
                 ),
-                lower_bounds: (0, 0, 0, 0),
-                upper_bounds: (2, 2, 0, 0),
             }
         )
     }
@@ -176,7 +502,141 @@ mod tests {
     #[test]
     fn test_conway_cube() {
         let cube = ConwayCube::try_from(INPUT).unwrap();
-        let cube = (0..6).fold(cube, |cube, _| cube.next_state());
-        assert_eq!(cube.active.len(), 848);
+        assert_eq!(run(cube, 6, &Rules::default(), false), 848);
+    }
+
+    #[test]
+    fn test_dense_conway_cube() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        assert_eq!(
+            run(DenseConwayCube::from(&cube), 6, &Rules::default(), false),
+            848
+        );
+    }
+
+    #[test]
+    fn test_dense_matches_sparse() {
+        let mut sparse = ConwayCube::try_from(INPUT).unwrap();
+        let mut dense = DenseConwayCube::from(&sparse);
+        for _ in 0..4 {
+            assert_eq!(dense.active_cells(), sparse.active_cells());
+            sparse = sparse.next_state(&Rules::default());
+            dense = dense.next_state(&Rules::default());
+        }
+    }
+
+    #[test]
+    fn test_display_slices() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        assert_eq!(cube.to_string(), "z=0, w=0\n.#.\n..#\n###\n");
+        let cube = cube.next_state(&Rules::default());
+        let slices = cube.to_string();
+        assert_eq!(slices.matches("z=").count(), 9);
+        assert!(slices.starts_with("z=-1, w=-1\n#..\n..#\n.#.\n\nz=0, w=-1\n"));
+        assert!(slices.contains("z=0, w=0\n#.#\n.##\n.#.\n"));
+    }
+
+    #[test]
+    fn test_dense_matches_sparse_with_survival_without_neighbours() {
+        let rules = Rules::try_from("B3/S0,2,3").unwrap();
+        let input = "#....\n.....\n..###\n.....\n....#";
+        let mut sparse = ConwayCube::try_from(input).unwrap();
+        let mut dense = DenseConwayCube::from(&sparse);
+        for _ in 0..4 {
+            assert_eq!(dense.active_cells(), sparse.active_cells());
+            sparse = sparse.next_state(&rules);
+            dense = dense.next_state(&rules);
+        }
+
+        let lone_cell_survives = ConwayCube::try_from(input)
+            .unwrap()
+            .next_state(&rules)
+            .active
+            .contains(&Default::default());
+        assert!(lone_cell_survives);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        let rules = Rules {
+            birth: vec![],
+            survival: vec![1],
+        };
+        assert_eq!(run(cube, 1, &rules, false), 2);
+    }
+
+    #[test]
+    fn test_write_population_csv() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        let mut out = vec![];
+        let n_active = write_population_csv(cube, 6, &Rules::default(), &mut out).unwrap();
+        assert_eq!(n_active, 848);
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "generation,active,volume");
+        assert_eq!(lines[1], "0,5,9");
+        assert!(lines[7].starts_with("6,848,"));
+
+        let empty = ConwayCube::try_from("...\n").unwrap();
+        let mut out = vec![];
+        write_population_csv(empty, 1, &Rules::default(), &mut out).unwrap();
+        assert_eq!(out, b"generation,active,volume\n0,0,0\n1,0,0\n");
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        assert_eq!(find_cycle(cube, 6, &Rules::default()), None);
+
+        let block = ConwayCube::try_from("##\n##\n").unwrap();
+        let rules = Rules {
+            birth: vec![],
+            survival: vec![3],
+        };
+        assert_eq!(
+            find_cycle(block, 10, &rules),
+            Some(Cycle {
+                start: 0,
+                period: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_cycle_dies_out() {
+        let cube = ConwayCube::try_from("#..\n..#\n").unwrap();
+        assert_eq!(
+            find_cycle(DenseConwayCube::from(&cube), 10, &Rules::default()),
+            Some(Cycle {
+                start: 1,
+                period: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_normalized_state_is_translation_invariant() {
+        let cube = ConwayCube::try_from(INPUT).unwrap();
+        let shifted: HashSet<Idx3> = cube
+            .active
+            .iter()
+            .map(|p| (p.0 + 3, p.1 - 2, p.2 + 1, p.3 - 5))
+            .collect();
+        assert_eq!(normalized_state(&shifted), normalized_state(&cube.active));
+    }
+
+    #[test]
+    fn test_rle_roundtrip() {
+        let cube = ConwayCube::try_from(INPUT)
+            .unwrap()
+            .next_state(&Rules::default());
+        let exported = cube.to_rle();
+        assert!(exported.starts_with("#C z=-1, w=-1\nx = 3, y = 3\no$2bo$bo!\n"));
+        assert_eq!(
+            ConwayCube::from_rle(&exported).map(|c| normalized_state(&c.active)),
+            Ok(normalized_state(&cube.active))
+        );
     }
 }
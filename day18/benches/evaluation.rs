@@ -0,0 +1,86 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day18::{run, Ast, Number, OperatorTable, Token, TokenizeError, Tokenizer};
+
+// Generates lines in the puzzle input format, but with much deeper nesting.
+// Only additions and subtractions are used to keep the values from overflowing.
+fn generate_expression(seed: &mut u64, depth: u32, out: &mut String) {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    let choice = (*seed >> 33) as usize;
+    if depth == 0 {
+        out.push_str(&(choice % 10).to_string());
+        return;
+    }
+    for i in 0..2 + choice % 3 {
+        if i > 0 {
+            out.push_str(if (choice >> (i + 4)) & 1 == 0 {
+                " + "
+            } else {
+                " - "
+            });
+        }
+        if (choice >> i) & 1 == 0 {
+            generate_expression(seed, 0, out);
+        } else {
+            out.push('(');
+            generate_expression(seed, depth - 1, out);
+            out.push(')');
+        }
+    }
+}
+
+fn generate_input(n_lines: usize, depth: u32) -> String {
+    let mut seed = 42;
+    let mut input = String::new();
+    for _ in 0..n_lines {
+        generate_expression(&mut seed, depth, &mut input);
+        input.push('\n');
+    }
+    input
+}
+
+fn parse(line: &str, operators: &OperatorTable) -> Ast {
+    let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new(line).collect();
+    Ast::parse(&mut tokens.unwrap().into_iter(), operators).unwrap()
+}
+
+fn bench_evaluation(c: &mut Criterion) {
+    let operators = OperatorTable::addition_first();
+    let mut group = c.benchmark_group("day18_evaluation");
+    for &depth in &[4, 8, 12] {
+        let input = generate_input(100, depth);
+        let asts: Vec<Ast> = input.lines().map(|line| parse(line, &operators)).collect();
+        let programs: Vec<_> = asts.iter().map(Ast::compile).collect();
+        group.bench_with_input(BenchmarkId::new("recursive", depth), &asts, |b, asts| {
+            b.iter(|| {
+                asts.iter()
+                    .map(|ast| ast.evaluate().unwrap())
+                    .sum::<Number>()
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("compile_and_run", depth),
+            &asts,
+            |b, asts| {
+                b.iter(|| {
+                    asts.iter()
+                        .map(|ast| run(&ast.compile()).unwrap())
+                        .sum::<Number>()
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("run", depth), &programs, |b, programs| {
+            b.iter(|| {
+                programs
+                    .iter()
+                    .map(|program| run(program).unwrap())
+                    .sum::<Number>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_evaluation);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day18::{Ast, OperatorTable, Token, TokenizeError, Tokenizer};
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new(input).collect();
+    tokens.unwrap()
+}
+
+// "((((1))))" with `depth` parentheses.
+fn nested_parentheses(depth: usize) -> String {
+    format!("{}1{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+// "1 + (1 + (1 + ... ))", every operand nested one level deeper.
+fn nested_operands(depth: usize) -> String {
+    format!("{}1{}", "1 + (".repeat(depth), ")".repeat(depth))
+}
+
+fn bench_parsing(c: &mut Criterion) {
+    let operators = OperatorTable::addition_first();
+    let mut group = c.benchmark_group("day18_parsing");
+    for &depth in &[10, 50, 100] {
+        for (name, input) in &[
+            ("nested_parentheses", nested_parentheses(depth)),
+            ("nested_operands", nested_operands(depth)),
+            ("flat_chain", "1 + ".repeat(depth) + "1"),
+        ] {
+            let tokens = tokenize(input);
+            group.bench_with_input(BenchmarkId::new(*name, depth), &tokens, |b, tokens| {
+                b.iter(|| Ast::parse(&mut tokens.iter().cloned(), &operators).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parsing);
+criterion_main!(benches);
@@ -0,0 +1,168 @@
+use super::{
+    functions, Ast, EvaluationError, FunctionRegistry, Number, Operator, Value, ValueType,
+};
+
+// Postfix bytecode for a stack machine. Operands are pushed before the
+// instruction consuming them, so a program for a valid tree always leaves
+// exactly one value on the stack.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instr {
+    Push(ValueType),
+    Negate,
+    Apply(Operator),
+    // Calls the function with the given number of arguments from the stack.
+    Call(String, usize),
+}
+
+impl Ast {
+    // Iterative post-order traversal, so that deeply nested trees do not
+    // overflow the call stack.
+    pub fn compile(&self) -> Vec<Instr> {
+        enum Visit<'a> {
+            Enter(&'a Ast),
+            Emit(Instr),
+        }
+
+        let mut program = vec![];
+        let mut stack = vec![Visit::Enter(self)];
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Emit(instr) => program.push(instr),
+                Visit::Enter(Ast::Leaf(v)) => program.push(Instr::Push(*v)),
+                Visit::Enter(Ast::Negation(operand)) => {
+                    stack.push(Visit::Emit(Instr::Negate));
+                    stack.push(Visit::Enter(operand));
+                }
+                Visit::Enter(Ast::Node(lhs, op, rhs)) => {
+                    stack.push(Visit::Emit(Instr::Apply(*op)));
+                    stack.push(Visit::Enter(rhs));
+                    stack.push(Visit::Enter(lhs));
+                }
+                Visit::Enter(Ast::Call(name, args)) => {
+                    stack.push(Visit::Emit(Instr::Call(name.clone(), args.len())));
+                    stack.extend(args.iter().rev().map(Visit::Enter));
+                }
+            }
+        }
+        program
+    }
+}
+
+// Runs with the builtin functions.
+pub fn run(program: &[Instr]) -> Result<Number, EvaluationError> {
+    functions::BUILTINS.with(|functions| run_with(program, functions))
+}
+
+fn pop<V>(stack: &mut Vec<V>) -> Result<V, EvaluationError> {
+    stack.pop().ok_or(EvaluationError::InvalidProgram)
+}
+
+// Programs not produced by compile may consume more values than are on the
+// stack or leave more than one, which gives an InvalidProgram error.
+pub fn run_with<V: Value>(
+    program: &[Instr],
+    functions: &FunctionRegistry<V>,
+) -> Result<V, EvaluationError> {
+    let mut stack: Vec<V> = Vec::with_capacity(program.len() / 2 + 1);
+    for instr in program {
+        match instr {
+            Instr::Push(v) => stack.push(V::from_literal(*v)),
+            Instr::Negate => {
+                let operand = pop(&mut stack)?;
+                stack.push(operand.negate()?);
+            }
+            Instr::Apply(op) => {
+                let rhs = pop(&mut stack)?;
+                let lhs = pop(&mut stack)?;
+                stack.push(V::apply(*op, lhs, rhs)?);
+            }
+            Instr::Call(name, n_args) => {
+                let first_arg = stack
+                    .len()
+                    .checked_sub(*n_args)
+                    .ok_or(EvaluationError::InvalidProgram)?;
+                let args = stack.split_off(first_arg);
+                stack.push(functions.call(name, args)?);
+            }
+        }
+    }
+    let result = pop(&mut stack)?;
+    if stack.is_empty() {
+        Ok(result)
+    } else {
+        Err(EvaluationError::InvalidProgram)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OperatorTable, Token, TokenizeError, Tokenizer};
+
+    fn parse(input: &str) -> Ast {
+        let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new(input).collect();
+        Ast::parse(
+            &mut tokens.unwrap().into_iter(),
+            &OperatorTable::addition_first(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(
+            parse("-1 + 2 * 3").compile(),
+            vec![
+                Instr::Push(1),
+                Instr::Negate,
+                Instr::Push(2),
+                Instr::Apply(Operator::Add),
+                Instr::Push(3),
+                Instr::Apply(Operator::Multiply),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_matches_evaluate() {
+        for input in &[
+            "1 + (2 * 3) + (4 * (5 + 6))",
+            "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+            "2 ^ 3 ^ 2 - 10 / -3",
+            "1 / (2 - 2)",
+            "9223372036854775807 + 1",
+            "max(1, 2 * 3, abs(-7)) + pow(2, 3)",
+            "min()",
+        ] {
+            let ast = parse(input);
+            assert_eq!(run(&ast.compile()), ast.evaluate(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_run_invalid_program() {
+        for program in &[
+            vec![],
+            vec![Instr::Negate],
+            vec![Instr::Push(1), Instr::Apply(Operator::Add)],
+            vec![Instr::Push(1), Instr::Call(String::from("max"), 2)],
+            vec![Instr::Push(1), Instr::Push(2)],
+        ] {
+            assert_eq!(
+                run(program),
+                Err(EvaluationError::InvalidProgram),
+                "{:?}",
+                program
+            );
+        }
+    }
+
+    #[test]
+    fn test_compile_deeply_nested() {
+        let mut ast = Ast::Leaf(1);
+        for _ in 0..10_000 {
+            ast = Ast::Node(Box::new(Ast::Leaf(1)), Operator::Add, Box::new(ast));
+        }
+        assert_eq!(run(&ast.compile()), Ok(Number::from(10_001)));
+    }
+}
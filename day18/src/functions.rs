@@ -0,0 +1,161 @@
+use super::{EvaluationError, Number, Operator, Value};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    fn accepts(&self, n_args: usize) -> bool {
+        match *self {
+            Self::Exactly(n) => n_args == n,
+            Self::AtLeast(n) => n_args >= n,
+        }
+    }
+}
+
+type Function<V> = dyn Fn(Vec<V>) -> Result<V, EvaluationError>;
+
+// Functions callable from expressions by name. The default registry contains
+// the builtins min, max, abs, and pow. The arity is checked before calling a
+// function, so that it may take the arguments without further checks.
+pub struct FunctionRegistry<V = Number> {
+    functions: HashMap<String, (Arity, Box<Function<V>>)>,
+}
+
+impl<V: Value> FunctionRegistry<V> {
+    pub fn empty() -> Self {
+        Self {
+            functions: HashMap::new(),
+        }
+    }
+
+    // Replaces any function previously registered under the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        function: impl Fn(Vec<V>) -> Result<V, EvaluationError> + 'static,
+    ) {
+        self.functions
+            .insert(String::from(name), (arity, Box::new(function)));
+    }
+
+    pub fn call(&self, name: &str, args: Vec<V>) -> Result<V, EvaluationError> {
+        let (arity, function) = self
+            .functions
+            .get(name)
+            .ok_or_else(|| EvaluationError::UnknownFunction(String::from(name)))?;
+        if !arity.accepts(args.len()) {
+            return Err(EvaluationError::WrongNumberOfArguments(
+                String::from(name),
+                args.len(),
+            ));
+        }
+        function(args)
+    }
+}
+
+// Floats are compared by partial order, but evaluation never produces NaN.
+impl<V: Value> Default for FunctionRegistry<V> {
+    fn default() -> Self {
+        let mut functions = Self::empty();
+        functions.register("min", Arity::AtLeast(1), |args| {
+            Ok(args
+                .into_iter()
+                .reduce(|min, v| if v < min { v } else { min })
+                .unwrap())
+        });
+        functions.register("max", Arity::AtLeast(1), |args| {
+            Ok(args
+                .into_iter()
+                .reduce(|max, v| if v > max { v } else { max })
+                .unwrap())
+        });
+        functions.register("abs", Arity::Exactly(1), |args| {
+            let value = args.into_iter().next().unwrap();
+            if value.is_negative() {
+                value.negate()
+            } else {
+                Ok(value)
+            }
+        });
+        functions.register("pow", Arity::Exactly(2), |args| {
+            let mut args = args.into_iter();
+            V::apply(Operator::Power, args.next().unwrap(), args.next().unwrap())
+        });
+        functions
+    }
+}
+
+thread_local! {
+    pub(crate) static BUILTINS: FunctionRegistry = FunctionRegistry::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(values: &[i64]) -> Vec<Number> {
+        values.iter().map(|&v| Number::from(v)).collect()
+    }
+
+    #[test]
+    fn test_builtins() {
+        let functions = FunctionRegistry::default();
+        assert_eq!(
+            functions.call("min", numbers(&[3, -1, 2])),
+            Ok(Number::from(-1))
+        );
+        assert_eq!(
+            functions.call("max", numbers(&[3, -1, 2])),
+            Ok(Number::from(3))
+        );
+        assert_eq!(functions.call("abs", numbers(&[-4])), Ok(Number::from(4)));
+        assert_eq!(
+            functions.call("pow", numbers(&[2, 10])),
+            Ok(Number::from(1024))
+        );
+    }
+
+    #[test]
+    fn test_call_errors() {
+        let functions = FunctionRegistry::default();
+        assert_eq!(
+            functions.call("sqrt", numbers(&[4])),
+            Err(EvaluationError::UnknownFunction(String::from("sqrt")))
+        );
+        assert_eq!(
+            functions.call("min", vec![]),
+            Err(EvaluationError::WrongNumberOfArguments(
+                String::from("min"),
+                0
+            ))
+        );
+        assert_eq!(
+            functions.call("abs", numbers(&[1, 2])),
+            Err(EvaluationError::WrongNumberOfArguments(
+                String::from("abs"),
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn test_register() {
+        let mut functions = FunctionRegistry::empty();
+        functions.register("sum", Arity::AtLeast(0), |args| {
+            Ok(args.into_iter().fold(Number::from(0), |sum, v| sum + v))
+        });
+        assert_eq!(
+            functions.call("sum", numbers(&[1, 2, 3])),
+            Ok(Number::from(6))
+        );
+        assert_eq!(
+            functions.call("min", numbers(&[1])),
+            Err(EvaluationError::UnknownFunction(String::from("min")))
+        );
+    }
+}
@@ -0,0 +1,979 @@
+use serde_json::json;
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::num::ParseIntError;
+use std::str::CharIndices;
+
+mod bytecode;
+mod functions;
+mod value;
+
+pub use bytecode::{run, run_with, Instr};
+pub use functions::{Arity, FunctionRegistry};
+pub use value::{Rational, Value, ValueMode};
+
+pub type ValueType = i64;
+
+// Evaluation results use checked arithmetic on i64, or arbitrary precision
+// with the `bigint` feature.
+#[cfg(not(feature = "bigint"))]
+pub type Number = i64;
+#[cfg(feature = "bigint")]
+pub type Number = num_bigint::BigInt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+const OPERATOR_SYMBOLS: [(char, Operator); 5] = [
+    ('+', Operator::Add),
+    ('-', Operator::Subtract),
+    ('*', Operator::Multiply),
+    ('/', Operator::Divide),
+    ('^', Operator::Power),
+];
+
+impl Operator {
+    pub fn from_symbol(c: char) -> Option<Self> {
+        OPERATOR_SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == c)
+            .map(|(_, op)| *op)
+    }
+
+    pub fn symbol(&self) -> char {
+        OPERATOR_SYMBOLS
+            .iter()
+            .find(|(_, op)| op == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Num(ValueType),
+    Operator(Operator),
+    OpenParens,
+    CloseParens,
+    Ident(String),
+    Comma,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Num(v) => write!(f, "{}", v),
+            Self::Operator(op) => write!(f, "{}", op.symbol()),
+            Self::OpenParens => f.write_str("("),
+            Self::CloseParens => f.write_str(")"),
+            Self::Ident(name) => f.write_str(name),
+            Self::Comma => f.write_str(","),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OperatorProperties {
+    precedence: u8,
+    associativity: Associativity,
+}
+
+impl OperatorProperties {
+    pub fn left(precedence: u8) -> Self {
+        Self {
+            precedence,
+            associativity: Associativity::Left,
+        }
+    }
+
+    pub fn right(precedence: u8) -> Self {
+        Self {
+            precedence,
+            associativity: Associativity::Right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OperatorTable {
+    binary: Vec<(Operator, OperatorProperties)>,
+    negation_precedence: u8,
+}
+
+impl OperatorTable {
+    pub fn equal_precedence() -> Self {
+        Self {
+            binary: vec![
+                (Operator::Add, OperatorProperties::left(1)),
+                (Operator::Subtract, OperatorProperties::left(1)),
+                (Operator::Multiply, OperatorProperties::left(1)),
+                (Operator::Divide, OperatorProperties::left(1)),
+                (Operator::Power, OperatorProperties::right(3)),
+            ],
+            negation_precedence: 2,
+        }
+    }
+
+    pub fn addition_first() -> Self {
+        Self {
+            binary: vec![
+                (Operator::Add, OperatorProperties::left(2)),
+                (Operator::Subtract, OperatorProperties::left(2)),
+                (Operator::Multiply, OperatorProperties::left(1)),
+                (Operator::Divide, OperatorProperties::left(1)),
+                (Operator::Power, OperatorProperties::right(4)),
+            ],
+            negation_precedence: 3,
+        }
+    }
+
+    pub fn properties(&self, op: Operator) -> Option<OperatorProperties> {
+        self.binary
+            .iter()
+            .find(|(candidate, _)| *candidate == op)
+            .map(|(_, properties)| *properties)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ast {
+    Leaf(ValueType),
+    Negation(Box<Ast>),
+    Node(Box<Ast>, Operator, Box<Ast>),
+    Call(String, Vec<Ast>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EvaluationError {
+    DivisionByZero,
+    NegativeExponent(Number),
+    NonIntegerExponent(String),
+    Overflow,
+    UnknownFunction(String),
+    WrongNumberOfArguments(String, usize),
+    // A bytecode program that does not leave exactly one value on the stack.
+    InvalidProgram,
+}
+
+impl Display for EvaluationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::DivisionByZero => f.write_str("division by zero"),
+            Self::NegativeExponent(exponent) => write!(f, "negative exponent {}", exponent),
+            Self::NonIntegerExponent(exponent) => write!(f, "non-integer exponent {}", exponent),
+            Self::Overflow => f.write_str("arithmetic overflow"),
+            Self::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            Self::WrongNumberOfArguments(name, n) => {
+                write!(f, "{} arguments are not valid for '{}'", n, name)
+            }
+            Self::InvalidProgram => f.write_str("invalid program"),
+        }
+    }
+}
+
+impl std::error::Error for EvaluationError {}
+
+impl Ast {
+    // Evaluates with the builtin functions.
+    pub fn evaluate(&self) -> Result<Number, EvaluationError> {
+        functions::BUILTINS.with(|functions| self.evaluate_with(functions))
+    }
+
+    // Evaluates in another value type with the builtin functions.
+    pub fn evaluate_as<V: Value>(&self) -> Result<V, EvaluationError> {
+        self.evaluate_with(&FunctionRegistry::default())
+    }
+
+    pub fn evaluate_with<V: Value>(
+        &self,
+        functions: &FunctionRegistry<V>,
+    ) -> Result<V, EvaluationError> {
+        match self {
+            Self::Leaf(v) => Ok(V::from_literal(*v)),
+            Self::Negation(operand) => operand.evaluate_with(functions)?.negate(),
+            Self::Node(lhs, op, rhs) => V::apply(
+                *op,
+                lhs.evaluate_with(functions)?,
+                rhs.evaluate_with(functions)?,
+            ),
+            Self::Call(name, args) => functions.call(
+                name,
+                args.iter()
+                    .map(|arg| arg.evaluate_with(functions))
+                    .collect::<Result<_, _>>()?,
+            ),
+        }
+    }
+}
+
+impl Ast {
+    pub fn display<'a>(&'a self, operators: &'a OperatorTable) -> InfixDisplay<'a> {
+        InfixDisplay {
+            ast: self,
+            operators,
+        }
+    }
+
+    pub fn to_sexpr(&self) -> String {
+        match self {
+            Self::Leaf(v) => v.to_string(),
+            Self::Negation(operand) => format!("(- {})", operand.to_sexpr()),
+            Self::Node(lhs, op, rhs) => {
+                format!("({} {} {})", op.symbol(), lhs.to_sexpr(), rhs.to_sexpr())
+            }
+            Self::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(Ast::to_sexpr).collect();
+                format!("({})", [vec![name.clone()], args].concat().join(" "))
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Leaf(v) => json!(v),
+            Self::Negation(operand) => json!({ "op": "neg", "operand": operand.to_json() }),
+            Self::Node(lhs, op, rhs) => json!({
+                "op": op.symbol().to_string(),
+                "lhs": lhs.to_json(),
+                "rhs": rhs.to_json(),
+            }),
+            Self::Call(name, args) => json!({
+                "call": name,
+                "args": args.iter().map(Ast::to_json).collect::<Vec<_>>(),
+            }),
+        }
+    }
+}
+
+// Prints an expression with the parentheses required to parse it back into the
+// same tree with the given operator table, but no more.
+pub struct InfixDisplay<'a> {
+    ast: &'a Ast,
+    operators: &'a OperatorTable,
+}
+
+impl<'a> InfixDisplay<'a> {
+    // `followed_by` is the precedence of the operator that will be printed
+    // right after the expression. It must not get pulled into the expression.
+    fn write(
+        &self,
+        f: &mut Formatter<'_>,
+        ast: &Ast,
+        min_precedence: u8,
+        followed_by: Option<u8>,
+    ) -> Result<(), std::fmt::Error> {
+        match ast {
+            Ast::Leaf(v) => write!(f, "{}", v),
+            Ast::Negation(operand) => {
+                let precedence = self.operators.negation_precedence;
+                let parens = followed_by.is_some_and(|p| p >= precedence);
+                let followed_by = if parens { None } else { followed_by };
+                f.write_str(if parens { "(-" } else { "-" })?;
+                self.write(f, operand, precedence, followed_by)?;
+                if parens {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            Ast::Node(lhs, op, rhs) => {
+                let properties = self
+                    .operators
+                    .properties(*op)
+                    .unwrap_or_else(|| OperatorProperties::left(0));
+                let precedence = properties.precedence;
+                let (lhs_min_precedence, rhs_min_precedence) = match properties.associativity {
+                    Associativity::Left => (precedence, precedence + 1),
+                    Associativity::Right => (precedence + 1, precedence),
+                };
+                let parens = precedence < min_precedence
+                    || followed_by.is_some_and(|p| p >= rhs_min_precedence);
+                let followed_by = if parens { None } else { followed_by };
+                if parens {
+                    f.write_str("(")?;
+                }
+                self.write(f, lhs, lhs_min_precedence, Some(precedence))?;
+                write!(f, " {} ", op.symbol())?;
+                self.write(f, rhs, rhs_min_precedence, followed_by)?;
+                if parens {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            Ast::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    self.write(f, arg, 0, None)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl<'a> Display for InfixDisplay<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        self.write(f, self.ast, 0, None)
+    }
+}
+
+impl Display for Ast {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        self.display(&OperatorTable::addition_first()).fmt(f)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TokenizeError {
+    UnexpectedCharacter(usize, char),
+    InvalidNumber(usize, ParseIntError),
+}
+
+impl Display for TokenizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::UnexpectedCharacter(position, c) => {
+                write!(f, "unexpected character '{}' at column {}", c, position + 1)
+            }
+            Self::InvalidNumber(position, err) => {
+                write!(f, "invalid number at column {}: {}", position + 1, err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TokenizeError {}
+
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    fn number(&mut self, start: usize) -> Result<Token, TokenizeError> {
+        let mut end = start + 1;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            end = i + c.len_utf8();
+            self.chars.next();
+        }
+        self.input[start..end]
+            .parse::<ValueType>()
+            .map(Token::Num)
+            .map_err(|err| TokenizeError::InvalidNumber(start, err))
+    }
+
+    fn ident(&mut self, start: usize) -> Token {
+        let mut end = start + 1;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            end = i + c.len_utf8();
+            self.chars.next();
+        }
+        Token::Ident(String::from(&self.input[start..end]))
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, c) = self.chars.find(|(_, c)| !c.is_whitespace())?;
+        Some(match c {
+            '(' => Ok(Token::OpenParens),
+            ')' => Ok(Token::CloseParens),
+            ',' => Ok(Token::Comma),
+            c if c.is_ascii_digit() => self.number(i),
+            c if c.is_ascii_alphabetic() || c == '_' => Ok(self.ident(i)),
+            c => Operator::from_symbol(c)
+                .map(Token::Operator)
+                .ok_or(TokenizeError::UnexpectedCharacter(i, c)),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    UnexpectedToken(Token),
+    UnexpectedEnd,
+    UnsupportedOperator(Operator),
+    TooDeeplyNested,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::UnexpectedToken(token) => write!(f, "unexpected '{}'", token),
+            Self::UnexpectedEnd => f.write_str("unexpected end of expression"),
+            Self::UnsupportedOperator(op) => write!(f, "unsupported operator '{}'", op.symbol()),
+            Self::TooDeeplyNested => write!(
+                f,
+                "expression nested deeper than {} levels",
+                MAX_NESTING_DEPTH
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Limits the recursion of the parser, so that deeply nested input gives an
+// error instead of a stack overflow, even on the 2 MiB stack of a spawned
+// thread in a debug build. Every parenthesis, negation, function call, and
+// operand on the right of an operator counts as a level.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+// Precedence climbing: operators are folded into the left-hand side as long as
+// they bind at least as tight as the operator the current operand belongs to.
+impl Ast {
+    pub fn parse(
+        tokens: &mut impl Iterator<Item = Token>,
+        operators: &OperatorTable,
+    ) -> Result<Self, ParseError> {
+        let mut tokens = tokens.peekable();
+        let ast = Self::parse_expression(&mut tokens, operators, 0, 0)?;
+        match tokens.next() {
+            Some(token) => Err(ParseError::UnexpectedToken(token)),
+            None => Ok(ast),
+        }
+    }
+
+    fn parse_operand(
+        tokens: &mut Peekable<impl Iterator<Item = Token>>,
+        operators: &OperatorTable,
+        depth: usize,
+    ) -> Result<Self, ParseError> {
+        match tokens.next() {
+            Some(Token::Num(v)) => Ok(Ast::Leaf(v)),
+            Some(Token::Operator(Operator::Subtract)) => {
+                let operand = Self::parse_expression(
+                    tokens,
+                    operators,
+                    operators.negation_precedence,
+                    depth + 1,
+                )?;
+                Ok(Ast::Negation(Box::new(operand)))
+            }
+            Some(Token::OpenParens) => {
+                let ast = Self::parse_expression(tokens, operators, 0, depth + 1)?;
+                match tokens.next() {
+                    Some(Token::CloseParens) => Ok(ast),
+                    Some(token) => Err(ParseError::UnexpectedToken(token)),
+                    None => Err(ParseError::UnexpectedEnd),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                let args = Self::parse_arguments(tokens, operators, depth + 1)?;
+                Ok(Ast::Call(name, args))
+            }
+            Some(token) => Err(ParseError::UnexpectedToken(token)),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    // Parses a parenthesized, comma-separated argument list.
+    fn parse_arguments(
+        tokens: &mut Peekable<impl Iterator<Item = Token>>,
+        operators: &OperatorTable,
+        depth: usize,
+    ) -> Result<Vec<Self>, ParseError> {
+        match tokens.next() {
+            Some(Token::OpenParens) => {}
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEnd),
+        }
+        let mut args = vec![];
+        if tokens.peek() == Some(&Token::CloseParens) {
+            tokens.next();
+            return Ok(args);
+        }
+        loop {
+            args.push(Self::parse_expression(tokens, operators, 0, depth)?);
+            match tokens.next() {
+                Some(Token::Comma) => {}
+                Some(Token::CloseParens) => return Ok(args),
+                Some(token) => return Err(ParseError::UnexpectedToken(token)),
+                None => return Err(ParseError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn parse_expression(
+        tokens: &mut Peekable<impl Iterator<Item = Token>>,
+        operators: &OperatorTable,
+        min_precedence: u8,
+        depth: usize,
+    ) -> Result<Self, ParseError> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(ParseError::TooDeeplyNested);
+        }
+        let mut lhs = Self::parse_operand(tokens, operators, depth)?;
+        while let Some(&Token::Operator(op)) = tokens.peek() {
+            let properties = operators
+                .properties(op)
+                .ok_or(ParseError::UnsupportedOperator(op))?;
+            if properties.precedence < min_precedence {
+                break;
+            }
+            tokens.next();
+            let rhs_min_precedence = match properties.associativity {
+                Associativity::Left => properties.precedence + 1,
+                Associativity::Right => properties.precedence,
+            };
+            let rhs = Self::parse_expression(tokens, operators, rhs_min_precedence, depth + 1)?;
+            lhs = Ast::Node(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+}
+
+pub fn evaluate_line<V: Value>(
+    line: &str,
+    operator_tables: &[OperatorTable],
+) -> Result<Vec<V>, Box<dyn std::error::Error>> {
+    let tokens: Vec<Token> = Tokenizer::new(line).collect::<Result<_, _>>()?;
+    let functions = FunctionRegistry::default();
+    operator_tables
+        .iter()
+        .map(|operators| {
+            Ok(Ast::parse(&mut tokens.iter().cloned(), operators)?.evaluate_with(&functions)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn parse(input: &str, operators: &OperatorTable) -> Result<Ast, ParseError> {
+        let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new(input).collect();
+        let tokens = tokens.unwrap();
+        Ast::parse(&mut tokens.iter().cloned(), operators)
+    }
+
+    fn evaluate(input: &str, operators: &OperatorTable) -> Number {
+        parse(input, operators).unwrap().evaluate().unwrap()
+    }
+
+    #[test]
+    fn test_tokenization() {
+        let tokens: Result<Vec<Token>, TokenizeError> =
+            Tokenizer::new("1 + (22 * 3) - 4 / 5 ^ 6").collect();
+        assert_eq!(
+            tokens.unwrap(),
+            vec![
+                Token::Num(1),
+                Token::Operator(Operator::Add),
+                Token::OpenParens,
+                Token::Num(22),
+                Token::Operator(Operator::Multiply),
+                Token::Num(3),
+                Token::CloseParens,
+                Token::Operator(Operator::Subtract),
+                Token::Num(4),
+                Token::Operator(Operator::Divide),
+                Token::Num(5),
+                Token::Operator(Operator::Power),
+                Token::Num(6),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_tokenization_errors() {
+        let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new("1 + $").collect();
+        assert_eq!(tokens, Err(TokenizeError::UnexpectedCharacter(4, '$')));
+
+        let tokens: Result<Vec<Token>, TokenizeError> =
+            Tokenizer::new("1 + 99999999999999999999").collect();
+        assert!(matches!(tokens, Err(TokenizeError::InvalidNumber(4, _))));
+    }
+
+    #[rstest(
+        input,
+        equal_precedence,
+        addition_first,
+        case("1 + 2 * 3 + 4 * 5 + 6", 71, 231),
+        case("1 + (2 * 3) + (4 * (5 + 6))", 51, 51),
+        case("2 * 3 + (4 * 5)", 26, 46),
+        case("5 + (8 * 3 + 9 + 3 * 4 * 3)", 437, 1445),
+        case("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 12240, 669060),
+        case("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 13632, 23340),
+        case("10 - 4 - 3", 3, 3),
+        case("2 * 5 - 3", 7, 4),
+        case("20 / 2 + 3", 13, 4),
+        case("2 ^ 3 ^ 2", 512, 512),
+        case("2 * 2 ^ 3", 16, 16),
+        case("-2 ^ 2", -4, -4),
+        case("-2 * 3 + 1", -5, -8),
+        case("4 - -3", 7, 7)
+    )]
+    fn test_parsing_and_evaluation(
+        input: &str,
+        equal_precedence: ValueType,
+        addition_first: ValueType,
+    ) {
+        assert_eq!(
+            evaluate(input, &OperatorTable::equal_precedence()),
+            Number::from(equal_precedence)
+        );
+        assert_eq!(
+            evaluate(input, &OperatorTable::addition_first()),
+            Number::from(addition_first)
+        );
+    }
+
+    #[test]
+    fn test_multiplication_first() {
+        let operators = OperatorTable {
+            binary: vec![
+                (Operator::Add, OperatorProperties::left(1)),
+                (Operator::Multiply, OperatorProperties::left(2)),
+            ],
+            negation_precedence: 3,
+        };
+        assert_eq!(evaluate("2 * 3 + 4 * 5", &operators), Number::from(26));
+    }
+
+    #[test]
+    fn test_associativity() {
+        let operators = OperatorTable {
+            binary: vec![
+                (Operator::Add, OperatorProperties::right(1)),
+                (Operator::Multiply, OperatorProperties::right(1)),
+            ],
+            negation_precedence: 2,
+        };
+        assert_eq!(
+            parse("1 + 2 + 3", &operators),
+            Ok(Ast::Node(
+                Box::new(Ast::Leaf(1)),
+                Operator::Add,
+                Box::new(Ast::Node(
+                    Box::new(Ast::Leaf(2)),
+                    Operator::Add,
+                    Box::new(Ast::Leaf(3))
+                ))
+            ))
+        );
+        assert_eq!(
+            parse("1 + 2 + 3", &OperatorTable::equal_precedence()),
+            Ok(Ast::Node(
+                Box::new(Ast::Node(
+                    Box::new(Ast::Leaf(1)),
+                    Operator::Add,
+                    Box::new(Ast::Leaf(2))
+                )),
+                Operator::Add,
+                Box::new(Ast::Leaf(3))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        let operators = OperatorTable::addition_first();
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(
+            evaluate(&nested(MAX_NESTING_DEPTH), &operators),
+            Number::from(1)
+        );
+        assert_eq!(
+            parse(&nested(MAX_NESTING_DEPTH + 1), &operators),
+            Err(ParseError::TooDeeplyNested)
+        );
+        assert_eq!(
+            parse(&nested(100_000), &operators),
+            Err(ParseError::TooDeeplyNested)
+        );
+        let exponents = format!("2{}", "^1".repeat(100_000));
+        assert_eq!(
+            parse(&exponents, &operators),
+            Err(ParseError::TooDeeplyNested)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let operators = OperatorTable::addition_first();
+        assert_eq!(parse("(1 + 2", &operators), Err(ParseError::UnexpectedEnd));
+        assert_eq!(
+            parse("1 + 2)", &operators),
+            Err(ParseError::UnexpectedToken(Token::CloseParens))
+        );
+        assert_eq!(parse("1 +", &operators), Err(ParseError::UnexpectedEnd));
+        assert_eq!(
+            parse("1 2", &operators),
+            Err(ParseError::UnexpectedToken(Token::Num(2)))
+        );
+        assert_eq!(
+            parse("* 2", &operators),
+            Err(ParseError::UnexpectedToken(Token::Operator(
+                Operator::Multiply
+            )))
+        );
+        let without_multiplication = OperatorTable {
+            binary: vec![(Operator::Add, OperatorProperties::left(1))],
+            negation_precedence: 2,
+        };
+        assert_eq!(
+            parse("1 * 2", &without_multiplication),
+            Err(ParseError::UnsupportedOperator(Operator::Multiply))
+        );
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let operators = OperatorTable::addition_first();
+        assert_eq!(
+            parse("1 / (2 - 2)", &operators).unwrap().evaluate(),
+            Err(EvaluationError::DivisionByZero)
+        );
+        assert_eq!(
+            parse("2 ^ -1", &operators).unwrap().evaluate(),
+            Err(EvaluationError::NegativeExponent(Number::from(-1)))
+        );
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_evaluation_overflow() {
+        let operators = OperatorTable::addition_first();
+        for input in &[
+            "9223372036854775807 + 1",
+            "-9223372036854775807 - 2",
+            "4294967296 * 4294967296",
+            "2 ^ 63",
+            "-(-9223372036854775807 - 1)",
+            "(-9223372036854775807 - 1) / -1",
+        ] {
+            assert_eq!(
+                parse(input, &operators).unwrap().evaluate(),
+                Err(EvaluationError::Overflow),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_evaluation_bigint() {
+        let operators = OperatorTable::addition_first();
+        assert_eq!(
+            evaluate("9223372036854775807 + 1", &operators).to_string(),
+            "9223372036854775808"
+        );
+        assert_eq!(
+            evaluate("2 ^ 100", &operators).to_string(),
+            "1267650600228229401496703205376"
+        );
+    }
+
+    #[rstest(
+        input,
+        equal_precedence,
+        addition_first,
+        case("(1 + 2) * 3", "1 + 2 * 3", "1 + 2 * 3"),
+        case("1 * (2 + 3)", "1 * (2 + 3)", "1 * 2 + 3"),
+        case("(1 + 2) + 3", "1 + 2 + 3", "1 + 2 + 3"),
+        case("1 - (2 - 3)", "1 - (2 - 3)", "1 - (2 - 3)"),
+        case("(2 ^ 3) ^ 2", "(2 ^ 3) ^ 2", "(2 ^ 3) ^ 2"),
+        case("2 ^ (3 ^ 2)", "2 ^ 3 ^ 2", "2 ^ 3 ^ 2"),
+        case("-(2 ^ 2)", "-2 ^ 2", "-2 ^ 2"),
+        case("(-2) ^ 2", "(-2) ^ 2", "(-2) ^ 2"),
+        case("-(1 + 2)", "-(1 + 2)", "-(1 + 2)"),
+        case("4 - -3", "4 - -3", "4 - -3"),
+        case("((1 + 2))", "1 + 2", "1 + 2")
+    )]
+    fn test_minimal_parenthesization(input: &str, equal_precedence: &str, addition_first: &str) {
+        for (operators, expected) in &[
+            (OperatorTable::equal_precedence(), equal_precedence),
+            (OperatorTable::addition_first(), addition_first),
+        ] {
+            let ast = parse(input, operators).unwrap();
+            assert_eq!(ast.display(operators).to_string(), *expected);
+        }
+    }
+
+    fn random_ast(seed: &mut u64, depth: u32) -> Ast {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let choice = (*seed >> 33) as usize;
+        if depth == 0 || choice.is_multiple_of(7) {
+            Ast::Leaf((choice % 10) as ValueType)
+        } else if choice % 7 == 1 {
+            Ast::Negation(Box::new(random_ast(seed, depth - 1)))
+        } else {
+            let (_, op) = OPERATOR_SYMBOLS[choice % OPERATOR_SYMBOLS.len()];
+            Ast::Node(
+                Box::new(random_ast(seed, depth - 1)),
+                op,
+                Box::new(random_ast(seed, depth - 1)),
+            )
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mixed_associativity = OperatorTable {
+            binary: vec![
+                (Operator::Add, OperatorProperties::left(2)),
+                (Operator::Subtract, OperatorProperties::right(2)),
+                (Operator::Multiply, OperatorProperties::right(5)),
+                (Operator::Divide, OperatorProperties::left(5)),
+                (Operator::Power, OperatorProperties::left(1)),
+            ],
+            negation_precedence: 3,
+        };
+        let mut seed = 42;
+        for _ in 0..500 {
+            let ast = random_ast(&mut seed, 5);
+            for operators in &[
+                OperatorTable::equal_precedence(),
+                OperatorTable::addition_first(),
+                mixed_associativity.clone(),
+            ] {
+                let printed = ast.display(operators).to_string();
+                assert_eq!(parse(&printed, operators), Ok(ast.clone()), "{}", printed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tokenize_call() {
+        let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new("max(1,x_2)").collect();
+        assert_eq!(
+            tokens.unwrap(),
+            vec![
+                Token::Ident(String::from("max")),
+                Token::OpenParens,
+                Token::Num(1),
+                Token::Comma,
+                Token::Ident(String::from("x_2")),
+                Token::CloseParens,
+            ]
+        );
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case("max(1, 2 + 3)", 5),
+        case("min(4, -2, 7) * 2", -4),
+        case("abs(3 - 10)", 7),
+        case("pow(2, 3 ^ 2)", 512),
+        case("1 + max(pow(2, 3), abs(-9))", 10)
+    )]
+    fn test_evaluate_calls(input: &str, expected: ValueType) {
+        assert_eq!(
+            evaluate(input, &OperatorTable::addition_first()),
+            Number::from(expected)
+        );
+    }
+
+    #[test]
+    fn test_parse_call_errors() {
+        let operators = OperatorTable::addition_first();
+        assert_eq!(
+            parse("max 1", &operators),
+            Err(ParseError::UnexpectedToken(Token::Num(1)))
+        );
+        assert_eq!(
+            parse("max(1, 2", &operators),
+            Err(ParseError::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse("max(1 2)", &operators),
+            Err(ParseError::UnexpectedToken(Token::Num(2)))
+        );
+        assert_eq!(
+            parse("f()", &operators),
+            Ok(Ast::Call(String::from("f"), vec![]))
+        );
+    }
+
+    #[test]
+    fn test_evaluate_with_registered_function() {
+        let mut functions = FunctionRegistry::default();
+        functions.register("double", Arity::Exactly(1), |args| {
+            args.into_iter()
+                .next()
+                .map(|v| v * Number::from(2))
+                .ok_or(EvaluationError::Overflow)
+        });
+        let ast = parse("double(3) + max(1, 2)", &OperatorTable::addition_first()).unwrap();
+        assert_eq!(ast.evaluate_with(&functions), Ok(Number::from(8)));
+        assert_eq!(
+            ast.evaluate(),
+            Err(EvaluationError::UnknownFunction(String::from("double")))
+        );
+    }
+
+    #[test]
+    fn test_display_call() {
+        let operators = OperatorTable::addition_first();
+        let ast = parse("max((1 + 2) * 3, -min(4))", &operators).unwrap();
+        assert_eq!(
+            ast.display(&operators).to_string(),
+            "max(1 + 2 * 3, -min(4))"
+        );
+        assert_eq!(ast.to_sexpr(), "(max (* (+ 1 2) 3) (- (min 4)))");
+        assert_eq!(
+            ast.to_json(),
+            json!({
+                "call": "max",
+                "args": [
+                    { "op": "*", "lhs": { "op": "+", "lhs": 1, "rhs": 2 }, "rhs": 3 },
+                    { "op": "neg", "operand": { "call": "min", "args": [4] } },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_sexpr() {
+        let ast = parse("-1 + 2 * 3", &OperatorTable::addition_first()).unwrap();
+        assert_eq!(ast.to_sexpr(), "(* (+ (- 1) 2) 3)");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let ast = parse("-1 + 2", &OperatorTable::addition_first()).unwrap();
+        assert_eq!(
+            ast.to_json(),
+            json!({
+                "op": "+",
+                "lhs": { "op": "neg", "operand": 1 },
+                "rhs": 2,
+            })
+        );
+    }
+}
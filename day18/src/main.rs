@@ -0,0 +1,85 @@
+use day18::{
+    evaluate_line, Ast, Number, Operator, OperatorTable, Rational, Token, Tokenizer, Value,
+    ValueMode,
+};
+use std::env;
+use std::io::{self, BufRead};
+
+fn print_line(
+    line: &str,
+    operator_tables: &[OperatorTable],
+    format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tokens: Vec<Token> = Tokenizer::new(line).collect::<Result<_, _>>()?;
+    for operators in operator_tables {
+        let ast = Ast::parse(&mut tokens.iter().cloned(), operators)?;
+        match format {
+            "infix" => println!("{}", ast.display(operators)),
+            "sexpr" => println!("{}", ast.to_sexpr()),
+            "json" => println!("{}", ast.to_json()),
+            _ => return Err(format!("unknown print format '{}'", format).into()),
+        }
+    }
+    Ok(())
+}
+
+// Sums the values of all lines for each operator table.
+fn sum_lines<V: Value>(
+    lines: &[String],
+    operator_tables: &[OperatorTable],
+) -> Result<Vec<V>, Box<dyn std::error::Error>> {
+    let mut sums = vec![V::from_literal(0); operator_tables.len()];
+    for (i, line) in lines.iter().enumerate() {
+        let values = evaluate_line::<V>(line, operator_tables)
+            .map_err(|err| format!("Line {}: {}", i + 1, err))?;
+        for (sum, value) in sums.iter_mut().zip(values) {
+            *sum = V::apply(Operator::Add, sum.clone(), value)?;
+        }
+    }
+    Ok(sums)
+}
+
+fn print_sums<V: Value>(sums: &[V]) {
+    println!("Equal precedence: {}", sums[0]);
+    println!("Addition first: {}", sums[1]);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    let print_format = args
+        .iter()
+        .position(|arg| arg == "--print")
+        .map(|i| {
+            args.get(i + 1)
+                .ok_or("--print requires infix, sexpr, or json")
+        })
+        .transpose()?;
+    let mode = args
+        .iter()
+        .position(|arg| arg == "--values")
+        .map(|i| {
+            args.get(i + 1)
+                .ok_or("--values requires integer, rational, or float")?
+                .parse()
+        })
+        .transpose()?
+        .unwrap_or(ValueMode::Integer);
+    let operator_tables = [
+        OperatorTable::equal_precedence(),
+        OperatorTable::addition_first(),
+    ];
+    let lines = io::stdin().lock().lines().collect::<Result<Vec<_>, _>>()?;
+    if let Some(format) = print_format {
+        for (i, line) in lines.iter().enumerate() {
+            print_line(line, &operator_tables, format)
+                .map_err(|err| format!("Line {}: {}", i + 1, err))?;
+        }
+        return Ok(());
+    }
+    match mode {
+        ValueMode::Integer => print_sums(&sum_lines::<Number>(&lines, &operator_tables)?),
+        ValueMode::Rational => print_sums(&sum_lines::<Rational>(&lines, &operator_tables)?),
+        ValueMode::Float => print_sums(&sum_lines::<f64>(&lines, &operator_tables)?),
+    }
+    Ok(())
+}
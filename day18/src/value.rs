@@ -0,0 +1,244 @@
+use super::{EvaluationError, Number, Operator, ValueType};
+use num_rational::Ratio;
+use num_traits::{
+    checked_pow, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Signed, ToPrimitive, Zero,
+};
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+pub type Rational = Ratio<Number>;
+
+// The arithmetic of a type expressions can be evaluated in. Literals are
+// integers, so every value type must be able to represent those.
+pub trait Value: Clone + Debug + Display + PartialOrd {
+    fn from_literal(v: ValueType) -> Self;
+    fn negate(self) -> Result<Self, EvaluationError>;
+    fn apply(op: Operator, lhs: Self, rhs: Self) -> Result<Self, EvaluationError>;
+
+    fn is_negative(&self) -> bool {
+        *self < Self::from_literal(0)
+    }
+}
+
+// Integer division truncates towards zero.
+impl Value for Number {
+    fn from_literal(v: ValueType) -> Self {
+        Number::from(v)
+    }
+
+    fn negate(self) -> Result<Self, EvaluationError> {
+        CheckedSub::checked_sub(&Number::zero(), &self).ok_or(EvaluationError::Overflow)
+    }
+
+    fn apply(op: Operator, lhs: Self, rhs: Self) -> Result<Self, EvaluationError> {
+        match op {
+            Operator::Add => CheckedAdd::checked_add(&lhs, &rhs).ok_or(EvaluationError::Overflow),
+            Operator::Subtract => {
+                CheckedSub::checked_sub(&lhs, &rhs).ok_or(EvaluationError::Overflow)
+            }
+            Operator::Multiply => {
+                CheckedMul::checked_mul(&lhs, &rhs).ok_or(EvaluationError::Overflow)
+            }
+            Operator::Divide if rhs.is_zero() => Err(EvaluationError::DivisionByZero),
+            Operator::Divide => {
+                CheckedDiv::checked_div(&lhs, &rhs).ok_or(EvaluationError::Overflow)
+            }
+            Operator::Power if Signed::is_negative(&rhs) => {
+                Err(EvaluationError::NegativeExponent(rhs))
+            }
+            Operator::Power => rhs
+                .to_usize()
+                .and_then(|exponent| checked_pow(lhs, exponent))
+                .ok_or(EvaluationError::Overflow),
+        }
+    }
+}
+
+// Exact fractions. Exponents must be integers, but may be negative.
+impl Value for Rational {
+    fn from_literal(v: ValueType) -> Self {
+        Ratio::from_integer(Number::from(v))
+    }
+
+    fn negate(self) -> Result<Self, EvaluationError> {
+        CheckedSub::checked_sub(&Ratio::zero(), &self).ok_or(EvaluationError::Overflow)
+    }
+
+    fn apply(op: Operator, lhs: Self, rhs: Self) -> Result<Self, EvaluationError> {
+        match op {
+            Operator::Add => CheckedAdd::checked_add(&lhs, &rhs).ok_or(EvaluationError::Overflow),
+            Operator::Subtract => {
+                CheckedSub::checked_sub(&lhs, &rhs).ok_or(EvaluationError::Overflow)
+            }
+            Operator::Multiply => {
+                CheckedMul::checked_mul(&lhs, &rhs).ok_or(EvaluationError::Overflow)
+            }
+            Operator::Divide if rhs.is_zero() => Err(EvaluationError::DivisionByZero),
+            Operator::Divide => {
+                CheckedDiv::checked_div(&lhs, &rhs).ok_or(EvaluationError::Overflow)
+            }
+            Operator::Power if !rhs.is_integer() => {
+                Err(EvaluationError::NonIntegerExponent(rhs.to_string()))
+            }
+            Operator::Power => {
+                let exponent = rhs.to_integer();
+                let power = Signed::abs(&exponent)
+                    .to_usize()
+                    .and_then(|exponent| checked_pow(lhs, exponent))
+                    .ok_or(EvaluationError::Overflow)?;
+                if !Signed::is_negative(&exponent) {
+                    Ok(power)
+                } else if power.is_zero() {
+                    Err(EvaluationError::DivisionByZero)
+                } else {
+                    CheckedDiv::checked_div(&Ratio::one(), &power).ok_or(EvaluationError::Overflow)
+                }
+            }
+        }
+    }
+}
+
+// Results that are not finite count as an overflow.
+impl Value for f64 {
+    fn from_literal(v: ValueType) -> Self {
+        v as f64
+    }
+
+    fn negate(self) -> Result<Self, EvaluationError> {
+        Ok(-self)
+    }
+
+    fn apply(op: Operator, lhs: Self, rhs: Self) -> Result<Self, EvaluationError> {
+        let result = match op {
+            Operator::Add => lhs + rhs,
+            Operator::Subtract => lhs - rhs,
+            Operator::Multiply => lhs * rhs,
+            Operator::Divide if rhs == 0.0 => return Err(EvaluationError::DivisionByZero),
+            Operator::Divide => lhs / rhs,
+            Operator::Power => lhs.powf(rhs),
+        };
+        if result.is_finite() {
+            Ok(result)
+        } else {
+            Err(EvaluationError::Overflow)
+        }
+    }
+}
+
+// The value types selectable at runtime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueMode {
+    Integer,
+    Rational,
+    Float,
+}
+
+impl FromStr for ValueMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "integer" => Ok(Self::Integer),
+            "rational" => Ok(Self::Rational),
+            "float" => Ok(Self::Float),
+            _ => Err(format!(
+                "unknown value mode '{}', expected integer, rational, or float",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ast, OperatorTable, Token, TokenizeError, Tokenizer};
+    use rstest::rstest;
+
+    // Evaluates with both operator tables, which must agree, so that the
+    // expected values do not depend on the precedence rules.
+    fn evaluate<V: Value + PartialEq>(input: &str) -> Result<V, EvaluationError> {
+        let tokens: Result<Vec<Token>, TokenizeError> = Tokenizer::new(input).collect();
+        let tokens = tokens.unwrap();
+        let mut results: Vec<Result<V, EvaluationError>> = [
+            OperatorTable::equal_precedence(),
+            OperatorTable::addition_first(),
+        ]
+        .iter()
+        .map(|operators| {
+            Ast::parse(&mut tokens.iter().cloned(), operators)
+                .unwrap()
+                .evaluate_as()
+        })
+        .collect();
+        assert_eq!(results[0], results[1], "{}", input);
+        results.swap_remove(0)
+    }
+
+    fn rational(numerator: i64, denominator: i64) -> Rational {
+        Ratio::new(Number::from(numerator), Number::from(denominator))
+    }
+
+    #[rstest(
+        input,
+        integer,
+        numerator,
+        denominator,
+        float,
+        case("(1 / 4) + (1 / 8)", 0, 3, 8, 0.375),
+        case("(7 / 2) * 2", 6, 7, 1, 7.0),
+        case("(-7) / 2", -3, -7, 2, -3.5),
+        case("(2 / 3) ^ 2", 0, 4, 9, 0.4444444444444444),
+        case("(1 + 2) * (3 - 4)", -3, -3, 1, -3.0),
+        case("max(1 / 2, 1 / 3)", 0, 1, 2, 0.5),
+        case("abs(1 - (5 / 2))", 1, 3, 2, 1.5)
+    )]
+    fn test_value_modes(input: &str, integer: i64, numerator: i64, denominator: i64, float: f64) {
+        assert_eq!(evaluate::<Number>(input), Ok(Number::from(integer)));
+        assert_eq!(
+            evaluate::<Rational>(input),
+            Ok(rational(numerator, denominator))
+        );
+        assert_eq!(evaluate::<f64>(input), Ok(float));
+    }
+
+    #[test]
+    fn test_exponents() {
+        assert_eq!(
+            evaluate::<Number>("2 ^ (-2)"),
+            Err(EvaluationError::NegativeExponent(Number::from(-2)))
+        );
+        assert_eq!(evaluate::<Rational>("2 ^ (-2)"), Ok(rational(1, 4)));
+        assert_eq!(evaluate::<f64>("2 ^ (-2)"), Ok(0.25));
+        assert_eq!(
+            evaluate::<Rational>("4 ^ (1 / 2)"),
+            Err(EvaluationError::NonIntegerExponent(String::from("1/2")))
+        );
+        assert_eq!(evaluate::<f64>("4 ^ (1 / 2)"), Ok(2.0));
+        assert_eq!(
+            evaluate::<Rational>("0 ^ (-1)"),
+            Err(EvaluationError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            evaluate::<Rational>("1 / (1 - 1)"),
+            Err(EvaluationError::DivisionByZero)
+        );
+        assert_eq!(
+            evaluate::<f64>("1 / (1 - 1)"),
+            Err(EvaluationError::DivisionByZero)
+        );
+        assert_eq!(evaluate::<f64>("10 ^ 400"), Err(EvaluationError::Overflow));
+    }
+
+    #[test]
+    fn test_parse_value_mode() {
+        assert_eq!("integer".parse(), Ok(ValueMode::Integer));
+        assert_eq!("rational".parse(), Ok(ValueMode::Rational));
+        assert_eq!("float".parse(), Ok(ValueMode::Float));
+        assert!("decimal".parse::<ValueMode>().is_err());
+    }
+}
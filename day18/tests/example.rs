@@ -0,0 +1,16 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day18"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(
+        run("example.txt", &[]),
+        "Equal precedence: 26457\nAddition first: 694173\n"
+    );
+}
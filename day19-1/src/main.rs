@@ -1,144 +1,37 @@
-use nom::{
-    branch::alt,
-    character::complete::{alphanumeric1, char, digit1, space0, space1},
-    combinator::map,
-    combinator::map_res,
-    multi::separated_list1,
-    sequence::{delimited, separated_pair, tuple},
-    IResult,
-};
-use std::collections::HashMap;
-use std::fmt::Display;
-use std::fmt::Formatter;
-use std::io::{self, BufRead};
-
-#[derive(Debug, PartialEq)]
-enum ProductionRule {
-    Terminal(String),
-    OneOf(Vec<Box<ProductionRule>>),
-    Sequence(Vec<Box<ProductionRule>>),
-    Ref(usize),
-}
-
-#[derive(Debug, PartialEq)]
-struct Grammar {
-    rules: HashMap<usize, ProductionRule>,
-    root: usize,
-}
-
-impl ProductionRule {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        let terminal = map(delimited(char('"'), alphanumeric1, char('"')), |token| {
-            Self::Terminal(String::from(token))
-        });
-        let reference = map(map_res(digit1, |num: &str| num.parse()), |token| {
-            Self::Ref(token)
-        });
-        let sequence = map(
-            separated_list1(space1, alt((terminal, reference))),
-            |tokens| Self::Sequence(tokens.into_iter().map(Box::new).collect()),
-        );
-        map(
-            separated_list1(tuple((space0, char('|'), space0)), sequence),
-            |tokens| Self::OneOf(tokens.into_iter().map(Box::new).collect()),
-        )(input)
-    }
-}
-
-#[derive(Debug)]
-enum GrammarParseError {
-    RuleParseError,
-    ExtraCharacters,
-}
-
-impl Display for GrammarParseError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match self {
-            Self::RuleParseError => f.write_str("Error while parsing rule.")?,
-            Self::ExtraCharacters => f.write_str("Extra characters after rule.")?,
+use cfg::cli::{parse_args, select_root, set_num_threads, Args};
+use cfg::{count_accepted, CompileError, EarleyParser, Grammar};
+use parsing::records;
+use std::env;
+use std::error::Error;
+use std::io::{self, Read};
+
+fn process(input: &str, args: &Args) -> Result<usize, Box<dyn Error>> {
+    let mut records = records(input);
+    let mut grammar = Grammar::parse(&mut records.next().unwrap_or("").lines())?;
+    grammar.override_rules(&mut args.overrides.iter())?;
+    let grammar = select_root(grammar, args.root)?;
+    let messages: Vec<&str> = records.flat_map(str::lines).collect();
+    // Overrides may introduce recursion, which requires the general parser.
+    Ok(match grammar.to_dfa() {
+        Ok(compiled) => count_accepted(&messages, |message| compiled.accepts(message)),
+        Err(CompileError::RecursiveRule(_)) => {
+            let parser = EarleyParser::new(&grammar);
+            count_accepted(&messages, |message| parser.accepts(message))
         }
-        Ok(())
-    }
+        Err(err) => return Err(err.into()),
+    })
 }
 
-impl std::error::Error for GrammarParseError {}
-
-impl Grammar {
-    fn parse_rule(input: &str) -> IResult<&str, (usize, ProductionRule)> {
-        let key = map_res(digit1, |num: &str| num.parse());
-        let separator = tuple((char(':'), space0));
-        separated_pair(key, separator, ProductionRule::parse)(input)
-    }
-
-    fn parse_lines(
-        lines: &mut impl Iterator<Item = impl AsRef<str>>,
-    ) -> Result<Self, GrammarParseError> {
-        Ok(Self {
-            rules: lines
-                .map(|line| {
-                    let (extra_chars, keyed_rule) = Self::parse_rule(line.as_ref())
-                        .map_err(|err| GrammarParseError::RuleParseError)?;
-                    if extra_chars.is_empty() {
-                        Ok(keyed_rule)
-                    } else {
-                        Err(GrammarParseError::ExtraCharacters)
-                    }
-                })
-                .collect::<Result<HashMap<_, _>, _>>()?,
-            root: 0,
-        })
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args(env::args().skip(1))?;
+    if let Some(n_threads) = args.n_threads {
+        set_num_threads(n_threads)?;
     }
-
-    fn rule_accepts<'a>(&self, rule: &ProductionRule, input: &'a str) -> Option<&'a str> {
-        use ProductionRule::*;
-        match rule {
-            Terminal(terminal) => {
-                if input.starts_with(terminal) {
-                    Some(&input[terminal.len()..])
-                } else {
-                    None
-                }
-            }
-            Sequence(children) => {
-                let mut input = input;
-                for child in children {
-                    if let Some(remainder) = self.rule_accepts(child, input) {
-                        input = remainder;
-                    } else {
-                        return None;
-                    }
-                }
-                Some(input)
-            }
-            OneOf(children) => {
-                for child in children {
-                    if let Some(remainder) = self.rule_accepts(child, input) {
-                        return Some(remainder);
-                    }
-                }
-                None
-            }
-            Ref(referenced_rule) => self.rule_accepts(self.rules.get(referenced_rule)?, input),
-        }
-    }
-
-    fn accepts(&self, input: &str) -> bool {
-        if let Some(root) = self.rules.get(&self.root) {
-            if let Some(remainder) = self.rule_accepts(root, input) {
-                return remainder.is_empty();
-            }
-        }
-        false
-    }
-}
-
-fn main() {
-    let stdin = io::stdin();
-    let mut lines = stdin.lock().lines().map(Result::unwrap);
-    let grammar =
-        Grammar::parse_lines(&mut lines.by_ref().take_while(|line| !line.is_empty())).unwrap();
-    let count = lines.filter(|line| grammar.accepts(line)).count();
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let count = process(&input, &args)?;
     println!("{}", count);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -146,76 +39,43 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_productiion_rule() {
-        let (_, rule) = ProductionRule::parse("1 3 | 3 \"x\"").unwrap();
-        assert_eq!(
-            rule,
-            ProductionRule::OneOf(vec![
-                Box::new(ProductionRule::Sequence(vec![
-                    Box::new(ProductionRule::Ref(1)),
-                    Box::new(ProductionRule::Ref(3)),
-                ])),
-                Box::new(ProductionRule::Sequence(vec![
-                    Box::new(ProductionRule::Ref(3)),
-                    Box::new(ProductionRule::Terminal("x".into())),
-                ])),
-            ])
-        );
-    }
-
-    #[test]
-    fn test_parse_grammar_rule() {
-        let (_, rule) = Grammar::parse_rule("0: 3 \"b\"").unwrap();
-        assert_eq!(
-            rule,
-            (
-                0,
-                ProductionRule::OneOf(vec![Box::new(ProductionRule::Sequence(vec![
-                    Box::new(ProductionRule::Ref(3)),
-                    Box::new(ProductionRule::Terminal("b".into()))
-                ]))])
-            )
-        )
-    }
-
-    #[test]
-    fn test_parse_grammar_lines() {
-        let grammar = Grammar::parse_lines(vec!["0: 1", "1: \"b\""].iter()).unwrap();
-        assert_eq!(
-            grammar,
-            Grammar {
-                root: 0,
-                rules: vec![
-                    (
-                        0,
-                        ProductionRule::OneOf(vec![Box::new(ProductionRule::Sequence(vec![
-                            Box::new(ProductionRule::Ref(1)),
-                        ]))])
-                    ),
-                    (
-                        1,
-                        ProductionRule::OneOf(vec![Box::new(ProductionRule::Sequence(vec![
-                            Box::new(ProductionRule::Terminal("b".into())),
-                        ]))])
-                    )
-                ]
-                .into_iter()
-                .collect()
-            }
-        );
+    fn test_process() {
+        let input = [
+            "0: 4 1 5",
+            "1: 2 3 | 3 2",
+            "2: 4 4 | 5 5",
+            "3: 4 5 | 5 4",
+            "4: \"a\"",
+            "5: \"b\"",
+            "",
+            "ababbb",
+            "bababa",
+            "abbbab",
+            "aaabbb",
+            "aaaabbb",
+        ]
+        .join("\n");
+        let with_overrides = |overrides: &[&str]| Args {
+            overrides: overrides.iter().map(|&rule| String::from(rule)).collect(),
+            ..Args::default()
+        };
+        assert_eq!(process(&input, &Args::default()).unwrap(), 2);
+        let overrides = with_overrides(&["4: \"a\" | \"b\""]);
+        assert_eq!(process(&input, &overrides).unwrap(), 3);
+        let recursive = with_overrides(&["1: 2 3 | 3 2 | 2 1 3"]);
+        assert_eq!(process(&input, &recursive).unwrap(), 2);
     }
 
     #[test]
-    fn test_grammar_accepts() {
-        let grammar =
-            Grammar::parse_lines(vec!["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""].iter())
-                .unwrap();
-        assert_eq!(grammar.accepts("aab"), true);
-        assert_eq!(grammar.accepts("aba"), true);
-        assert_eq!(grammar.accepts("aaa"), false);
-        assert_eq!(grammar.accepts("baa"), false);
-        assert_eq!(grammar.accepts("bab"), false);
-        assert_eq!(grammar.accepts("bba"), false);
-        assert_eq!(grammar.accepts("bbb"), false);
+    fn test_process_with_root() {
+        let input = "0: 1 1\n1: 2 3 | 3 2\n2: \"a\"\n3: \"b\"\n\nab\nba\nabba\naa";
+        let root = |root| Args {
+            root: Some(root),
+            ..Args::default()
+        };
+        assert_eq!(process(input, &Args::default()).unwrap(), 1);
+        assert_eq!(process(input, &root(1)).unwrap(), 2);
+        assert_eq!(process(input, &root(2)).unwrap(), 0);
+        assert!(process(input, &root(4)).is_err());
     }
 }
@@ -1,186 +1,110 @@
-use nom::{
-    branch::alt,
-    character::complete::{alphanumeric1, char, digit1, space0, space1},
-    combinator::map,
-    combinator::map_res,
-    multi::separated_list1,
-    sequence::{delimited, separated_pair, tuple},
-    IResult,
-};
-use std::collections::HashMap;
-use std::fmt::Display;
-use std::fmt::Formatter;
-use std::io::{self, BufRead};
-
-#[derive(Clone, Debug, PartialEq)]
-enum ProductionRule {
-    Terminal(String),
-    OneOf(Vec<Box<ProductionRule>>),
-    Sequence(Vec<Box<ProductionRule>>),
-    Ref(usize),
-}
-
-#[derive(Debug, PartialEq)]
-struct Grammar {
-    rules: HashMap<usize, ProductionRule>,
-    root: usize,
+use cfg::{cli, count_accepted, DerivationError, EarleyParser, Grammar};
+use parsing::records;
+use std::env;
+use std::error::Error;
+use std::io::{self, Read};
+#[cfg(feature = "profile")]
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+#[cfg(feature = "profile")]
+use tracing_subscriber::prelude::*;
+
+// Part 2 replaces these rules before any overrides from the command line.
+const RULE_EDITS: [&str; 2] = ["8: 42 | 42 8", "11: 42 31 | 42 11 31"];
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    grammar: cli::Args,
+    show_derivations: bool,
+    profile: Option<String>,
 }
 
-impl ProductionRule {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        let terminal = map(delimited(char('"'), alphanumeric1, char('"')), |token| {
-            Self::Terminal(String::from(token))
-        });
-        let reference = map(map_res(digit1, |num: &str| num.parse()), |token| {
-            Self::Ref(token)
-        });
-        let sequence = map(
-            separated_list1(space1, alt((terminal, reference))),
-            |tokens| Self::Sequence(tokens.into_iter().map(Box::new).collect()),
-        );
-        map(
-            separated_list1(tuple((space0, char('|'), space0)), sequence),
-            |tokens| Self::OneOf(tokens.into_iter().map(Box::new).collect()),
-        )(input)
+fn print_derivations(grammar: &Grammar, line: &str) -> Result<(), DerivationError> {
+    let derivations = grammar.derivations(line)?;
+    if derivations.len() > 1 {
+        println!("{}: ambiguous, {} derivations", line, derivations.len());
+    } else {
+        println!("{}:", line);
     }
+    for derivation in derivations {
+        println!("  {}", derivation);
+    }
+    Ok(())
 }
 
-#[derive(Debug)]
-enum GrammarParseError {
-    RuleParseError,
-    ExtraCharacters,
-}
-
-impl Display for GrammarParseError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match self {
-            Self::RuleParseError => f.write_str("Error while parsing rule.")?,
-            Self::ExtraCharacters => f.write_str("Extra characters after rule.")?,
+fn process(input: &str, args: &Args) -> Result<usize, Box<dyn Error>> {
+    let mut records = records(input);
+    let (grammar, messages) = {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("parse").entered();
+        let mut grammar = Grammar::parse(&mut records.next().unwrap_or("").lines())?;
+        grammar.override_rules(&mut RULE_EDITS.iter())?;
+        grammar.override_rules(&mut args.grammar.overrides.iter())?;
+        let messages: Vec<&str> = records.flat_map(str::lines).collect();
+        (cli::select_root(grammar, args.grammar.root)?, messages)
+    };
+    let parser = EarleyParser::new(&grammar);
+    if args.show_derivations {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("derivations").entered();
+        // Refused up front, even if no message is accepted.
+        if let Some(id) = grammar.left_recursive_rule() {
+            return Err(format!(
+                "--derivations does not support left-recursive grammars: {}",
+                DerivationError::LeftRecursiveRule(id)
+            )
+            .into());
+        }
+        for message in messages.iter().map(AsRef::as_ref) {
+            if parser.accepts(message) {
+                print_derivations(&grammar, message)?;
+            }
         }
-        Ok(())
     }
+    Ok(count_accepted(&messages, |message| parser.accepts(message)))
 }
 
-impl std::error::Error for GrammarParseError {}
-
-impl Grammar {
-    fn parse_rule(input: &str) -> IResult<&str, (usize, ProductionRule)> {
-        let key = map_res(digit1, |num: &str| num.parse());
-        let separator = tuple((char(':'), space0));
-        separated_pair(key, separator, ProductionRule::parse)(input)
-    }
-
-    fn parse_lines(
-        lines: &mut impl Iterator<Item = impl AsRef<str>>,
-    ) -> Result<Self, GrammarParseError> {
-        Ok(Self {
-            rules: lines
-                .map(|line| {
-                    let (extra_chars, keyed_rule) = Self::parse_rule(line.as_ref())
-                        .map_err(|err| GrammarParseError::RuleParseError)?;
-                    if extra_chars.is_empty() {
-                        Ok(keyed_rule)
-                    } else {
-                        Err(GrammarParseError::ExtraCharacters)
-                    }
-                })
-                .collect::<Result<HashMap<_, _>, _>>()?,
-            root: 0,
-        })
-    }
+// Records the spans in chrome://tracing format. The file is completed when
+// the guard is dropped.
+#[cfg(feature = "profile")]
+fn start_profile(path: &str) -> Result<FlushGuard, Box<dyn Error>> {
+    let (layer, guard) = ChromeLayerBuilder::new().file(path).build();
+    tracing_subscriber::registry().with(layer).init();
+    Ok(guard)
+}
 
-    fn rule_accepts<'a>(&self, rule: &ProductionRule, inputs: &Vec<&'a str>) -> Vec<&'a str> {
-        use ProductionRule::*;
-        inputs
-            .iter()
-            .flat_map(|input| match rule {
-                Terminal(terminal) => {
-                    if input.starts_with(terminal) {
-                        vec![&input[terminal.len()..]]
-                    } else {
-                        vec![]
-                    }
-                }
-                Sequence(children) => {
-                    if children.len() == 1 {
-                        self.rule_accepts(&children[0], &vec![input])
-                    } else {
-                        self.rule_accepts(&children[0], &vec![input])
-                            .iter()
-                            .flat_map(|remainder| {
-                                self.rule_accepts(
-                                    &ProductionRule::Sequence(
-                                        children.iter().skip(1).cloned().collect(),
-                                    ),
-                                    &vec![remainder],
-                                )
-                            })
-                            .collect()
-                    }
-                }
-                OneOf(children) => children
-                    .iter()
-                    .flat_map(|child| self.rule_accepts(child, &vec![input]))
-                    .collect(),
-                Ref(referenced_rule) => {
-                    if let Some(child_rule) = self.rules.get(referenced_rule) {
-                        self.rule_accepts(child_rule, &vec![input])
-                    } else {
-                        vec![]
-                    }
-                }
-            })
-            .collect()
-    }
+#[cfg(not(feature = "profile"))]
+fn start_profile(_path: &str) -> Result<(), Box<dyn Error>> {
+    Err("--profile requires the profile feature".into())
+}
 
-    fn accepts(&self, input: &str) -> bool {
-        if let Some(root) = self.rules.get(&self.root) {
-            for remainder in self.rule_accepts(root, &vec![input]) {
-                if remainder.is_empty() {
-                    return true;
-                }
-            }
+// Takes the arguments of cli::parse_args. `--derivations` prints the parse
+// trees of all accepted messages. `--profile trace.json` records a
+// chrome://tracing profile with the profile feature.
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, Box<dyn Error>> {
+    let mut args = args;
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--derivations" => parsed.show_derivations = true,
+            "--profile" => parsed.profile = Some(args.next().ok_or("--profile requires a file")?),
+            _ if cli::parse_arg(&mut parsed.grammar, &arg, &mut args)? => {}
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
         }
-        false
     }
+    Ok(parsed)
 }
 
-fn process(lines: impl Iterator<Item = impl AsRef<str>>) -> usize {
-    let mut lines = lines;
-    let mut grammar =
-        Grammar::parse_lines(&mut lines.by_ref().take_while(|line| !line.as_ref().is_empty()))
-            .unwrap();
-    grammar.rules.insert(
-        8,
-        ProductionRule::OneOf(vec![
-            Box::new(ProductionRule::Sequence(vec![
-                Box::new(ProductionRule::Ref(42)),
-                Box::new(ProductionRule::Ref(8)),
-            ])),
-            Box::new(ProductionRule::Ref(42)),
-        ]),
-    );
-    grammar.rules.insert(
-        11,
-        ProductionRule::OneOf(vec![
-            Box::new(ProductionRule::Sequence(vec![
-                Box::new(ProductionRule::Ref(42)),
-                Box::new(ProductionRule::Ref(11)),
-                Box::new(ProductionRule::Ref(31)),
-            ])),
-            Box::new(ProductionRule::Sequence(vec![
-                Box::new(ProductionRule::Ref(42)),
-                Box::new(ProductionRule::Ref(31)),
-            ])),
-        ]),
-    );
-    lines.filter(|line| grammar.accepts(line.as_ref())).count()
-}
-
-fn main() {
-    let stdin = io::stdin();
-    let count = process(stdin.lock().lines().map(Result::unwrap));
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args(env::args().skip(1))?;
+    if let Some(n_threads) = args.grammar.n_threads {
+        cli::set_num_threads(n_threads)?;
+    }
+    let _guard = args.profile.as_deref().map(start_profile).transpose()?;
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let count = process(&input, &args)?;
     println!("{}", count);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -188,83 +112,40 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_productiion_rule() {
-        let (_, rule) = ProductionRule::parse("1 3 | 3 \"x\"").unwrap();
-        assert_eq!(
-            rule,
-            ProductionRule::OneOf(vec![
-                Box::new(ProductionRule::Sequence(vec![
-                    Box::new(ProductionRule::Ref(1)),
-                    Box::new(ProductionRule::Ref(3)),
-                ])),
-                Box::new(ProductionRule::Sequence(vec![
-                    Box::new(ProductionRule::Ref(3)),
-                    Box::new(ProductionRule::Terminal("x".into())),
-                ])),
-            ])
-        );
-    }
-
-    #[test]
-    fn test_parse_grammar_rule() {
-        let (_, rule) = Grammar::parse_rule("0: 3 \"b\"").unwrap();
-        assert_eq!(
-            rule,
-            (
-                0,
-                ProductionRule::OneOf(vec![Box::new(ProductionRule::Sequence(vec![
-                    Box::new(ProductionRule::Ref(3)),
-                    Box::new(ProductionRule::Terminal("b".into()))
-                ]))])
-            )
-        )
-    }
-
-    #[test]
-    fn test_parse_grammar_lines() {
-        let grammar = Grammar::parse_lines(&mut vec!["0: 1", "1: \"b\""].iter()).unwrap();
+    fn test_parse_args() {
+        let args = [
+            "--override",
+            "8: 42 8 | 42",
+            "--derivations",
+            "--override",
+            "0: 8",
+            "--profile",
+            "trace.json",
+            "--threads",
+            "4",
+            "--root",
+            "42",
+        ];
         assert_eq!(
-            grammar,
-            Grammar {
-                root: 0,
-                rules: vec![
-                    (
-                        0,
-                        ProductionRule::OneOf(vec![Box::new(ProductionRule::Sequence(vec![
-                            Box::new(ProductionRule::Ref(1)),
-                        ]))])
-                    ),
-                    (
-                        1,
-                        ProductionRule::OneOf(vec![Box::new(ProductionRule::Sequence(vec![
-                            Box::new(ProductionRule::Terminal("b".into())),
-                        ]))])
-                    )
-                ]
-                .into_iter()
-                .collect()
+            parse_args(args.iter().map(|&arg| String::from(arg))).unwrap(),
+            Args {
+                grammar: cli::Args {
+                    overrides: vec![String::from("8: 42 8 | 42"), String::from("0: 8")],
+                    root: Some(42),
+                    n_threads: Some(4),
+                },
+                show_derivations: true,
+                profile: Some(String::from("trace.json")),
             }
         );
-    }
-
-    #[test]
-    fn test_grammar_accepts() {
-        let grammar = Grammar::parse_lines(
-            &mut vec!["0: 1 2", "1: \"a\"", "2: 1 3 | 3 1", "3: \"b\""].iter(),
-        )
-        .unwrap();
-        assert_eq!(grammar.accepts("aab"), true);
-        assert_eq!(grammar.accepts("aba"), true);
-        assert_eq!(grammar.accepts("aaa"), false);
-        assert_eq!(grammar.accepts("baa"), false);
-        assert_eq!(grammar.accepts("bab"), false);
-        assert_eq!(grammar.accepts("bba"), false);
-        assert_eq!(grammar.accepts("bbb"), false);
+        assert!(parse_args(["--override"].iter().map(|&arg| String::from(arg))).is_err());
+        assert!(parse_args(["--root"].iter().map(|&arg| String::from(arg))).is_err());
+        assert!(parse_args(["--threads", "x"].iter().map(|&arg| String::from(arg))).is_err());
     }
 
     #[test]
     fn test_process() {
-        let input = vec![
+        let input = [
             "42: 9 14 | 10 1",
             "9: 14 27 | 1 26",
             "10: 23 14 | 28 1",
@@ -312,7 +193,29 @@ mod tests {
             "aaaabbaabbaaaaaaabbbabbbaaabbaabaaa",
             "babaaabbbaaabaababbaabababaaab",
             "aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba",
-        ];
-        assert_eq!(process(input.iter()), 12);
+        ]
+        .join("\n");
+        assert_eq!(process(&input, &Args::default()).unwrap(), 12);
+        let left_recursive = Args {
+            grammar: cli::Args {
+                overrides: vec![String::from("8: 42 | 8 42")],
+                ..cli::Args::default()
+            },
+            ..Args::default()
+        };
+        assert_eq!(process(&input, &left_recursive).unwrap(), 12);
+        let left_recursive_derivations = Args {
+            show_derivations: true,
+            ..left_recursive
+        };
+        assert!(process(&input, &left_recursive_derivations).is_err());
+        let rule_42 = Args {
+            grammar: cli::Args {
+                root: Some(42),
+                ..cli::Args::default()
+            },
+            ..Args::default()
+        };
+        assert_eq!(process(&input, &rule_42).unwrap(), 0);
     }
 }
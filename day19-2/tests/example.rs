@@ -0,0 +1,19 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day19"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(run("example.txt", &[]), "12\n");
+}
+
+#[test]
+fn test_example_without_loops() {
+    let args = ["--override", "8: 42", "--override", "11: 42 31"];
+    assert_eq!(run("example.txt", &args), "3\n");
+}
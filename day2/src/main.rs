@@ -1,100 +1,433 @@
+use std::env;
 use std::io::{self, BufRead};
+use std::iter::Peekable;
 
 type Password = String;
 
 trait Policy {
-    fn parse(definition: &str) -> Self;
     fn check_password(&self, password: &Password) -> bool;
 }
 
+// The definition in front of each password, i.e. a character and two numbers
+// that the policies interpret differently.
+#[derive(Debug, PartialEq)]
+struct Definition {
+    character: char,
+    numbers: (usize, usize),
+}
+
+impl Definition {
+    fn from_parts(min: &str, max: &str, character: &str) -> Option<Self> {
+        let mut chars = character.chars();
+        let character = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return None,
+        };
+        Some(Self {
+            character,
+            numbers: (min.parse().ok()?, max.parse().ok()?),
+        })
+    }
+
+    // Parses the plain format `1-3 a`.
+    fn parse(definition: &str) -> Result<Self, String> {
+        definition
+            .split_once(' ')
+            .and_then(|(numbers, character)| {
+                let (min, max) = numbers.split_once('-')?;
+                Self::from_parts(min, max, character)
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Invalid policy '{}', expected a format like '1-3 a'.",
+                    definition
+                )
+            })
+    }
+}
+
+// Policies given by the definition in front of each password.
+trait FromDefinition: Sized {
+    fn from_definition(definition: &Definition) -> Self;
+}
+
 struct OldPolicy {
     character: char,
     occurences_bounds: (usize, usize),
 }
 
-impl OldPolicy {
-    fn parse(definition: &str) -> Self {
-        let split: Vec<&str> = definition.splitn(2, ' ').collect();
-        let character = split[1].chars().next().unwrap();
-        let split: Vec<&str> = split[0].splitn(2, '-').collect();
-        let lower_bound = split[0].parse().unwrap();
-        let upper_bound = split[1].parse().unwrap();
-
+impl FromDefinition for OldPolicy {
+    fn from_definition(definition: &Definition) -> Self {
         Self {
-            character,
-            occurences_bounds: (lower_bound, upper_bound),
+            character: definition.character,
+            occurences_bounds: definition.numbers,
         }
     }
+}
 
+impl Policy for OldPolicy {
     fn check_password(&self, password: &Password) -> bool {
         let count = password.chars().filter(|c| *c == self.character).count();
         self.occurences_bounds.0 <= count && count <= self.occurences_bounds.1
     }
 }
 
+// Positions start at 1, so that position 0 never matches.
 struct NewPolicy {
     character: char,
-    indices: Vec<usize>,
+    positions: (usize, usize),
 }
 
-impl NewPolicy {
-    fn parse(definition: &str) -> Self {
-        let split: Vec<&str> = definition.splitn(2, ' ').collect();
-        let character = split[1].chars().next().unwrap();
-        let indices = split[0]
-            .split('-')
-            .map(str::parse)
-            .map(Result::unwrap)
-            .map(|i: usize| i - 1)
-            .collect();
-        Self { character, indices }
+impl FromDefinition for NewPolicy {
+    fn from_definition(definition: &Definition) -> Self {
+        Self {
+            character: definition.character,
+            positions: definition.numbers,
+        }
     }
+}
 
+impl Policy for NewPolicy {
     fn check_password(&self, password: &Password) -> bool {
-        let mut password_iter = password.chars();
-        let raw_result = self.indices.iter().fold(
-            (0, 0),
-            |(pos_in_password, matching_characters): (usize, usize), i| {
-                let advance_by = i - pos_in_password;
-                let c = password_iter.nth(advance_by).unwrap();
-                if c == self.character {
-                    (i + 1, matching_characters + 1)
-                } else {
-                    (i + 1, matching_characters)
-                }
-            },
-        );
+        let matches = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|i| password.chars().nth(i))
+                == Some(self.character)
+        };
+        matches(self.positions.0) != matches(self.positions.1)
+    }
+}
+
+struct ContainsDigitPolicy;
+
+impl FromDefinition for ContainsDigitPolicy {
+    fn from_definition(_definition: &Definition) -> Self {
+        Self
+    }
+}
+
+impl Policy for ContainsDigitPolicy {
+    fn check_password(&self, password: &Password) -> bool {
+        password.chars().any(|c| c.is_ascii_digit())
+    }
+}
+
+struct AndPolicy<A, B>(A, B);
+struct OrPolicy<A, B>(A, B);
+struct NotPolicy<P>(P);
 
-        raw_result.1 == 1
+impl<A: Policy, B: Policy> Policy for AndPolicy<A, B> {
+    fn check_password(&self, password: &Password) -> bool {
+        self.0.check_password(password) && self.1.check_password(password)
+    }
+}
+
+impl<A: Policy, B: Policy> Policy for OrPolicy<A, B> {
+    fn check_password(&self, password: &Password) -> bool {
+        self.0.check_password(password) || self.1.check_password(password)
     }
 }
 
-fn parse_input_line(line: &str) -> (OldPolicy, NewPolicy, Password) {
-    let split: Vec<&str> = line.splitn(2, ':').collect();
-    let policy_definition = split[0];
-    let password = split[1].trim();
-    return (
-        OldPolicy::parse(policy_definition),
-        NewPolicy::parse(policy_definition),
-        String::from(password),
-    );
+impl<P: Policy> Policy for NotPolicy<P> {
+    fn check_password(&self, password: &Password) -> bool {
+        !self.0.check_password(password)
+    }
 }
 
-fn main() {
+// Both parts of a compound policy are parsed from the same definition.
+impl<A: FromDefinition, B: FromDefinition> FromDefinition for AndPolicy<A, B> {
+    fn from_definition(definition: &Definition) -> Self {
+        Self(
+            A::from_definition(definition),
+            B::from_definition(definition),
+        )
+    }
+}
+
+impl<A: FromDefinition, B: FromDefinition> FromDefinition for OrPolicy<A, B> {
+    fn from_definition(definition: &Definition) -> Self {
+        Self(
+            A::from_definition(definition),
+            B::from_definition(definition),
+        )
+    }
+}
+
+impl<P: FromDefinition> FromDefinition for NotPolicy<P> {
+    fn from_definition(definition: &Definition) -> Self {
+        Self(P::from_definition(definition))
+    }
+}
+
+impl<P: Policy + ?Sized> Policy for Box<P> {
+    fn check_password(&self, password: &Password) -> bool {
+        (**self).check_password(password)
+    }
+}
+
+// Compound policy given on the command line like "old and not digit". The
+// operator "not" binds strongest, followed by "and" and "or".
+#[derive(Debug, PartialEq)]
+enum PolicyExpr {
+    Old,
+    New,
+    ContainsDigit,
+    And(Box<PolicyExpr>, Box<PolicyExpr>),
+    Or(Box<PolicyExpr>, Box<PolicyExpr>),
+    Not(Box<PolicyExpr>),
+}
+
+impl PolicyExpr {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let mut tokens = expr.split_whitespace().peekable();
+        let parsed = Self::parse_or(&mut tokens)?;
+        match tokens.next() {
+            Some(token) => Err(format!("Unexpected '{}' in policy.", token)),
+            None => Ok(parsed),
+        }
+    }
+
+    fn parse_or<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>) -> Result<Self, String> {
+        let mut lhs = Self::parse_and(tokens)?;
+        while tokens.next_if_eq(&"or").is_some() {
+            lhs = Self::Or(Box::new(lhs), Box::new(Self::parse_and(tokens)?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>) -> Result<Self, String> {
+        let mut lhs = Self::parse_not(tokens)?;
+        while tokens.next_if_eq(&"and").is_some() {
+            lhs = Self::And(Box::new(lhs), Box::new(Self::parse_not(tokens)?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>) -> Result<Self, String> {
+        match tokens.next() {
+            Some("not") => Ok(Self::Not(Box::new(Self::parse_not(tokens)?))),
+            Some("old") => Ok(Self::Old),
+            Some("new") => Ok(Self::New),
+            Some("digit") => Ok(Self::ContainsDigit),
+            Some(token) => Err(format!("Unknown policy '{}'.", token)),
+            None => Err(String::from("Incomplete policy.")),
+        }
+    }
+
+    fn build(&self, definition: &Definition) -> Box<dyn Policy> {
+        match self {
+            Self::Old => Box::new(OldPolicy::from_definition(definition)),
+            Self::New => Box::new(NewPolicy::from_definition(definition)),
+            Self::ContainsDigit => Box::new(ContainsDigitPolicy),
+            Self::And(lhs, rhs) => {
+                Box::new(AndPolicy(lhs.build(definition), rhs.build(definition)))
+            }
+            Self::Or(lhs, rhs) => Box::new(OrPolicy(lhs.build(definition), rhs.build(definition))),
+            Self::Not(expr) => Box::new(NotPolicy(expr.build(definition))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InputFormat {
+    // `1-3 a: password` as in the puzzle input.
+    Plain,
+    // `min,max,char,password` records, separated by the given character. The
+    // password is the rest of the record and may contain the separator.
+    Delimited(char),
+}
+
+impl InputFormat {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "plain" => Ok(Self::Plain),
+            "csv" => Ok(Self::Delimited(',')),
+            "tsv" => Ok(Self::Delimited('\t')),
+            _ => Err(format!(
+                "Unknown format '{}', expected plain, csv, or tsv.",
+                format
+            )),
+        }
+    }
+
+    fn parse_record(&self, line: &str) -> Result<(Definition, Password), String> {
+        match *self {
+            Self::Plain => {
+                let (definition, password) = line.split_once(':').ok_or_else(|| {
+                    format!(
+                        "Invalid line '{}', expected a policy and a password separated by ':'.",
+                        line
+                    )
+                })?;
+                Ok((
+                    Definition::parse(definition)?,
+                    String::from(password.trim()),
+                ))
+            }
+            Self::Delimited(separator) => {
+                let fields: Vec<&str> = line.splitn(4, separator).collect();
+                let definition = match fields[..] {
+                    [min, max, character, password] => {
+                        Definition::from_parts(min.trim(), max.trim(), character.trim())
+                            .map(|definition| (definition, String::from(password)))
+                    }
+                    _ => None,
+                };
+                definition.ok_or_else(|| {
+                    format!(
+                        "Invalid record '{}', expected min, max, character, and password.",
+                        line
+                    )
+                })
+            }
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut custom_policy = None;
+    let mut format = InputFormat::Plain;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--policy" => {
+                let expr = args.next().ok_or("--policy requires an argument.")?;
+                custom_policy = Some(PolicyExpr::parse(&expr)?);
+            }
+            "--format" => {
+                format = InputFormat::parse(&args.next().ok_or("--format requires an argument.")?)?
+            }
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+
     let mut valid_old = 0;
     let mut valid_new = 0;
-    io::stdin()
-        .lock()
-        .lines()
-        .map(|l| parse_input_line(&l.unwrap()))
-        .for_each(|(old_policy, new_policy, password)| {
-            if old_policy.check_password(&password) {
-                valid_old += 1;
-            }
-            if new_policy.check_password(&password) {
-                valid_new += 1;
+    let mut valid_custom = 0;
+    for line in io::stdin().lock().lines() {
+        let (definition, password) = format.parse_record(&line?)?;
+        if OldPolicy::from_definition(&definition).check_password(&password) {
+            valid_old += 1;
+        }
+        if NewPolicy::from_definition(&definition).check_password(&password) {
+            valid_new += 1;
+        }
+        if let Some(policy) = &custom_policy {
+            if policy.build(&definition).check_password(&password) {
+                valid_custom += 1;
             }
-        });
+        }
+    }
     println!("Valid for old policy: {}", valid_old);
     println!("Valid for new policy: {}", valid_new);
+    if custom_policy.is_some() {
+        println!("Valid for custom policy: {}", valid_custom);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<P: Policy>(policy: &P, password: &str) -> bool {
+        policy.check_password(&String::from(password))
+    }
+
+    fn definition(definition: &str) -> Definition {
+        Definition::parse(definition).unwrap()
+    }
+
+    #[test]
+    fn test_combinators() {
+        let policy: AndPolicy<OldPolicy, NotPolicy<NewPolicy>> =
+            FromDefinition::from_definition(&definition("1-3 a"));
+        assert!(!check(&policy, "abcde"));
+        assert!(check(&policy, "abade"));
+        assert!(!check(&policy, "bbbbb"));
+
+        let policy = OrPolicy(
+            OldPolicy::from_definition(&definition("2-9 c")),
+            ContainsDigitPolicy,
+        );
+        assert!(check(&policy, "ccccc"));
+        assert!(check(&policy, "c2"));
+        assert!(!check(&policy, "cdefg"));
+    }
+
+    #[test]
+    fn test_new_policy_out_of_range() {
+        let policy = NewPolicy::from_definition(&definition("0-3 a"));
+        assert!(check(&policy, "bba"));
+        assert!(!check(&policy, "aab"));
+        assert!(!check(&policy, "ab"));
+        let policy = NewPolicy::from_definition(&definition("1-9 a"));
+        assert!(check(&policy, "ab"));
+    }
+
+    #[test]
+    fn test_parse_definition() {
+        assert_eq!(
+            Definition::parse("1-3 a"),
+            Ok(Definition {
+                character: 'a',
+                numbers: (1, 3),
+            })
+        );
+        assert!(Definition::parse("1-3").is_err());
+        assert!(Definition::parse("1 a").is_err());
+        assert!(Definition::parse("1-x a").is_err());
+        assert!(Definition::parse("1-3 ab").is_err());
+    }
+
+    #[test]
+    fn test_parse_record() {
+        assert_eq!(
+            InputFormat::Plain.parse_record("1-3 a: abcde"),
+            Ok((definition("1-3 a"), String::from("abcde")))
+        );
+        assert!(InputFormat::Plain.parse_record("1-3 a abcde").is_err());
+        assert!(InputFormat::Plain.parse_record("1-3: abcde").is_err());
+        let csv = InputFormat::parse("csv").unwrap();
+        assert_eq!(
+            csv.parse_record("1,3,a,abcde"),
+            Ok((definition("1-3 a"), String::from("abcde")))
+        );
+        assert_eq!(
+            csv.parse_record("2, 9 ,c,cc,cc"),
+            Ok((definition("2-9 c"), String::from("cc,cc")))
+        );
+        assert!(csv.parse_record("1,3,a").is_err());
+        assert!(csv.parse_record("1,x,a,abcde").is_err());
+        assert!(csv.parse_record("1,3,ab,abcde").is_err());
+        let tsv = InputFormat::parse("tsv").unwrap();
+        assert_eq!(
+            tsv.parse_record("1\t3\tb\tcdefg"),
+            Ok((definition("1-3 b"), String::from("cdefg")))
+        );
+        assert!(InputFormat::parse("json").is_err());
+    }
+
+    #[test]
+    fn test_policy_expr() {
+        use PolicyExpr::*;
+        assert_eq!(
+            PolicyExpr::parse("not old and digit or new"),
+            Ok(Or(
+                Box::new(And(Box::new(Not(Box::new(Old))), Box::new(ContainsDigit))),
+                Box::new(New)
+            ))
+        );
+        assert!(PolicyExpr::parse("old and").is_err());
+        assert!(PolicyExpr::parse("old xor new").is_err());
+        assert!(PolicyExpr::parse("odd").is_err());
+
+        let policy = PolicyExpr::parse("old and digit")
+            .unwrap()
+            .build(&definition("1-3 a"));
+        assert!(check(&policy, "a1"));
+        assert!(!check(&policy, "a"));
+        assert!(!check(&policy, "1"));
+    }
 }
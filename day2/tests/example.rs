@@ -0,0 +1,16 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day2"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(
+        run("example.txt", &[]),
+        "Valid for old policy: 2\nValid for new policy: 1\n"
+    );
+}
@@ -0,0 +1,786 @@
+use graph::hopcroft_karp;
+use nom::{
+    bytes::complete::tag,
+    character::complete::{alphanumeric1, char, space0, space1},
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::{delimited, preceded, tuple},
+    IResult,
+};
+use parsing::parse_full;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+pub type Id = u32;
+pub type Ingredient = Id;
+pub type Allergen = Id;
+
+// Maps names to dense ids, so that sets of names can be stored as bitsets.
+// Ids are assigned in order of first occurrence, starting at 0.
+#[derive(Debug, Default, PartialEq)]
+pub struct Interner {
+    ids: HashMap<String, Id>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, name: &str) -> Id {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as Id;
+        self.ids.insert(String::from(name), id);
+        self.names.push(String::from(name));
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<Id> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: Id) -> &str {
+        &self.names[id as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct IdSet {
+    words: Vec<u64>,
+}
+
+impl IdSet {
+    fn empty(capacity: usize) -> Self {
+        Self {
+            words: vec![0; capacity.div_ceil(64)],
+        }
+    }
+
+    fn full(capacity: usize) -> Self {
+        let mut set = Self::empty(capacity);
+        for id in 0..capacity {
+            set.insert(id as Id);
+        }
+        set
+    }
+
+    fn from_ids<'a>(capacity: usize, ids: impl IntoIterator<Item = &'a Id>) -> Self {
+        let mut set = Self::empty(capacity);
+        for &id in ids {
+            set.insert(id);
+        }
+        set
+    }
+
+    fn insert(&mut self, id: Id) {
+        self.words[id as usize / 64] |= 1 << (id % 64);
+    }
+
+    fn contains(&self, id: Id) -> bool {
+        self.words[id as usize / 64] & 1 << (id % 64) != 0
+    }
+
+    fn intersect_with(&mut self, other: &Self) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+    }
+
+    fn difference_with(&mut self, other: &Self) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = Id> + '_ {
+        (0..self.words.len() * 64)
+            .map(|id| id as Id)
+            .filter(move |&id| self.contains(id))
+    }
+}
+
+// A single line of the input. The ids refer to the interners of the Foods
+// the food belongs to. The listed allergens might be incomplete.
+#[derive(Debug, PartialEq)]
+pub struct Food {
+    pub ingredients: Vec<Ingredient>,
+    pub allergens: Vec<Allergen>,
+}
+
+// A list of foods together with the names of their ingredients and
+// allergens.
+#[derive(Debug, Default, PartialEq)]
+pub struct Foods {
+    foods: Vec<Food>,
+    ingredients: Interner,
+    allergens: Interner,
+}
+
+impl Foods {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, ingredients: &[&str], allergens: &[&str]) {
+        let food = Food {
+            ingredients: ingredients
+                .iter()
+                .map(|name| self.ingredients.intern(name))
+                .collect(),
+            allergens: allergens
+                .iter()
+                .map(|name| self.allergens.intern(name))
+                .collect(),
+        };
+        self.foods.push(food);
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        &self.foods
+    }
+
+    pub fn ingredients(&self) -> &Interner {
+        &self.ingredients
+    }
+
+    pub fn allergens(&self) -> &Interner {
+        &self.allergens
+    }
+}
+
+fn parse_food(input: &str) -> IResult<&str, (Vec<&str>, Vec<&str>)> {
+    let allergens = delimited(
+        tag("(contains "),
+        separated_list1(tag(", "), alphanumeric1),
+        char(')'),
+    );
+    map(
+        tuple((
+            preceded(space0, separated_list1(space1, alphanumeric1)),
+            opt(preceded(space1, allergens)),
+            space0,
+        )),
+        |(ingredients, allergens, _)| (ingredients, allergens.unwrap_or_default()),
+    )(input)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FoodParseError {
+    pub line: usize,
+    pub food: String,
+}
+
+impl Display for FoodParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "line {}: invalid food '{}'", self.line, self.food)
+    }
+}
+
+impl std::error::Error for FoodParseError {}
+
+// Parses one food per line, e.g. `abc def (contains dairy, fish)`. The
+// allergen list is optional and blank lines are skipped.
+pub fn parse_foods(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<Foods, FoodParseError> {
+    let mut foods = Foods::new();
+    for (i, line) in lines.enumerate() {
+        let line = line.as_ref();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (ingredients, allergens) =
+            parse_full(parse_food, line).map_err(|_: nom::Err<nom::error::Error<&str>>| {
+                FoodParseError {
+                    line: i + 1,
+                    food: String::from(line),
+                }
+            })?;
+        foods.add(&ingredients, &allergens);
+    }
+    Ok(foods)
+}
+
+// How to assign the allergens to ingredients. The matching always finds an
+// assignment if there is one, but picks an arbitrary one if it is ambiguous.
+// Elimination repeatedly assigns allergens with a single candidate left and
+// removes that ingredient from the candidates of all others. This only
+// succeeds if the assignment is unique, but is how the puzzle is meant to be
+// solved by hand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    Matching,
+    Elimination,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "matching" => Ok(Self::Matching),
+            "elimination" => Ok(Self::Elimination),
+            _ => Err(format!("Unknown strategy '{}'.", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AssignmentError {
+    // The allergens that cannot be assigned to any ingredient.
+    Unsolvable(Vec<String>),
+    // The allergens elimination got stuck with, each having several
+    // candidates left.
+    Ambiguous(Vec<String>),
+}
+
+impl Display for AssignmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Unsolvable(allergens) => write!(
+                f,
+                "no ingredient left for the allergens {}",
+                allergens.join(", ")
+            ),
+            Self::Ambiguous(allergens) => write!(
+                f,
+                "ambiguous assignment of the allergens {}",
+                allergens.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssignmentError {}
+
+// Each allergen is contained in exactly one ingredient, so it can only be in
+// the ingredients common to all foods listing it. The analysis computes these
+// candidates once and derives the puzzle answers from them.
+pub struct Analysis<'a> {
+    foods: &'a Foods,
+    candidates: Vec<IdSet>,
+    occurrences: Vec<usize>,
+    strategy: Strategy,
+}
+
+impl<'a> Analysis<'a> {
+    pub fn new(foods: &'a Foods) -> Self {
+        let n_ingredients = foods.ingredients.len();
+        let mut candidates = vec![IdSet::full(n_ingredients); foods.allergens.len()];
+        let mut occurrences = vec![0; n_ingredients];
+        for food in &foods.foods {
+            let ingredients = IdSet::from_ids(n_ingredients, &food.ingredients);
+            for &allergen in &food.allergens {
+                candidates[allergen as usize].intersect_with(&ingredients);
+            }
+            for &ingredient in &food.ingredients {
+                occurrences[ingredient as usize] += 1;
+            }
+        }
+        Self {
+            foods,
+            candidates,
+            occurrences,
+            strategy: Strategy::Matching,
+        }
+    }
+
+    pub fn with_strategy(self, strategy: Strategy) -> Self {
+        Self { strategy, ..self }
+    }
+
+    // The ingredients that might contain the allergen.
+    pub fn candidates(&self, allergen: Allergen) -> impl Iterator<Item = Ingredient> + '_ {
+        self.candidates[allergen as usize].iter()
+    }
+
+    // Number of foods containing the ingredient.
+    pub fn occurrences(&self, ingredient: &str) -> usize {
+        self.foods
+            .ingredients
+            .id(ingredient)
+            .map_or(0, |id| self.occurrences[id as usize])
+    }
+
+    // The candidate ingredients of all allergens by name.
+    pub fn candidate_map(&self) -> BTreeMap<&'a str, Vec<&'a str>> {
+        self.candidates
+            .iter()
+            .enumerate()
+            .map(|(allergen, ingredients)| {
+                let mut names: Vec<&str> = ingredients
+                    .iter()
+                    .map(|ingredient| self.foods.ingredients.name(ingredient))
+                    .collect();
+                names.sort_unstable();
+                (self.foods.allergens.name(allergen as Allergen), names)
+            })
+            .collect()
+    }
+
+    // Ingredients that cannot contain any allergen with their number of
+    // occurrences, sorted by ingredient name.
+    pub fn allergen_free_ingredient_counts(&self) -> Vec<(&'a str, usize)> {
+        let mut allergen_free = IdSet::full(self.foods.ingredients.len());
+        for ingredients in &self.candidates {
+            allergen_free.difference_with(ingredients);
+        }
+
+        let mut counts: Vec<(&str, usize)> = allergen_free
+            .iter()
+            .map(|ingredient| {
+                (
+                    self.foods.ingredients.name(ingredient),
+                    self.occurrences[ingredient as usize],
+                )
+            })
+            .collect();
+        counts.sort_unstable();
+        counts
+    }
+
+    pub fn count_allergen_free_ingredients(&self) -> usize {
+        self.allergen_free_ingredient_counts()
+            .iter()
+            .map(|&(_, count)| count)
+            .sum()
+    }
+
+    fn allergen_names(&self, allergens: impl Iterator<Item = usize>) -> Vec<String> {
+        let mut names: Vec<String> = allergens
+            .map(|allergen| String::from(self.foods.allergens.name(allergen as Allergen)))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    // Assigns each allergen to one of its candidate ingredients with a
+    // maximum bipartite matching. Indexed by allergen id.
+    fn matching(&self) -> Vec<Option<Ingredient>> {
+        let adjacency: Vec<Vec<usize>> = self
+            .candidates
+            .iter()
+            .map(|ingredients| ingredients.iter().map(|i| i as usize).collect())
+            .collect();
+        hopcroft_karp(&adjacency, self.foods.ingredients.len())
+            .into_iter()
+            .map(|ingredient| ingredient.map(|i| i as Ingredient))
+            .collect()
+    }
+
+    fn eliminate(&self) -> Result<Vec<Ingredient>, AssignmentError> {
+        let mut remaining: Vec<Vec<Ingredient>> = self
+            .candidates
+            .iter()
+            .map(|ingredients| ingredients.iter().collect())
+            .collect();
+        let mut assignment = vec![None; remaining.len()];
+        while let Some(allergen) = (0..remaining.len())
+            .find(|&allergen| assignment[allergen].is_none() && remaining[allergen].len() == 1)
+        {
+            let ingredient = remaining[allergen][0];
+            assignment[allergen] = Some(ingredient);
+            for candidates in remaining.iter_mut() {
+                candidates.retain(|&i| i != ingredient);
+            }
+        }
+
+        let unassigned = || (0..remaining.len()).filter(|&allergen| assignment[allergen].is_none());
+        if unassigned().any(|allergen| remaining[allergen].is_empty()) {
+            return Err(AssignmentError::Unsolvable(self.allergen_names(
+                unassigned().filter(|&allergen| remaining[allergen].is_empty()),
+            )));
+        }
+        if unassigned().next().is_some() {
+            return Err(AssignmentError::Ambiguous(
+                self.allergen_names(unassigned()),
+            ));
+        }
+        Ok(assignment.into_iter().map(Option::unwrap).collect())
+    }
+
+    // The ingredient containing each allergen with the chosen strategy,
+    // indexed by allergen id.
+    pub fn assignment(&self) -> Result<Vec<Ingredient>, AssignmentError> {
+        match self.strategy {
+            Strategy::Matching => {
+                let matching = self.matching();
+                let unmatched =
+                    (0..matching.len()).filter(|&allergen| matching[allergen].is_none());
+                if unmatched.clone().next().is_some() {
+                    return Err(AssignmentError::Unsolvable(self.allergen_names(unmatched)));
+                }
+                Ok(matching.into_iter().map(Option::unwrap).collect())
+            }
+            Strategy::Elimination => self.eliminate(),
+        }
+    }
+
+    // Pairs of allergen and the ingredient containing it, sorted by allergen
+    // name.
+    pub fn dangerous_ingredients(&self) -> Result<Vec<(&'a str, &'a str)>, AssignmentError> {
+        let foods = self.foods;
+        let assignment = self.assignment()?;
+        let mut allergens: Vec<Allergen> = (0..foods.allergens.len() as Allergen).collect();
+        allergens.sort_unstable_by_key(|&allergen| foods.allergens.name(allergen));
+        Ok(allergens
+            .iter()
+            .map(|&allergen| {
+                (
+                    foods.allergens.name(allergen),
+                    foods.ingredients.name(assignment[allergen as usize]),
+                )
+            })
+            .collect())
+    }
+
+    // The assignment is unique if no allergen could switch to an unassigned
+    // candidate and there is no alternating cycle, i.e. no sequence of
+    // allergens that could each switch to the ingredient of the next one.
+    pub fn is_assignment_unique(&self) -> bool {
+        let assignment = self.matching();
+        let mut assigned_to = vec![None; self.foods.ingredients.len()];
+        for (allergen, ingredient) in assignment.iter().enumerate() {
+            if let Some(ingredient) = ingredient {
+                assigned_to[*ingredient as usize] = Some(allergen);
+            }
+        }
+
+        let mut switches = vec![vec![]; self.candidates.len()];
+        for (allergen, candidates) in self.candidates.iter().enumerate() {
+            for ingredient in candidates
+                .iter()
+                .filter(|&i| Some(i) != assignment[allergen])
+            {
+                match assigned_to[ingredient as usize] {
+                    Some(other) => switches[allergen].push(other),
+                    None => return false,
+                }
+            }
+        }
+        !has_cycle(&switches)
+    }
+
+    pub fn canonical_dangerous_ingredient_list(&self) -> Result<Vec<&'a str>, AssignmentError> {
+        Ok(self
+            .dangerous_ingredients()?
+            .into_iter()
+            .map(|(_, ingredient)| ingredient)
+            .collect())
+    }
+}
+
+// Whether the directed graph given as adjacency lists over the node ids
+// contains a cycle. A single depth-first search over all nodes suffices, as a
+// cycle exists exactly if an edge leads back to a node still on the stack.
+fn has_cycle(adjacency: &[Vec<usize>]) -> bool {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnStack,
+        Done,
+    }
+
+    let mut states = vec![State::Unvisited; adjacency.len()];
+    for root in 0..adjacency.len() {
+        if states[root] != State::Unvisited {
+            continue;
+        }
+        states[root] = State::OnStack;
+        let mut stack = vec![(root, adjacency[root].iter())];
+        while let Some((node, targets)) = stack.last_mut() {
+            match targets.next() {
+                Some(&target) => match states[target] {
+                    State::Unvisited => {
+                        states[target] = State::OnStack;
+                        stack.push((target, adjacency[target].iter()));
+                    }
+                    State::OnStack => return true,
+                    State::Done => {}
+                },
+                None => {
+                    states[*node] = State::Done;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    false
+}
+
+pub fn count_allergen_free_ingredients(foods: &Foods) -> usize {
+    Analysis::new(foods).count_allergen_free_ingredients()
+}
+
+pub fn canonical_dangerous_ingredient_list(foods: &Foods) -> Result<Vec<&str>, AssignmentError> {
+    Analysis::new(foods).canonical_dangerous_ingredient_list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static INPUT: [&str; 4] = [
+        "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)",
+        "trh fvjkl sbzzf mxmxvkd (contains dairy)",
+        "sqjhc fvjkl (contains soy)",
+        "sqjhc mxmxvkd sbzzf (contains fish)",
+    ];
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+
+        fn chance(&mut self, denominator: u64) -> bool {
+            self.next().is_multiple_of(denominator)
+        }
+    }
+
+    // Allergen `a{i}` is contained in ingredient `d{(i * 5) % n_allergens}`.
+    // Foods list each allergen with a probability of 1/3 and might contain
+    // further, unlisted dangerous ingredients. Returns the foods and the
+    // number of occurrences of the filler ingredients `f{i}`.
+    fn generate_foods(
+        seed: u64,
+        n_foods: usize,
+        n_allergens: usize,
+        n_fillers: usize,
+    ) -> (Foods, usize) {
+        let mut rng = Lcg(seed);
+        let mut foods = Foods::new();
+        let mut n_filler_occurrences = 0;
+        for _ in 0..n_foods {
+            let mut ingredients = vec![];
+            let mut allergens = vec![];
+            for i in 0..n_allergens {
+                let ingredient = format!("d{}", (i * 5) % n_allergens);
+                if rng.chance(3) {
+                    allergens.push(format!("a{}", i));
+                    ingredients.push(ingredient);
+                } else if rng.chance(2) {
+                    ingredients.push(ingredient);
+                }
+            }
+            for i in 0..n_fillers {
+                if rng.chance(2) {
+                    ingredients.push(format!("f{}", i));
+                    n_filler_occurrences += 1;
+                }
+            }
+            if ingredients.is_empty() {
+                continue;
+            }
+            let ingredients: Vec<&str> = ingredients.iter().map(String::as_str).collect();
+            let allergens: Vec<&str> = allergens.iter().map(String::as_str).collect();
+            foods.add(&ingredients, &allergens);
+        }
+        (foods, n_filler_occurrences)
+    }
+
+    #[test]
+    fn fn_test_parsing() {
+        assert_eq!(
+            parse_full::<_, nom::error::Error<&str>, _>(parse_food, "ab cd (contains dairy, fish)")
+                .unwrap(),
+            (vec!["ab", "cd"], vec!["dairy", "fish"])
+        )
+    }
+
+    #[test]
+    fn test_parse_foods() {
+        let foods = parse_foods(["ab cd  ", "ef cd (contains soy) ", ""].iter()).unwrap();
+        assert_eq!(
+            foods.foods(),
+            &[
+                Food {
+                    ingredients: vec![0, 1],
+                    allergens: vec![]
+                },
+                Food {
+                    ingredients: vec![2, 1],
+                    allergens: vec![0]
+                }
+            ]
+        );
+        assert_eq!(foods.ingredients().names, vec!["ab", "cd", "ef"]);
+        assert_eq!(foods.allergens().names, vec!["soy"]);
+    }
+
+    #[test]
+    fn test_parse_foods_reports_line() {
+        assert_eq!(
+            parse_foods(["ab", "cd (contains)"].iter()),
+            Err(FoodParseError {
+                line: 2,
+                food: "cd (contains)".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_id_set() {
+        let mut set = IdSet::from_ids(130, &[0, 64, 129]);
+        set.intersect_with(&IdSet::from_ids(130, &[64, 129, 3]));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![64, 129]);
+        set.difference_with(&IdSet::from_ids(130, &[129]));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![64]);
+    }
+
+    #[test]
+    fn test_candidates() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        let analysis = Analysis::new(&foods);
+        let dairy = foods.allergens().id("dairy").unwrap();
+        let candidates: Vec<&str> = analysis
+            .candidates(dairy)
+            .map(|ingredient| foods.ingredients().name(ingredient))
+            .collect();
+        assert_eq!(candidates, vec!["mxmxvkd"]);
+        assert_eq!(analysis.occurrences("sqjhc"), 3);
+        assert_eq!(analysis.occurrences("unknown"), 0);
+    }
+
+    #[test]
+    fn test_candidate_map() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        let expected: BTreeMap<&str, Vec<&str>> = vec![
+            ("dairy", vec!["mxmxvkd"]),
+            ("fish", vec!["mxmxvkd", "sqjhc"]),
+            ("soy", vec!["fvjkl", "sqjhc"]),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(Analysis::new(&foods).candidate_map(), expected);
+    }
+
+    #[test]
+    fn test_allergen_free_ingredient_counts() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        assert_eq!(
+            Analysis::new(&foods).allergen_free_ingredient_counts(),
+            vec![("kfcds", 1), ("nhms", 1), ("sbzzf", 2), ("trh", 1)]
+        );
+    }
+
+    #[test]
+    fn test_count_allergen_free_ingredients() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        assert_eq!(count_allergen_free_ingredients(&foods), 5);
+    }
+
+    #[test]
+    fn test_canonical_dangerous_ingredients() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        assert_eq!(
+            canonical_dangerous_ingredient_list(&foods),
+            Ok(vec!["mxmxvkd", "sqjhc", "fvjkl"])
+        );
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        let analysis = Analysis::new(&foods);
+        assert_eq!(
+            analysis.assignment(),
+            analysis.with_strategy(Strategy::Elimination).assignment()
+        );
+        for &(seed, n_foods, n_allergens, n_fillers) in &[(4, 200, 6, 30), (5, 500, 12, 100)] {
+            let (foods, _) = generate_foods(seed, n_foods, n_allergens, n_fillers);
+            let analysis = Analysis::new(&foods);
+            assert_eq!(
+                analysis.assignment(),
+                analysis.with_strategy(Strategy::Elimination).assignment()
+            );
+        }
+    }
+
+    #[test]
+    fn test_assignment_errors() {
+        let foods = parse_foods(["x y (contains a, b)", "x y z"].iter()).unwrap();
+        let analysis = Analysis::new(&foods);
+        assert!(analysis.assignment().is_ok());
+        assert_eq!(
+            analysis.with_strategy(Strategy::Elimination).assignment(),
+            Err(AssignmentError::Ambiguous(vec![
+                String::from("a"),
+                String::from("b")
+            ]))
+        );
+
+        let foods = parse_foods(["x (contains a, b)", "x y (contains c)"].iter()).unwrap();
+        assert_eq!(
+            Analysis::new(&foods).assignment(),
+            Err(AssignmentError::Unsolvable(vec![String::from("b")]))
+        );
+        assert_eq!(
+            Analysis::new(&foods)
+                .with_strategy(Strategy::Elimination)
+                .assignment(),
+            Err(AssignmentError::Unsolvable(vec![String::from("b")]))
+        );
+    }
+
+    #[test]
+    fn test_is_assignment_unique() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        assert!(Analysis::new(&foods).is_assignment_unique());
+
+        let foods = parse_foods(["x y (contains a, b)", "x y z"].iter()).unwrap();
+        assert!(!Analysis::new(&foods).is_assignment_unique());
+
+        let foods = parse_foods(["x y (contains a)", "x z (contains b)"].iter()).unwrap();
+        assert!(!Analysis::new(&foods).is_assignment_unique());
+
+        let foods = parse_foods(["x y (contains a, b)", "x z (contains b)"].iter()).unwrap();
+        assert!(Analysis::new(&foods).is_assignment_unique());
+    }
+
+    #[test]
+    fn test_has_cycle() {
+        assert!(!has_cycle(&[]));
+        assert!(!has_cycle(&[vec![1, 2], vec![3], vec![3], vec![]]));
+        assert!(has_cycle(&[vec![1], vec![2], vec![0]]));
+        assert!(has_cycle(&[vec![], vec![2], vec![3], vec![1]]));
+    }
+
+    #[test]
+    fn test_generated_foods() {
+        for &(seed, n_foods, n_allergens, n_fillers) in
+            &[(1, 100, 3, 10), (2, 300, 8, 50), (3, 1000, 16, 200)]
+        {
+            let (foods, n_filler_occurrences) =
+                generate_foods(seed, n_foods, n_allergens, n_fillers);
+            let analysis = Analysis::new(&foods);
+            assert_eq!(
+                analysis.count_allergen_free_ingredients(),
+                n_filler_occurrences
+            );
+
+            let mut allergens: Vec<usize> = (0..n_allergens).collect();
+            allergens.sort_unstable_by_key(|i| format!("a{}", i));
+            let expected: Vec<String> = allergens
+                .iter()
+                .map(|i| format!("d{}", (i * 5) % n_allergens))
+                .collect();
+            assert_eq!(
+                analysis.canonical_dangerous_ingredient_list().unwrap(),
+                expected
+            );
+            assert!(analysis.is_assignment_unique());
+        }
+    }
+}
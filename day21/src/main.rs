@@ -1,173 +1,161 @@
-#[macro_use]
-extern crate lazy_static;
-
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::convert::TryFrom;
-use std::io::{self, BufRead};
-use std::rc::Rc;
-
-lazy_static! {
-    static ref FOOD_PARSE_REGEX: Regex = Regex::new(r"^(.*) \(contains (.*)\)$").unwrap();
-}
-
-type Ingredient = String;
-type Allergen = String;
-
-#[derive(Debug, PartialEq)]
-struct Food {
-    ingredients: Vec<Ingredient>,
-    allergens: Vec<Allergen>,
+use day21::{parse_foods, Analysis, Strategy};
+use parsing::dump_intermediate;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Tsv,
 }
 
-impl TryFrom<&str> for Food {
-    type Error = ();
+impl FromStr for OutputFormat {
+    type Err = String;
 
-    fn try_from(input: &str) -> Result<Self, Self::Error> {
-        if let Some(cap) = FOOD_PARSE_REGEX.captures(input) {
-            Ok(Self {
-                ingredients: cap[1].split(" ").map(String::from).collect(),
-                allergens: cap[2].split(", ").map(String::from).collect(),
-            })
-        } else {
-            Err(())
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(format!("Unknown output format '{}'.", s)),
         }
     }
 }
 
-fn find_what_ingredients_an_allergen_might_be_contained_in(
-    foods: &Vec<Food>,
-) -> HashMap<&Allergen, HashSet<&Ingredient>> {
-    let mut might_be_contained_in: HashMap<&Allergen, HashSet<&Ingredient>> = HashMap::new();
-    for food in foods {
-        for allergen in &food.allergens {
-            might_be_contained_in
-                .entry(allergen)
-                .and_modify(|e| {
-                    *e = e
-                        .intersection(&food.ingredients.iter().collect())
-                        .copied()
-                        .collect()
-                })
-                .or_insert(food.ingredients.iter().collect());
+fn write_report(
+    analysis: &Analysis,
+    writer: &mut impl Write,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Text => {
+            writeln!(writer, "{}", analysis.count_allergen_free_ingredients())?;
+            writeln!(
+                writer,
+                "{}",
+                analysis.canonical_dangerous_ingredient_list()?.join(",")
+            )?;
         }
-    }
-    might_be_contained_in
-}
-
-fn count_allergen_free_ingredients(foods: &Vec<Food>) -> usize {
-    let might_be_contained_in = find_what_ingredients_an_allergen_might_be_contained_in(foods);
-
-    let mut allergen_free: HashSet<&Ingredient> =
-        foods.iter().flat_map(|f| &f.ingredients).collect();
-    for ingredients in might_be_contained_in.values() {
-        for ingredient in ingredients.iter() {
-            allergen_free.remove(ingredient);
+        OutputFormat::Json => {
+            let dangerous: serde_json::Map<String, serde_json::Value> = analysis
+                .dangerous_ingredients()?
+                .into_iter()
+                .map(|(allergen, ingredient)| (String::from(allergen), ingredient.into()))
+                .collect();
+            let allergen_free: serde_json::Map<String, serde_json::Value> = analysis
+                .allergen_free_ingredient_counts()
+                .into_iter()
+                .map(|(ingredient, count)| (String::from(ingredient), count.into()))
+                .collect();
+            let report = serde_json::json!({
+                "dangerous": dangerous,
+                "allergen_free": allergen_free,
+            });
+            serde_json::to_writer_pretty(&mut *writer, &report)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Tsv => {
+            writeln!(writer, "ingredient\tallergen\toccurrences")?;
+            for (allergen, ingredient) in analysis.dangerous_ingredients()? {
+                let count = analysis.occurrences(ingredient);
+                writeln!(writer, "{}\t{}\t{}", ingredient, allergen, count)?;
+            }
+            for (ingredient, count) in analysis.allergen_free_ingredient_counts() {
+                writeln!(writer, "{}\t\t{}", ingredient, count)?;
+            }
         }
     }
-
-    allergen_free
-        .iter()
-        .flat_map(|allergen| {
-            foods.iter().map(move |f| {
-                if f.ingredients.contains(allergen) {
-                    1
-                } else {
-                    0
-                }
-            })
-        })
-        .sum()
+    Ok(())
 }
 
-fn canonical_dangerous_ingredient_list(foods: &Vec<Food>) -> Vec<Ingredient> {
-    use graph::DirectedGraph;
-
-    let might_be_contained_in = find_what_ingredients_an_allergen_might_be_contained_in(foods);
-
-    let mut graph: DirectedGraph<&String> = DirectedGraph::new();
-    let start_token = String::from("start");
-    let end_token = String::from("end");
-    let start = Rc::new(&start_token);
-    let end = Rc::new(&end_token);
-
-    for item in might_be_contained_in.iter() {
-        let (allergen, ingredients) = item;
-        let allergen = Rc::new(*allergen);
-        graph.add_edge(&start, &allergen);
-        for ingredient in ingredients {
-            let ingredient = Rc::new(*ingredient);
-            graph.add_edge(&allergen, &ingredient);
-            graph.add_edge(&ingredient, &end);
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut format = OutputFormat::Text;
+    let mut dump_dir = None;
+    let mut strategy = Strategy::Matching;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = args
+                    .next()
+                    .ok_or("--format requires text, json, or tsv.")?
+                    .parse()?
+            }
+            "--strategy" => {
+                strategy = args
+                    .next()
+                    .ok_or("--strategy requires matching or elimination.")?
+                    .parse()?
+            }
+            "--dump-intermediate" => {
+                dump_dir = Some(PathBuf::from(
+                    args.next()
+                        .ok_or("--dump-intermediate requires a directory.")?,
+                ))
+            }
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
         }
     }
 
-    let flow = graph.max_flow(&start, &end);
-
-    let mut allergens: Vec<&Allergen> = might_be_contained_in.keys().copied().collect();
-    allergens.sort_unstable();
-    allergens
-        .iter()
-        .map(|allergen| (**flow.adjancency[allergen].iter().next().unwrap()).clone())
-        .collect()
-}
-
-fn main() {
     let stdin = io::stdin();
-    let foods: Vec<Food> = stdin
-        .lock()
-        .lines()
-        .map(|line| Food::try_from(line.unwrap().as_str()).unwrap())
-        .collect();
-    println!("{}", count_allergen_free_ingredients(&foods));
-    println!("{}", canonical_dangerous_ingredient_list(&foods).join(","));
+    let lines: Vec<String> = stdin.lock().lines().collect::<Result<_, _>>()?;
+    let foods = parse_foods(lines.iter())?;
+    let analysis = Analysis::new(&foods).with_strategy(strategy);
+    if let Some(dir) = &dump_dir {
+        dump_intermediate(dir, "candidates", &analysis.candidate_map())?;
+    }
+    if strategy == Strategy::Matching && !analysis.is_assignment_unique() {
+        eprintln!("Warning: the allergen assignment is ambiguous, more than one is possible.");
+    }
+    let stdout = io::stdout();
+    write_report(&analysis, &mut stdout.lock(), format)?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn fn_test_parsing() {
-        assert_eq!(
-            Food::try_from("ab cd (contains dairy, fish)").unwrap(),
-            Food {
-                ingredients: vec!["ab".into(), "cd".into()],
-                allergens: vec!["dairy".into(), "fish".into()]
-            }
-        )
-    }
+    static INPUT: [&str; 4] = [
+        "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)",
+        "trh fvjkl sbzzf mxmxvkd (contains dairy)",
+        "sqjhc fvjkl (contains soy)",
+        "sqjhc mxmxvkd sbzzf (contains fish)",
+    ];
 
     #[test]
-    fn test_count_allergen_free_ingredients() {
-        let input = vec![
-            "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)",
-            "trh fvjkl sbzzf mxmxvkd (contains dairy)",
-            "sqjhc fvjkl (contains soy)",
-            "sqjhc mxmxvkd sbzzf (contains fish)",
-        ];
-        let foods: Result<Vec<Food>, ()> = input.into_iter().map(Food::try_from).collect();
-        let foods = foods.unwrap();
-        assert_eq!(count_allergen_free_ingredients(&foods), 5);
+    fn test_write_report_tsv() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        let mut output = Vec::new();
+        write_report(&Analysis::new(&foods), &mut output, OutputFormat::Tsv).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ingredient\tallergen\toccurrences\n\
+             mxmxvkd\tdairy\t3\n\
+             sqjhc\tfish\t3\n\
+             fvjkl\tsoy\t2\n\
+             kfcds\t\t1\n\
+             nhms\t\t1\n\
+             sbzzf\t\t2\n\
+             trh\t\t1\n"
+        );
     }
 
     #[test]
-    fn test_canonical_dangerous_ingredients() {
-        let input = vec![
-            "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)",
-            "trh fvjkl sbzzf mxmxvkd (contains dairy)",
-            "sqjhc fvjkl (contains soy)",
-            "sqjhc mxmxvkd sbzzf (contains fish)",
-        ];
-        let foods: Result<Vec<Food>, ()> = input.into_iter().map(Food::try_from).collect();
-        let foods = foods.unwrap();
+    fn test_write_report_json() {
+        let foods = parse_foods(INPUT.iter()).unwrap();
+        let mut output = Vec::new();
+        write_report(&Analysis::new(&foods), &mut output, OutputFormat::Json).unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
         assert_eq!(
-            canonical_dangerous_ingredient_list(&foods),
-            vec![
-                String::from("mxmxvkd"),
-                String::from("sqjhc"),
-                String::from("fvjkl")
-            ]
+            report,
+            serde_json::json!({
+                "dangerous": {"dairy": "mxmxvkd", "fish": "sqjhc", "soy": "fvjkl"},
+                "allergen_free": {"kfcds": 1, "nhms": 1, "sbzzf": 2, "trh": 1},
+            })
         );
     }
 }
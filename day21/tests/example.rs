@@ -0,0 +1,27 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day21"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(run("example.txt", &[]), "5\nmxmxvkd,sqjhc,fvjkl\n");
+}
+
+#[test]
+fn test_example_as_tsv() {
+    let output = run("example.txt", &["--format", "tsv"]);
+    assert!(output.starts_with("ingredient\tallergen\toccurrences\nmxmxvkd\tdairy\t3\n"));
+}
+
+#[test]
+fn test_example_with_elimination() {
+    assert_eq!(
+        run("example.txt", &["--strategy", "elimination"]),
+        "5\nmxmxvkd,sqjhc,fvjkl\n"
+    );
+}
@@ -0,0 +1,49 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day24::{default_rules, get_flipped_tiles, DenseFloor, LifeEngine, SparseFloor};
+
+// Random paths of the given length, generated with a linear congruential
+// generator to be reproducible.
+fn random_paths(n_paths: usize, length: usize) -> Vec<String> {
+    let directions = ["e", "se", "sw", "w", "nw", "ne"];
+    let mut state: u64 = 42;
+    (0..n_paths)
+        .map(|_| {
+            (0..length)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    directions[(state >> 33) as usize % directions.len()]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn bench_floor(c: &mut Criterion) {
+    let black_tiles = get_flipped_tiles(random_paths(400, 20).iter()).unwrap();
+    let mut group = c.benchmark_group("day24_floor");
+    group.sample_size(10);
+    for &n_days in &[10, 100] {
+        group.bench_with_input(BenchmarkId::new("sparse", n_days), &n_days, |b, &n| {
+            b.iter(|| {
+                SparseFloor {
+                    black: black_tiles.clone(),
+                }
+                .advance(n, &default_rules())
+                .n_active()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("dense", n_days), &n_days, |b, &n| {
+            b.iter(|| {
+                DenseFloor::from(&black_tiles)
+                    .advance(n, &default_rules())
+                    .n_active()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_floor);
+criterion_main!(benches);
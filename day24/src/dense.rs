@@ -0,0 +1,182 @@
+use super::{Index, LifeEngine, Rules};
+use std::collections::HashSet;
+
+// Stores the tiles within the bounding box of the black tiles as a bitmap.
+// The bounding box grows by one tile in each direction per day and is
+// shrunk to fit the black tiles again afterwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DenseFloor {
+    origin: Index,
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl DenseFloor {
+    fn new(origin: Index, width: usize, height: usize) -> Self {
+        Self {
+            origin,
+            width,
+            height,
+            bits: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+
+    fn offset(&self, index: Index) -> Option<usize> {
+        let q = index.q - self.origin.q;
+        let r = index.r - self.origin.r;
+        if 0 <= q && (q as usize) < self.width && 0 <= r && (r as usize) < self.height {
+            Some(r as usize * self.width + q as usize)
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, index: Index) {
+        let offset = self
+            .offset(index)
+            .expect("Index must be within the bounding box.");
+        self.bits[offset / 64] |= 1 << (offset % 64);
+    }
+
+    pub fn is_black(&self, index: Index) -> bool {
+        self.offset(index)
+            .map(|offset| self.bits[offset / 64] & 1 << (offset % 64) != 0)
+            .unwrap_or(false)
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Index> + '_ {
+        (0..self.height as isize).flat_map(move |r| {
+            (0..self.width as isize).map(move |q| Index::new(self.origin.q + q, self.origin.r + r))
+        })
+    }
+
+    fn shrink_to_fit(self) -> Self {
+        let mut bounds: Option<(Index, Index)> = None;
+        for index in self.indices().filter(|&index| self.is_black(index)) {
+            bounds = Some(match bounds {
+                None => (index, index),
+                Some((lb, ub)) => (
+                    Index::new(lb.q.min(index.q), lb.r.min(index.r)),
+                    Index::new(ub.q.max(index.q), ub.r.max(index.r)),
+                ),
+            });
+        }
+        let (lb, ub) = match bounds {
+            Some(bounds) => bounds,
+            None => return Self::new(Index::default(), 0, 0),
+        };
+        if lb == self.origin
+            && (ub.q - lb.q + 1) as usize == self.width
+            && (ub.r - lb.r + 1) as usize == self.height
+        {
+            return self;
+        }
+        let mut shrunk = Self::new(lb, (ub.q - lb.q + 1) as usize, (ub.r - lb.r + 1) as usize);
+        let indices: Vec<Index> = shrunk.indices().collect();
+        for index in indices {
+            if self.is_black(index) {
+                shrunk.set(index);
+            }
+        }
+        shrunk
+    }
+}
+
+impl From<&HashSet<Index>> for DenseFloor {
+    fn from(black_tiles: &HashSet<Index>) -> Self {
+        if black_tiles.is_empty() {
+            return Self::new(Index::default(), 0, 0);
+        }
+        let min_q = black_tiles.iter().map(|index| index.q).min().unwrap();
+        let max_q = black_tiles.iter().map(|index| index.q).max().unwrap();
+        let min_r = black_tiles.iter().map(|index| index.r).min().unwrap();
+        let max_r = black_tiles.iter().map(|index| index.r).max().unwrap();
+        let mut floor = Self::new(
+            Index::new(min_q, min_r),
+            (max_q - min_q + 1) as usize,
+            (max_r - min_r + 1) as usize,
+        );
+        for &index in black_tiles {
+            floor.set(index);
+        }
+        floor
+    }
+}
+
+impl LifeEngine for DenseFloor {
+    type Cell = Index;
+
+    #[cfg_attr(
+        feature = "profile",
+        tracing::instrument(name = "generation", skip_all)
+    )]
+    fn next_state(self, rules: &Rules) -> Self {
+        if self.width == 0 {
+            return self;
+        }
+        let mut next = Self::new(
+            Index::new(self.origin.q - 1, self.origin.r - 1),
+            self.width + 2,
+            self.height + 2,
+        );
+        let indices: Vec<Index> = next.indices().collect();
+        for index in indices {
+            let n_black_neighbours = index.neighbours().filter(|&n| self.is_black(n)).count();
+            if rules.is_active_next(self.is_black(index), n_black_neighbours) {
+                next.set(index);
+            }
+        }
+        next.shrink_to_fit()
+    }
+
+    fn active_cells(&self) -> HashSet<Index> {
+        self.indices()
+            .filter(|&index| self.is_black(index))
+            .collect()
+    }
+
+    fn n_active(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{default_rules, SparseFloor};
+
+    #[test]
+    fn test_roundtrip() {
+        let black_tiles: HashSet<Index> = vec![Index::new(-2, 1), Index::new(3, 0)]
+            .into_iter()
+            .collect();
+        let floor = DenseFloor::from(&black_tiles);
+        assert_eq!(floor.n_active(), 2);
+        assert_eq!(floor.active_cells(), black_tiles);
+    }
+
+    #[test]
+    fn test_matches_sparse() {
+        let black_tiles: HashSet<Index> = vec![
+            Index::new(0, 0),
+            Index::new(1, 0),
+            Index::new(0, 2),
+            Index::new(-3, 1),
+        ]
+        .into_iter()
+        .collect();
+        let mut sparse = SparseFloor {
+            black: black_tiles.clone(),
+        };
+        let mut dense = DenseFloor::from(&black_tiles);
+        for _ in 0..20 {
+            sparse = sparse.next_state(&default_rules());
+            dense = dense.next_state(&default_rules());
+            assert_eq!(dense.active_cells(), sparse.black);
+        }
+    }
+}
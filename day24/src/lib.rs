@@ -0,0 +1,243 @@
+use hexgrid::{Axial, DirectionParseError, PointyDirection};
+use life::sparse;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+mod dense;
+pub mod render;
+
+pub use dense::DenseFloor;
+pub use life::{LifeEngine, Rules};
+
+pub type Index = Axial;
+
+#[derive(Debug, PartialEq)]
+pub struct PathParseError {
+    pub line: usize,
+    pub error: DirectionParseError,
+}
+
+impl Display for PathParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for PathParseError {}
+
+pub fn get_flipped_tiles(
+    input: impl Iterator<Item = impl AsRef<str>>,
+) -> Result<HashSet<Index>, PathParseError> {
+    let mut flipped = HashSet::new();
+    for (i, line) in input.enumerate() {
+        let path: Vec<PointyDirection> = PointyDirection::parse_path(line.as_ref().chars())
+            .collect::<Result<_, _>>()
+            .map_err(|error| PathParseError { line: i + 1, error })?;
+        let index = Axial::default().walk(path);
+        if flipped.contains(&index) {
+            flipped.remove(&index);
+        } else {
+            flipped.insert(index);
+        }
+    }
+    Ok(flipped)
+}
+
+// Numbers of black neighbours for which a white tile is flipped to black
+// (birth) and a black tile stays black (survival).
+pub fn default_rules() -> Rules {
+    Rules {
+        birth: vec![2],
+        survival: vec![1, 2],
+    }
+}
+
+pub fn advance_day(flipped_state: HashSet<Index>, rules: &Rules) -> HashSet<Index> {
+    sparse::next_generation(&flipped_state, |tile| tile.neighbours(), rules)
+}
+
+pub fn advance_n_days(
+    flipped_state: HashSet<Index>,
+    n_days: usize,
+    rules: &Rules,
+) -> HashSet<Index> {
+    advance_n_days_observed(flipped_state, n_days, rules, &mut |_, _| {})
+}
+
+// Like advance_n_days, but calls observe with the day and the state after
+// each day.
+pub fn advance_n_days_observed(
+    flipped_state: HashSet<Index>,
+    n_days: usize,
+    rules: &Rules,
+    observe: &mut dyn FnMut(usize, &HashSet<Index>),
+) -> HashSet<Index> {
+    sparse::advance_observed(
+        flipped_state,
+        n_days,
+        |tile| tile.neighbours(),
+        rules,
+        observe,
+    )
+}
+
+// Stores only the black tiles.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SparseFloor {
+    pub black: HashSet<Index>,
+}
+
+impl LifeEngine for SparseFloor {
+    type Cell = Index;
+
+    fn next_state(self, rules: &Rules) -> Self {
+        Self {
+            black: advance_day(self.black, rules),
+        }
+    }
+
+    fn advance_observed(
+        self,
+        n_days: usize,
+        rules: &Rules,
+        observe: &mut dyn FnMut(usize, usize),
+    ) -> Self {
+        Self {
+            black: advance_n_days_observed(self.black, n_days, rules, &mut |day, black| {
+                observe(day, black.len())
+            }),
+        }
+    }
+
+    fn active_cells(&self) -> HashSet<Index> {
+        self.black.clone()
+    }
+
+    fn n_active(&self) -> usize {
+        self.black.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn input() -> Vec<&'static str> {
+        vec![
+            "sesenwnenenewseeswwswswwnenewsewsw",
+            "neeenesenwnwwswnenewnwwsewnenwseswesw",
+            "seswneswswsenwwnwse",
+            "nwnwneseeswswnenewneswwnewseswneseene",
+            "swweswneswnenwsewnwneneseenw",
+            "eesenwseswswnenwswnwnwsewwnwsene",
+            "sewnenenenesenwsewnenwwwse",
+            "wenwwweseeeweswwwnwwe",
+            "wsweesenenewnwwnwsenewsenwwsesesenwne",
+            "neeswseenwwswnwswswnw",
+            "nenwswwsewswnenenewsenwsenwnesesenew",
+            "enewnwewneswsewnwswenweswnenwsenwsw",
+            "sweneswneswneneenwnewenewwneswswnese",
+            "swwesenesewenwneswnwwneseswwne",
+            "enesenwswwswneneswsenwnewswseenwsese",
+            "wnwnesenesenenwwnenwsewesewsesesew",
+            "nenewswnwewswnenesenwnesewesw",
+            "eneswnwswnwsenenwnwnwwseeswneewsenese",
+            "neswnwewnwnwseenwseesewsenwsweewe",
+            "wseweeenwnesenwwwswnew",
+        ]
+    }
+
+    #[test]
+    fn test_get_flipped_tiles() {
+        assert_eq!(get_flipped_tiles(&mut input().iter()).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_get_flipped_tiles_reports_invalid_path() {
+        assert_eq!(
+            get_flipped_tiles(["esew", "nwwswee", "nwx"].iter()),
+            Err(PathParseError {
+                line: 3,
+                error: DirectionParseError {
+                    position: 2,
+                    found: Some('x')
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_advance_day() {
+        assert_eq!(
+            advance_day(
+                get_flipped_tiles(&mut input().iter()).unwrap(),
+                &default_rules()
+            )
+            .len(),
+            15
+        );
+    }
+    #[test]
+
+    fn test_advance_n_days() {
+        assert_eq!(
+            advance_n_days(
+                get_flipped_tiles(&mut input().iter()).unwrap(),
+                100,
+                &default_rules()
+            )
+            .len(),
+            2208
+        );
+    }
+
+    #[test]
+    fn test_advance_observed() {
+        let flipped = get_flipped_tiles(&mut input().iter()).unwrap();
+        let expected = vec![15, 12, 25, 14, 23, 28, 41, 37, 49, 37];
+        let mut series = vec![];
+        let floor = SparseFloor {
+            black: flipped.clone(),
+        }
+        .advance_observed(10, &default_rules(), &mut |day, n_black| {
+            series.push((day, n_black))
+        });
+        assert_eq!(series, (1..=10).zip(expected.clone()).collect::<Vec<_>>());
+        assert_eq!(floor.n_active(), 37);
+
+        let mut dense_series = vec![];
+        crate::DenseFloor::from(&flipped).advance_observed(
+            10,
+            &default_rules(),
+            &mut |_, n_black| dense_series.push(n_black),
+        );
+        assert_eq!(dense_series, expected);
+    }
+
+    #[test]
+    fn test_survival_without_neighbours() {
+        let rules = Rules::try_from("B/S0").unwrap();
+        let flipped: HashSet<Index> = vec![Index::new(0, 0), Index::new(5, 5)]
+            .into_iter()
+            .collect();
+        assert_eq!(advance_n_days(flipped.clone(), 3, &rules), flipped);
+    }
+
+    #[test]
+    fn test_default_rules() {
+        assert_eq!(Rules::try_from("B2/S1,2"), Ok(default_rules()));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let rules = Rules::try_from("B2/S").unwrap();
+        let flipped: HashSet<Index> = vec![Index::new(0, 0), Index::new(1, 0)]
+            .into_iter()
+            .collect();
+        let expected: HashSet<Index> = vec![Index::new(0, 1), Index::new(1, -1)]
+            .into_iter()
+            .collect();
+        assert_eq!(advance_day(flipped, &rules), expected);
+    }
+}
@@ -1,213 +1,111 @@
-use std::collections::HashSet;
-use std::io::{self, BufRead};
-
-type Index = (isize, isize);
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum HexNeighbour {
-    East,
-    SouthEast,
-    SouthWest,
-    West,
-    NorthWest,
-    NorthEast,
+use day24::render::{render_svg, render_terminal};
+use day24::{default_rules, get_flipped_tiles, DenseFloor, LifeEngine, Rules, SparseFloor};
+use std::convert::TryFrom;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+#[cfg(feature = "profile")]
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+#[cfg(feature = "profile")]
+use tracing_subscriber::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RenderFormat {
+    Terminal,
+    Svg,
 }
 
-impl HexNeighbour {
-    fn from_char_iter<'a>(
-        input: &'a mut impl Iterator<Item = char>,
-    ) -> HexNeighbourParser<'a, impl Iterator<Item = char>> {
-        HexNeighbourParser { input }
-    }
-
-    fn of_index(&self, index: Index) -> Index {
-        use HexNeighbour::*;
-        match self {
-            East => (index.0 + 1, index.1),
-            SouthEast => (index.0 + 1, index.1 - 1),
-            SouthWest => (index.0, index.1 - 1),
-            West => (index.0 - 1, index.1),
-            NorthWest => (index.0 - 1, index.1 + 1),
-            NorthEast => (index.0, index.1 + 1),
-        }
-    }
-
-    fn get_index(path: &mut impl Iterator<Item = Self>) -> Index {
-        path.fold((0, 0), |index, neighbour| neighbour.of_index(index))
-    }
-
-    fn all() -> [Self; 6] {
-        use HexNeighbour::*;
-        [East, SouthEast, SouthWest, West, NorthWest, NorthEast]
+// Writes the number of black tiles per day, starting with the initial state.
+fn write_series_csv(series: &[usize], path: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "day,black_tiles")?;
+    for (day, n_black) in series.iter().enumerate() {
+        writeln!(out, "{},{}", day, n_black)?;
     }
+    out.flush()
 }
 
-fn get_flipped_tiles(input: impl Iterator<Item = impl AsRef<str>>) -> HashSet<Index> {
-    let mut flipped = HashSet::new();
-    for line in input {
-        let index = HexNeighbour::get_index(&mut HexNeighbour::from_char_iter(
-            &mut line.as_ref().chars(),
-        ));
-        if flipped.contains(&index) {
-            flipped.remove(&index);
-        } else {
-            flipped.insert(index);
-        }
-    }
-    flipped
+// Records the spans in chrome://tracing format. The file is completed when
+// the guard is dropped.
+#[cfg(feature = "profile")]
+fn start_profile(path: &str) -> Result<FlushGuard, Box<dyn std::error::Error>> {
+    let (layer, guard) = ChromeLayerBuilder::new().file(path).build();
+    tracing_subscriber::registry().with(layer).init();
+    Ok(guard)
 }
 
-fn neighbours_of(index: Index) -> Vec<Index> {
-    HexNeighbour::all()
-        .iter()
-        .map(|n| n.of_index(index))
-        .collect()
+#[cfg(not(feature = "profile"))]
+fn start_profile(_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--profile requires the profile feature.".into())
 }
 
-fn advance_day(flipped_state: HashSet<Index>) -> HashSet<Index> {
-    let mut new_flipped_state = HashSet::new();
-    let white_tiles_to_consider: Vec<Index> = flipped_state
-        .iter()
-        .flat_map(|&black_tile| {
-            neighbours_of(black_tile)
-                .iter()
-                .filter(|index| !flipped_state.contains(index))
-                .copied()
-                .collect::<Vec<Index>>()
-        })
-        .collect();
-
-    for black_tile in &flipped_state {
-        let n_black_neighbours = neighbours_of(*black_tile)
-            .iter()
-            .filter(|tile| flipped_state.contains(tile))
-            .count();
-        if 0 < n_black_neighbours && n_black_neighbours <= 2 {
-            new_flipped_state.insert(*black_tile);
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut n_days = 100;
+    let mut rules = default_rules();
+    let mut render_format = None;
+    let mut dense = false;
+    let mut profile = None;
+    let mut print_series = false;
+    let mut csv_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--render" => {
+                render_format = match args.next().as_deref() {
+                    Some("terminal") => Some(RenderFormat::Terminal),
+                    Some("svg") => Some(RenderFormat::Svg),
+                    _ => return Err("--render requires terminal or svg.".into()),
+                }
+            }
+            "--days" => n_days = args.next().ok_or("--days requires a number.")?.parse()?,
+            "--rules" => {
+                rules = Rules::try_from(args.next().ok_or("--rules requires rules.")?.as_str())?
+            }
+            "--dense" => dense = true,
+            "--profile" => profile = Some(args.next().ok_or("--profile requires a file.")?),
+            "--series" => print_series = true,
+            "--csv" => csv_path = Some(args.next().ok_or("--csv requires a path.")?),
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
         }
     }
 
-    for white_tile in &white_tiles_to_consider {
-        let n_black_neighbours = neighbours_of(*white_tile)
-            .iter()
-            .filter(|tile| flipped_state.contains(tile))
-            .count();
-        if n_black_neighbours == 2 {
-            new_flipped_state.insert(*white_tile);
-        }
-    }
-
-    new_flipped_state
-}
-
-fn advance_n_days(flipped_state: HashSet<Index>, n_days: usize) -> HashSet<Index> {
-    let mut flipped_state = flipped_state;
-    for _ in 0..n_days {
-        flipped_state = advance_day(flipped_state);
-    }
-    flipped_state
-}
-
-struct HexNeighbourParser<'a, I>
-where
-    I: Iterator<Item = char>,
-{
-    input: &'a mut I,
-}
+    let _guard = profile.as_deref().map(start_profile).transpose()?;
 
-impl<'a, I> Iterator for HexNeighbourParser<'a, I>
-where
-    I: Iterator<Item = char>,
-{
-    type Item = HexNeighbour;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        use HexNeighbour::*;
-        match self.input.next() {
-            Some('e') => Some(East),
-            Some('w') => Some(West),
-            Some('s') => match self.input.next() {
-                Some('e') => Some(SouthEast),
-                Some('w') => Some(SouthWest),
-                _ => None,
-            },
-            Some('n') => match self.input.next() {
-                Some('e') => Some(NorthEast),
-                Some('w') => Some(NorthWest),
-                _ => None,
-            },
-            _ => None,
-        }
-    }
-}
-
-fn main() {
     let stdin = io::stdin();
-    let mut lines = stdin.lock().lines().map(Result::unwrap);
-    let flipped = get_flipped_tiles(&mut lines);
-    println!("Black tiles initially: {}", flipped.len());
-    println!(
-        "Black tiles after 100 days: {}",
-        advance_n_days(flipped, 100).len()
-    );
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn input() -> Vec<&'static str> {
-        vec![
-            "sesenwnenenewseeswwswswwnenewsewsw",
-            "neeenesenwnwwswnenewnwwsewnenwseswesw",
-            "seswneswswsenwwnwse",
-            "nwnwneseeswswnenewneswwnewseswneseene",
-            "swweswneswnenwsewnwneneseenw",
-            "eesenwseswswnenwswnwnwsewwnwsene",
-            "sewnenenenesenwsewnenwwwse",
-            "wenwwweseeeweswwwnwwe",
-            "wsweesenenewnwwnwsenewsenwwsesesenwne",
-            "neeswseenwwswnwswswnw",
-            "nenwswwsewswnenenewsenwsenwnesesenew",
-            "enewnwewneswsewnwswenweswnenwsenwsw",
-            "sweneswneswneneenwnewenewwneswswnese",
-            "swwesenesewenwneswnwwneseswwne",
-            "enesenwswwswneneswsenwnewswseenwsese",
-            "wnwnesenesenenwwnenwsewesewsesesew",
-            "nenewswnwewswnenesenwnesewesw",
-            "eneswnwswnwsenenwnwnwwseeswneewsenese",
-            "neswnwewnwnwseenwseesewsenwsweewe",
-            "wseweeenwnesenwwwswnew",
-        ]
+    let lines: Vec<String> = stdin.lock().lines().collect::<Result<_, _>>()?;
+    let flipped = {
+        #[cfg(feature = "profile")]
+        let _span = tracing::info_span!("parse").entered();
+        get_flipped_tiles(lines.iter())?
+    };
+    if render_format.is_none() {
+        println!("Black tiles initially: {}", flipped.len());
     }
-
-    #[test]
-    fn test_hex_neighbour_from_char_iter() {
-        use HexNeighbour::*;
-        assert_eq!(
-            HexNeighbour::from_char_iter(&mut "sesenwnee".chars()).collect::<Vec<HexNeighbour>>(),
-            vec![SouthEast, SouthEast, NorthWest, NorthEast, East]
-        );
-    }
-
-    #[test]
-    fn test_get_flipped_tiles() {
-        assert_eq!(get_flipped_tiles(&mut input().iter()).len(), 10);
-    }
-
-    #[test]
-    fn test_advance_day() {
-        assert_eq!(
-            advance_day(get_flipped_tiles(&mut input().iter())).len(),
-            15
-        );
+    let mut series = vec![flipped.len()];
+    let mut observe = |day, n_black| {
+        if print_series {
+            println!("Black tiles after day {}: {}", day, n_black);
+        }
+        series.push(n_black);
+    };
+    let flipped = if dense {
+        DenseFloor::from(&flipped)
+            .advance_observed(n_days, &rules, &mut observe)
+            .active_cells()
+    } else {
+        SparseFloor { black: flipped }
+            .advance_observed(n_days, &rules, &mut observe)
+            .black
+    };
+    if let Some(path) = &csv_path {
+        write_series_csv(&series, path)?;
     }
-    #[test]
-
-    fn test_advance_n_days() {
-        assert_eq!(
-            advance_n_days(get_flipped_tiles(&mut input().iter()), 100).len(),
-            2208
-        );
+    #[cfg(feature = "profile")]
+    let _span = tracing::info_span!("output").entered();
+    match render_format {
+        Some(RenderFormat::Terminal) => print!("{}", render_terminal(&flipped)),
+        Some(RenderFormat::Svg) => print!("{}", render_svg(&flipped)),
+        None => println!("Black tiles after {} days: {}", n_days, flipped.len()),
     }
+    Ok(())
 }
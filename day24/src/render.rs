@@ -0,0 +1,122 @@
+use crate::Index;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+const BLACK: char = '⬢';
+const WHITE: char = '·';
+
+const SVG_HEX_SIZE: f64 = 10.0;
+
+// Horizontal position of a tile in units of half a tile width. Tiles in
+// neighbouring rows are offset by half a tile.
+fn column(index: Index) -> isize {
+    2 * index.q + index.r
+}
+
+// Draws the tiles with north at the top, every other character column
+// belonging to a row.
+pub fn render_terminal(black_tiles: &HashSet<Index>) -> String {
+    let mut output = String::new();
+    if black_tiles.is_empty() {
+        return output;
+    }
+    let min_column = black_tiles.iter().copied().map(column).min().unwrap();
+    let max_column = black_tiles.iter().copied().map(column).max().unwrap();
+    let min_row = black_tiles.iter().map(|index| index.r).min().unwrap();
+    let max_row = black_tiles.iter().map(|index| index.r).max().unwrap();
+
+    for r in (min_row..=max_row).rev() {
+        let mut line = String::new();
+        for c in min_column..=max_column {
+            if (c - r).rem_euclid(2) != 0 {
+                line.push(' ');
+            } else if black_tiles.contains(&Index::new((c - r) / 2, r)) {
+                line.push(BLACK);
+            } else {
+                line.push(WHITE);
+            }
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+fn center(index: Index) -> (f64, f64) {
+    (
+        SVG_HEX_SIZE * 3f64.sqrt() / 2.0 * column(index) as f64,
+        -SVG_HEX_SIZE * 1.5 * index.r as f64,
+    )
+}
+
+// Draws the black tiles as pointy-top hexagons.
+pub fn render_svg(black_tiles: &HashSet<Index>) -> String {
+    let mut tiles: Vec<Index> = black_tiles.iter().copied().collect();
+    tiles.sort_unstable();
+
+    let centers: Vec<(f64, f64)> = tiles.iter().copied().map(center).collect();
+    let min_x = centers.iter().map(|c| c.0).fold(0.0, f64::min) - SVG_HEX_SIZE;
+    let max_x = centers.iter().map(|c| c.0).fold(0.0, f64::max) + SVG_HEX_SIZE;
+    let min_y = centers.iter().map(|c| c.1).fold(0.0, f64::min) - SVG_HEX_SIZE;
+    let max_y = centers.iter().map(|c| c.1).fold(0.0, f64::max) + SVG_HEX_SIZE;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+        min_x,
+        min_y,
+        max_x - min_x,
+        max_y - min_y
+    )
+    .unwrap();
+    for (x, y) in centers {
+        let points: Vec<String> = (0..6)
+            .map(|i| {
+                let angle = (60.0 * i as f64 + 30.0).to_radians();
+                format!(
+                    "{:.2},{:.2}",
+                    x + SVG_HEX_SIZE * angle.cos(),
+                    y + SVG_HEX_SIZE * angle.sin()
+                )
+            })
+            .collect();
+        writeln!(svg, r#"  <polygon points="{}" />"#, points.join(" ")).unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_terminal() {
+        let tiles: HashSet<Index> = vec![
+            Index::new(0, 0),
+            Index::new(1, 0),
+            Index::new(0, 1),
+            Index::new(1, -1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(render_terminal(&tiles), " ⬢\n⬢ ⬢\n ⬢\n");
+    }
+
+    #[test]
+    fn test_render_terminal_empty() {
+        assert_eq!(render_terminal(&HashSet::new()), "");
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let tiles: HashSet<Index> = vec![Index::new(0, 0), Index::new(1, 0)]
+            .into_iter()
+            .collect();
+        let svg = render_svg(&tiles);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
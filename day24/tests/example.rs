@@ -0,0 +1,24 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day24"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(
+        run("example.txt", &[]),
+        "Black tiles initially: 10\nBlack tiles after 100 days: 2208\n"
+    );
+}
+
+#[test]
+fn test_example_dense() {
+    assert_eq!(
+        run("example.txt", &["--dense", "--days", "10"]),
+        "Black tiles initially: 10\nBlack tiles after 10 days: 37\n"
+    );
+}
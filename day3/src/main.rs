@@ -1,38 +1,268 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
 use std::io::{self, BufRead};
 
-fn main() {
-    let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
-
-    let trees_hit = io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .enumerate()
-        .fold(vec![0; slopes.len()], |trees_hit, (i, line)| {
-            slopes
-                .iter()
-                .zip(trees_hit)
-                .map(|((slope_right, slope_down), trees_hit_on_slope)| {
-                    trees_hit_on_slope
-                        + if i % slope_down == 0 {
-                            let x = (i as f64 / *slope_down as f64).ceil() as usize;
-                            match line.as_bytes()[(slope_right * x) % line.len()] {
-                                b'#' => 1,
-                                b'.' => 0,
-                                c => panic!("Invalid input {}", c),
-                            }
-                        } else {
-                            0
-                        }
-                })
-                .collect()
-        });
-    println!(
-        "Trees hit on slope (3, 1): {}",
-        trees_hit[slopes.iter().position(|x| *x == (3, 1)).unwrap()]
-    );
+type Slope = (usize, usize);
+
+// Cost of passing each kind of terrain. By default trees cost 1 and open
+// squares nothing, so that the cost is the number of trees hit. Characters
+// missing from the table are invalid in the map.
+#[derive(Clone, Debug, PartialEq)]
+struct CostTable(HashMap<char, u64>);
+
+impl Default for CostTable {
+    fn default() -> Self {
+        Self(vec![('#', 1), ('.', 0)].into_iter().collect())
+    }
+}
+
+impl CostTable {
+    fn insert(&mut self, terrain: &str, cost: u64) -> Result<(), String> {
+        let mut chars = terrain.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                self.0.insert(c, cost);
+                Ok(())
+            }
+            _ => Err(format!(
+                "Invalid terrain '{}', expected a single character.",
+                terrain
+            )),
+        }
+    }
+
+    // Adds costs given like `@=2,~=5` to the table.
+    fn extend_from_str(&mut self, s: &str) -> Result<(), String> {
+        for entry in s.split(',') {
+            let error = || format!("Invalid cost '{}', expected terrain=cost.", entry);
+            let (terrain, cost) = entry.rsplit_once('=').ok_or_else(error)?;
+            self.insert(terrain.trim(), cost.trim().parse().map_err(|_| error())?)?;
+        }
+        Ok(())
+    }
+
+    // Adds the costs of a TOML table mapping terrain characters to costs.
+    fn extend_from_toml(&mut self, s: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let costs: HashMap<String, u64> = toml::from_str(s)?;
+        for (terrain, cost) in costs {
+            self.insert(&terrain, cost)?;
+        }
+        Ok(())
+    }
+}
+
+// Map of the terrain costs, repeating infinitely to the right.
+struct Map {
+    rows: Vec<Vec<u64>>,
+}
+
+impl Map {
+    fn parse(
+        lines: impl Iterator<Item = impl AsRef<str>>,
+        costs: &CostTable,
+    ) -> Result<Self, String> {
+        let rows = lines
+            .map(|line| {
+                line.as_ref()
+                    .chars()
+                    .map(|c| {
+                        costs
+                            .0
+                            .get(&c)
+                            .copied()
+                            .ok_or(format!("Invalid input {}", c))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rows })
+    }
+
+    // Total cost when going (right, down) per step from the top left until
+    // passing the bottom of the map.
+    fn cost(&self, (slope_right, slope_down): Slope) -> u64 {
+        self.rows
+            .iter()
+            .step_by(slope_down)
+            .enumerate()
+            .filter(|(_, row)| !row.is_empty())
+            .map(|(step, row)| row[(slope_right * step) % row.len()])
+            .sum()
+    }
+
+    // Costs of all slopes with 0 <= right <= max_right and
+    // 1 <= down <= max_down.
+    fn costs_on_slopes(
+        &self,
+        (max_right, max_down): Slope,
+    ) -> impl Iterator<Item = (Slope, u64)> + '_ {
+        (0..=max_right)
+            .flat_map(move |right| (1..=max_down).map(move |down| (right, down)))
+            .map(move |slope| (slope, self.cost(slope)))
+    }
+
+    // Slopes with the lowest and the highest cost within the bounds. The
+    // first slope wins on ties. None if the bounds do not contain any slope.
+    fn best_slopes(&self, bounds: Slope) -> Option<((Slope, u64), (Slope, u64))> {
+        let costs: Vec<_> = self.costs_on_slopes(bounds).collect();
+        let lowest = costs.iter().copied().min_by_key(|&(_, cost)| cost)?;
+        let highest = costs
+            .iter()
+            .copied()
+            .min_by_key(|&(_, cost)| Reverse(cost))?;
+        Some((lowest, highest))
+    }
+}
+
+fn parse_slope(s: &str) -> Result<Slope, String> {
+    let error = || format!("Invalid slope '{}', expected right,down.", s);
+    let (right, down) = s.split_once(',').ok_or_else(error)?;
+    Ok((
+        right.trim().parse().map_err(|_| error())?,
+        down.trim().parse().map_err(|_| error())?,
+    ))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut slopes = vec![];
+    let mut search_bounds = None;
+    let mut costs = CostTable::default();
+    let mut custom_costs = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--slope" => {
+                let slope = parse_slope(&args.next().ok_or("--slope requires an argument.")?)?;
+                if slope.1 == 0 {
+                    return Err("The slope must go down.".into());
+                }
+                slopes.push(slope);
+            }
+            "--best-slope" => {
+                search_bounds = Some(parse_slope(
+                    &args.next().ok_or("--best-slope requires an argument.")?,
+                )?);
+            }
+            "--costs" => {
+                costs.extend_from_str(&args.next().ok_or("--costs requires an argument.")?)?;
+                custom_costs = true;
+            }
+            "--cost-table" => {
+                let path = args.next().ok_or("--cost-table requires a path.")?;
+                costs.extend_from_toml(&fs::read_to_string(path)?)?;
+                custom_costs = true;
+            }
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+
+    if slopes.is_empty() {
+        slopes = vec![(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    }
+
+    let map = Map::parse(io::stdin().lock().lines().map(Result::unwrap), &costs)?;
+    let (lowest, highest, quantity, quantities) = if custom_costs {
+        ("Lowest cost", "Highest cost", "Cost", "costs")
+    } else {
+        (
+            "Fewest trees hit",
+            "Most trees hit",
+            "Trees hit",
+            "trees hit",
+        )
+    };
+    if let Some(bounds) = search_bounds {
+        let ((lowest_slope, lowest_cost), (highest_slope, highest_cost)) = map
+            .best_slopes(bounds)
+            .ok_or("No slope within the bounds.")?;
+        println!("{} on slope {:?}: {}", lowest, lowest_slope, lowest_cost);
+        println!("{} on slope {:?}: {}", highest, highest_slope, highest_cost);
+        return Ok(());
+    }
+
+    println!("{} on slope (3, 1): {}", quantity, map.cost((3, 1)));
     println!(
-        "Product of trees hit on all slopes: {}",
-        trees_hit.iter().fold(1, |x: u64, y| x * y)
+        "Product of {} on all slopes: {}",
+        quantities,
+        slopes.iter().map(|&slope| map.cost(slope)).product::<u64>()
     );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MAP: [&str; 11] = [
+        "..##.......",
+        "#...#...#..",
+        ".#....#..#.",
+        "..#.#...#.#",
+        ".#...##..#.",
+        "..#.##.....",
+        ".#.#.#....#",
+        ".#........#",
+        "#.##...#...",
+        "#...##....#",
+        ".#..#...#.#",
+    ];
+
+    #[test]
+    fn test_trees_hit() {
+        let map = Map::parse(MAP.iter(), &CostTable::default()).unwrap();
+        let trees_hit: Vec<u64> = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+            .iter()
+            .map(|&slope| map.cost(slope))
+            .collect();
+        assert_eq!(trees_hit, vec![2, 7, 3, 4, 2]);
+    }
+
+    #[test]
+    fn test_best_slopes() {
+        let map = Map::parse(MAP.iter(), &CostTable::default()).unwrap();
+        assert_eq!(map.best_slopes((3, 1)), Some((((2, 1), 1), ((3, 1), 7))));
+        assert_eq!(map.costs_on_slopes((7, 2)).count(), 16);
+        assert_eq!(
+            map.best_slopes((7, 2)).map(|(lowest, _)| lowest),
+            map.costs_on_slopes((7, 2)).min_by_key(|&(_, cost)| cost)
+        );
+    }
+
+    #[test]
+    fn test_costs() {
+        let mut costs = CostTable::default();
+        costs.extend_from_str("@=2, #=3").unwrap();
+        let map = Map::parse(["#.@", "@@#", ".#."].iter(), &costs).unwrap();
+        assert_eq!(map.cost((1, 1)), 3 + 2);
+        assert_eq!(map.cost((2, 1)), 3 + 3 + 3);
+        assert_eq!(map.cost((0, 2)), 3);
+        assert!(Map::parse(["#.@"].iter(), &CostTable::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_costs() {
+        let mut costs = CostTable::default();
+        costs.extend_from_str("==4").unwrap();
+        assert_eq!(costs.0.get(&'='), Some(&4));
+        assert!(costs.extend_from_str("@").is_err());
+        assert!(costs.extend_from_str("@@=1").is_err());
+        assert!(costs.extend_from_str("@=-1").is_err());
+
+        let mut costs = CostTable::default();
+        costs.extend_from_toml("\"@\" = 2\n\"#\" = 5\n").unwrap();
+        let mut expected = CostTable::default();
+        expected.extend_from_str("@=2,#=5").unwrap();
+        assert_eq!(costs, expected);
+        assert!(costs.extend_from_toml("\"@@\" = 2").is_err());
+    }
+
+    #[test]
+    fn test_parse_slope() {
+        assert_eq!(parse_slope("3,1"), Ok((3, 1)));
+        assert_eq!(parse_slope(" 7 , 2"), Ok((7, 2)));
+        assert!(parse_slope("3").is_err());
+        assert!(parse_slope("a,1").is_err());
+    }
 }
@@ -0,0 +1,16 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day3"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(
+        run("example.txt", &[]),
+        "Trees hit on slope (3, 1): 7\nProduct of trees hit on all slopes: 336\n"
+    );
+}
@@ -1,7 +1,8 @@
 use itertools::{process_results, Itertools};
-use std::collections::HashSet;
 use std::convert::{TryFrom, TryInto};
+use std::env;
 use std::io::{self, BufRead};
+use std::iter::FromIterator;
 
 enum BinPartitionSelector {
     Lower,
@@ -35,10 +36,19 @@ impl Seat {
     }
 }
 
-impl TryFrom<&[u8; 10]> for Seat {
-    type Error = String;
+// Decodes the binary space partitioning of a seat, given as seven F or B
+// characters selecting the row followed by three L or R characters selecting
+// the column.
+trait SeatDecoder {
+    fn decode(&self, encoded: &[u8; 10]) -> Result<Seat, String>;
+}
 
-    fn try_from(encoded: &[u8; 10]) -> Result<Self, Self::Error> {
+// Narrows down the rows and columns one partition at a time, which follows the
+// puzzle description.
+struct PartitionDecoder;
+
+impl SeatDecoder for PartitionDecoder {
+    fn decode(&self, encoded: &[u8; 10]) -> Result<Seat, String> {
         let row = encoded[..7]
             .iter()
             .map(|c| match c {
@@ -61,27 +71,138 @@ impl TryFrom<&[u8; 10]> for Seat {
     }
 }
 
+// Reads the encoding as the binary number of the seat ID. Bit 2 of the ASCII
+// codes is cleared for the upper partitions B and R and set for F and L.
+struct BitDecoder;
+
+impl SeatDecoder for BitDecoder {
+    fn decode(&self, encoded: &[u8; 10]) -> Result<Seat, String> {
+        if let Some(c) = encoded[..7].iter().find(|&&c| c != b'F' && c != b'B') {
+            return Err(format!("Invalid row partition character '{}'.", c));
+        }
+        if let Some(c) = encoded[7..].iter().find(|&&c| c != b'L' && c != b'R') {
+            return Err(format!("Invalid column partition character '{}'.", c));
+        }
+        let seat_id = encoded
+            .iter()
+            .fold(0, |seat_id, &c| seat_id << 1 | usize::from(!c >> 2 & 1));
+        Ok(Seat {
+            row: seat_id >> 3,
+            col: seat_id & 7,
+        })
+    }
+}
+
+impl TryFrom<&[u8; 10]> for Seat {
+    type Error = String;
+
+    fn try_from(encoded: &[u8; 10]) -> Result<Self, Self::Error> {
+        BitDecoder.decode(encoded)
+    }
+}
+
+const N_SEATS: usize = 128 * 8;
+
+// Set of seat IDs stored as a bitmap with one bit per seat of the plane.
+#[derive(Clone, Debug, PartialEq)]
+struct SeatSet {
+    bits: [u64; N_SEATS / 64],
+}
+
+impl SeatSet {
+    fn new() -> Self {
+        Self {
+            bits: [0; N_SEATS / 64],
+        }
+    }
+
+    // Returns whether the seat was not in the set before.
+    fn insert(&mut self, seat_id: usize) -> bool {
+        assert!(seat_id < N_SEATS, "Seat ID {} out of range.", seat_id);
+        let is_new = !self.contains(seat_id);
+        self.bits[seat_id / 64] |= 1 << (seat_id % 64);
+        is_new
+    }
+
+    fn contains(&self, seat_id: usize) -> bool {
+        seat_id < N_SEATS && self.bits[seat_id / 64] & 1 << (seat_id % 64) != 0
+    }
+
+    fn min(&self) -> Option<usize> {
+        self.bits
+            .iter()
+            .enumerate()
+            .find(|(_, &word)| word != 0)
+            .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+    }
+
+    fn max(&self) -> Option<usize> {
+        self.bits
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, &word)| word != 0)
+            .map(|(i, word)| i * 64 + 63 - word.leading_zeros() as usize)
+    }
+
+    // Seats between the lowest and highest seat in the set that are missing
+    // from it.
+    fn gaps(&self) -> impl Iterator<Item = usize> + '_ {
+        let range = match (self.min(), self.max()) {
+            (Some(min), Some(max)) => min..max,
+            _ => 0..0,
+        };
+        range.filter(move |&seat_id| !self.contains(seat_id))
+    }
+}
+
+impl FromIterator<usize> for SeatSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut seats = Self::new();
+        for seat_id in iter {
+            seats.insert(seat_id);
+        }
+        seats
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut decoder: Box<dyn SeatDecoder> = Box::new(BitDecoder);
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--decoder" => {
+                decoder = match args.next().as_deref() {
+                    Some("bits") => Box::new(BitDecoder),
+                    Some("partition") => Box::new(PartitionDecoder),
+                    _ => return Err("--decoder requires bits or partition.".into()),
+                }
+            }
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+
     let stdin = io::stdin();
-    let seat_ids: HashSet<usize> = process_results(
+    let seat_ids: SeatSet = process_results(
         stdin
             .lock()
             .lines()
             .map(|line| -> Result<Seat, Box<dyn std::error::Error>> {
                 let encoding: [u8; 10] = line?.as_bytes().try_into()?;
-                Ok(Seat::try_from(&encoding)?)
+                Ok(decoder.decode(&encoding)?)
             }),
         |iter| iter.map(|s| s.seat_id()).collect(),
     )?;
 
-    let max_seat_id = seat_ids.iter().max().ok_or("No seat IDs.")?;
+    let max_seat_id = seat_ids.max().ok_or("No seat IDs.")?;
     println!("Max seat id: {}", max_seat_id);
 
-    for id in &seat_ids {
-        if !seat_ids.contains(&(id + 1)) && seat_ids.contains(&(id + 2)) {
-            println!("My seat: {}", id + 1);
-            break;
-        }
+    // The seats directly in front of and behind mine are occupied.
+    let my_seat = seat_ids
+        .gaps()
+        .find(|&id| seat_ids.contains(id - 1) && seat_ids.contains(id + 1));
+    if let Some(id) = my_seat {
+        println!("My seat: {}", id);
     }
 
     Ok(())
@@ -139,4 +260,58 @@ mod tests {
         let seat = Seat::try_from(encoding).unwrap();
         assert_eq!(seat, expected_seat);
     }
+
+    #[test]
+    fn test_decoders_agree() {
+        for seat_id in 0..N_SEATS {
+            let mut encoded = [0; 10];
+            for (i, c) in encoded.iter_mut().enumerate() {
+                let is_upper = seat_id >> (9 - i) & 1 == 1;
+                *c = match (i < 7, is_upper) {
+                    (true, false) => b'F',
+                    (true, true) => b'B',
+                    (false, false) => b'L',
+                    (false, true) => b'R',
+                };
+            }
+            let seat = BitDecoder.decode(&encoded).unwrap();
+            assert_eq!(seat.seat_id(), seat_id);
+            assert_eq!(PartitionDecoder.decode(&encoded), Ok(seat));
+        }
+    }
+
+    #[rstest(
+        encoding,
+        case(b"FBFBBFLRLR"),
+        case(b"FBFBBFFRBR"),
+        case(b"fbfbbffrlr")
+    )]
+    fn test_decoders_reject_invalid_characters(encoding: &[u8; 10]) {
+        assert!(BitDecoder.decode(encoding).is_err());
+        assert!(PartitionDecoder.decode(encoding).is_err());
+    }
+
+    #[test]
+    fn test_seat_set() {
+        let mut seats = SeatSet::new();
+        assert_eq!(seats.min(), None);
+        assert_eq!(seats.max(), None);
+        assert!(seats.insert(820));
+        assert!(seats.insert(64));
+        assert!(!seats.insert(64));
+        assert!(seats.insert(63));
+        assert_eq!(seats.min(), Some(63));
+        assert_eq!(seats.max(), Some(820));
+        assert_eq!(seats.gaps().count(), 820 - 63 + 1 - 3);
+        assert!(seats.contains(820));
+        assert!(!seats.contains(819));
+        assert!(!seats.contains(N_SEATS));
+    }
+
+    #[test]
+    fn test_seat_set_gaps() {
+        let seats: SeatSet = vec![5, 6, 8, 11].into_iter().collect();
+        assert_eq!(seats.gaps().collect::<Vec<_>>(), vec![7, 9, 10]);
+        assert_eq!(SeatSet::new().gaps().count(), 0);
+    }
 }
@@ -0,0 +1,140 @@
+use crate::mutation::{Fix, Mutation};
+use crate::{successor, OpCode};
+
+// Runs the program for at most step_limit instructions and returns the final
+// accumulator, None if it does not terminate within the limit or jumps before
+// the start.
+fn run_with_step_limit(program: &[OpCode], step_limit: usize) -> Option<i32> {
+    let mut accumulator = 0;
+    let mut instruction_pointer = 0;
+    for _ in 0..step_limit {
+        if instruction_pointer >= program.len() {
+            return Some(accumulator);
+        }
+        let operation = &program[instruction_pointer];
+        if let OpCode::Acc(value) = operation {
+            accumulator += value;
+        }
+        instruction_pointer = successor(instruction_pointer, operation, program.len())?;
+    }
+    if instruction_pointer >= program.len() {
+        Some(accumulator)
+    } else {
+        None
+    }
+}
+
+fn try_mutation(
+    program: &[OpCode],
+    position: usize,
+    mutation: Mutation,
+    step_limit: usize,
+) -> Option<Fix> {
+    let replacement = mutation.apply(&program[position])?;
+    let mut repaired = program.to_vec();
+    repaired[position] = replacement.clone();
+    let accumulator = run_with_step_limit(&repaired, step_limit)?;
+    Some(Fix {
+        position,
+        mutation,
+        replacement,
+        accumulator,
+    })
+}
+
+// Runs every single-instruction mutation of the program and returns those
+// that terminate within the step limit, ordered by position. Much slower than
+// find_fixes, but without any cleverness that might be wrong, so it serves as
+// an oracle. With the `parallel` feature the variants are run on all cores.
+#[cfg(feature = "parallel")]
+pub fn brute_force_fixes(
+    program: &[OpCode],
+    mutations: &[Mutation],
+    step_limit: usize,
+) -> Vec<Fix> {
+    use rayon::prelude::*;
+    (0..program.len())
+        .into_par_iter()
+        .flat_map_iter(|position| {
+            mutations
+                .iter()
+                .filter_map(move |&mutation| try_mutation(program, position, mutation, step_limit))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn brute_force_fixes(
+    program: &[OpCode],
+    mutations: &[Mutation],
+    step_limit: usize,
+) -> Vec<Fix> {
+    (0..program.len())
+        .flat_map(|position| {
+            mutations
+                .iter()
+                .filter_map(move |&mutation| try_mutation(program, position, mutation, step_limit))
+        })
+        .collect()
+}
+
+// Sets the number of threads brute_force_fixes runs on. Has to be called
+// before it, otherwise rayon picks the number of threads from the
+// RAYON_NUM_THREADS environment variable or the number of cores.
+#[cfg(feature = "parallel")]
+pub fn set_num_threads(n_threads: usize) -> Result<(), Box<dyn std::error::Error>> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(n_threads)
+        .build_global()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn set_num_threads(_n_threads: usize) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--threads requires the parallel feature.".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::find_fixes;
+    use OpCode::*;
+
+    static PROGRAM: [OpCode; 9] = [
+        Nop(0),
+        Acc(1),
+        Jmp(4),
+        Acc(3),
+        Jmp(-3),
+        Acc(-99),
+        Acc(1),
+        Jmp(-4),
+        Acc(6),
+    ];
+
+    #[test]
+    fn test_run_with_step_limit() {
+        assert_eq!(run_with_step_limit(&[Acc(1), Acc(2)], 2), Some(3));
+        assert_eq!(run_with_step_limit(&[Acc(1), Acc(2)], 1), None);
+        assert_eq!(run_with_step_limit(&[Acc(1), Jmp(-1)], 100), None);
+        assert_eq!(run_with_step_limit(&[Jmp(-1)], 100), None);
+    }
+
+    #[test]
+    fn test_brute_force_fixes_agrees_with_find_fixes() {
+        let mutations = [
+            Mutation::SwapJmpNop,
+            Mutation::AccToNop,
+            Mutation::IncrementOperand,
+            Mutation::DecrementOperand,
+        ];
+        for program in &[&PROGRAM[..], &[Acc(1), Jmp(0)], &[Nop(2), Jmp(0), Jmp(-1)]] {
+            let mut expected = find_fixes(program, &mutations);
+            expected.sort_by_key(|fix| fix.position);
+            assert_eq!(
+                brute_force_fixes(program, &mutations, program.len()),
+                expected
+            );
+        }
+    }
+}
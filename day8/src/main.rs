@@ -1,6 +1,17 @@
+use brute_force::{brute_force_fixes, set_num_threads};
+use mutation::{find_fixes, Fix, Mutation};
+use optimize::eliminate_dead_code;
+use parsing::dump_intermediate;
+use serde::Serialize;
 use std::collections::{HashSet, VecDeque};
+use std::env;
 use std::fmt::Display;
 use std::io::{self, BufRead};
+use std::path::PathBuf;
+
+mod brute_force;
+mod mutation;
+mod optimize;
 
 #[derive(Clone, Debug, PartialEq)]
 struct State {
@@ -17,7 +28,8 @@ impl State {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "op", content = "arg", rename_all = "lowercase")]
 enum OpCode {
     Acc(i32),
     Jmp(isize),
@@ -53,8 +65,18 @@ impl<'a> Display for OpCodeParseError<'a> {
 
 impl<'a> std::error::Error for OpCodeParseError<'a> {}
 
+impl Display for OpCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            OpCode::Acc(value) => write!(f, "acc {:+}", value),
+            OpCode::Jmp(value) => write!(f, "jmp {:+}", value),
+            OpCode::Nop(value) => write!(f, "nop {:+}", value),
+        }
+    }
+}
+
 impl OpCode {
-    fn parse(input: &str) -> Result<Self, OpCodeParseError> {
+    fn parse(input: &str) -> Result<Self, OpCodeParseError<'_>> {
         let tokens: Vec<&str> = input.split(' ').collect();
         match tokens[0] {
             "acc" => Ok(OpCode::Acc(tokens[1].parse()?)),
@@ -86,32 +108,42 @@ fn reduce(state: State, operation: &OpCode) -> State {
     }
 }
 
+// The instruction executed after the given one. Jumps past the end of the
+// program terminate it just like reaching the end, which is mapped to the
+// program length. Jumps before the start return None.
+fn successor(instruction_pointer: usize, operation: &OpCode, program_len: usize) -> Option<usize> {
+    let next = match operation {
+        OpCode::Jmp(value) => (instruction_pointer as isize).checked_add(*value)?,
+        OpCode::Acc(_) | OpCode::Nop(_) => instruction_pointer as isize + 1,
+    };
+    if next < 0 {
+        None
+    } else {
+        Some((next as usize).min(program_len))
+    }
+}
+
 fn construct_reverse_flow_graph(program: &[OpCode]) -> Vec<Vec<usize>> {
     let mut graph = vec![vec![]; program.len() + 1];
 
     for (i, operation) in program.iter().enumerate() {
-        let new_state = reduce(
-            State {
-                accumulator: 0,
-                instruction_pointer: i,
-            },
-            operation,
-        );
-        graph[new_state.instruction_pointer].push(i)
+        if let Some(next) = successor(i, operation, program.len()) {
+            graph[next].push(i)
+        }
     }
 
     graph
 }
 
-fn determine_halting_nodes(reverse_flow_graph: &Vec<Vec<usize>>) -> HashSet<usize> {
+fn determine_halting_nodes(reverse_flow_graph: &[Vec<usize>]) -> HashSet<usize> {
     let mut halting_nodes = HashSet::new();
     let mut queue = VecDeque::new();
     queue.push_back(reverse_flow_graph.len() - 1);
 
     while let Some(node_idx) = queue.pop_front() {
-        halting_nodes.insert(node_idx.clone());
+        halting_nodes.insert(node_idx);
         for preceding_node in &reverse_flow_graph[node_idx] {
-            queue.push_back(preceding_node.clone());
+            queue.push_back(*preceding_node);
         }
     }
 
@@ -132,47 +164,110 @@ fn detect_loop(program: &[OpCode]) -> State {
     }
 }
 
-fn execute_program_with_self_healing(program: &[OpCode]) -> i32 {
-    let mut state = State::new();
-    let halting_nodes = determine_halting_nodes(&construct_reverse_flow_graph(program));
-    let mut fixed = false;
+fn execute_program_with_self_healing(program: &[OpCode]) -> Option<i32> {
+    find_fixes(program, &[Mutation::SwapJmpNop])
+        .first()
+        .map(|fix| fix.accumulator)
+}
 
-    while state.instruction_pointer < program.len() {
-        let operation = &program[state.instruction_pointer];
+fn print_fixes(program: &[OpCode], fixes: &[Fix]) {
+    for fix in fixes {
+        println!(
+            "fix: {} at {}: {} -> {}, result {}",
+            fix.mutation, fix.position, program[fix.position], fix.replacement, fix.accumulator
+        );
+    }
+}
 
-        let flipped = match (fixed, operation) {
-            (false, OpCode::Jmp(value)) => Some(OpCode::Nop(*value)),
-            (false, OpCode::Nop(value)) => Some(OpCode::Jmp(*value)),
-            _ => None,
-        };
-        if let Some(flipped) = flipped {
-            let state_with_flipping = reduce(state.clone(), &flipped);
-            if halting_nodes.contains(&state_with_flipping.instruction_pointer) {
-                state = state_with_flipping;
-                fixed = true;
-                continue;
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut dump_dir = None;
+    let mut mutations = None;
+    let mut brute_force = false;
+    let mut step_limit = None;
+    let mut n_threads = None;
+    let mut optimize = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump-intermediate" => {
+                dump_dir = Some(PathBuf::from(
+                    args.next()
+                        .ok_or("--dump-intermediate requires a directory.")?,
+                ))
             }
+            "--mutations" => {
+                mutations = Some(
+                    args.next()
+                        .ok_or("--mutations requires a comma-separated list.")?
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<Result<Vec<Mutation>, _>>()?,
+                )
+            }
+            "--brute-force" => brute_force = true,
+            "--eliminate-dead-code" => optimize = true,
+            "--step-limit" => {
+                step_limit = Some(
+                    args.next()
+                        .ok_or("--step-limit requires a number.")?
+                        .parse()?,
+                )
+            }
+            "--threads" => {
+                n_threads = Some(args.next().ok_or("--threads requires a number.")?.parse()?)
+            }
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
         }
-
-        state = reduce(state, operation);
+    }
+    if let Some(n_threads) = n_threads {
+        set_num_threads(n_threads)?;
     }
 
-    state.accumulator
-}
-
-fn main() {
     let program: Vec<OpCode> = io::stdin()
         .lock()
         .lines()
         .map(Result::unwrap)
         .map(|line| OpCode::parse(&line).unwrap())
         .collect();
+    if let Some(dir) = &dump_dir {
+        dump_intermediate(dir, "program", &program)?;
+        dump_intermediate(
+            dir,
+            "reverse_flow_graph",
+            &construct_reverse_flow_graph(&program),
+        )?;
+    }
+
+    if optimize {
+        let optimized = eliminate_dead_code(&program);
+        if let Some(dir) = &dump_dir {
+            dump_intermediate(dir, "optimized", &optimized)?;
+        }
+        for (operation, origin) in optimized.program.iter().zip(&optimized.origins) {
+            println!("{:<8} ; {}", operation.to_string(), origin);
+        }
+        return Ok(());
+    }
 
     let loop_state = detect_loop(&program);
     println!("loop_state: {:?}", loop_state);
 
-    let result = execute_program_with_self_healing(&program);
-    println!("result of fixed program: {}", result);
+    if brute_force {
+        // A terminating program cannot execute an instruction twice, thus
+        // the program length is a sufficient default limit.
+        let step_limit = step_limit.unwrap_or(program.len());
+        let mutations = mutations.unwrap_or_else(|| vec![Mutation::SwapJmpNop]);
+        print_fixes(
+            &program,
+            &brute_force_fixes(&program, &mutations, step_limit),
+        );
+    } else if let Some(mutations) = mutations {
+        print_fixes(&program, &find_fixes(&program, &mutations));
+    } else {
+        let result = execute_program_with_self_healing(&program).ok_or("No fix found.")?;
+        println!("result of fixed program: {}", result);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -204,6 +299,18 @@ mod tests {
         assert_eq!(OpCode::parse(input).unwrap(), expected);
     }
 
+    #[test]
+    fn test_serialize_opcode() {
+        assert_eq!(
+            serde_json::to_value(&PROGRAM[..3]).unwrap(),
+            serde_json::json!([
+                {"op": "nop", "arg": 0},
+                {"op": "acc", "arg": 1},
+                {"op": "jmp", "arg": 4},
+            ])
+        );
+    }
+
     #[rstest(state, operation, new_state,
         case(State::new(), OpCode::Acc(10), State { accumulator: 10, instruction_pointer: 1 }),
         case(State::new(), OpCode::Acc(-10), State { accumulator: -10, instruction_pointer: 1 }),
@@ -255,6 +362,6 @@ mod tests {
 
     #[test]
     fn test_execute_program_with_self_healing() {
-        assert_eq!(execute_program_with_self_healing(&PROGRAM), 8);
+        assert_eq!(execute_program_with_self_healing(&PROGRAM), Some(8));
     }
 }
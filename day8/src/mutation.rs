@@ -0,0 +1,212 @@
+use crate::{construct_reverse_flow_graph, determine_halting_nodes, successor, OpCode};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// A change to a single instruction that might repair the program.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mutation {
+    SwapJmpNop,
+    AccToNop,
+    IncrementOperand,
+    DecrementOperand,
+}
+
+impl Mutation {
+    // The replacement for the operation, None if the mutation does not apply.
+    pub fn apply(self, operation: &OpCode) -> Option<OpCode> {
+        use OpCode::*;
+        match (self, operation) {
+            (Mutation::SwapJmpNop, Jmp(value)) => Some(Nop(*value)),
+            (Mutation::SwapJmpNop, Nop(value)) => Some(Jmp(*value)),
+            (Mutation::AccToNop, Acc(value)) => Some(Nop(*value as isize)),
+            (Mutation::IncrementOperand, Acc(value)) => Some(Acc(value + 1)),
+            (Mutation::IncrementOperand, Jmp(value)) => Some(Jmp(value + 1)),
+            (Mutation::IncrementOperand, Nop(value)) => Some(Nop(value + 1)),
+            (Mutation::DecrementOperand, Acc(value)) => Some(Acc(value - 1)),
+            (Mutation::DecrementOperand, Jmp(value)) => Some(Jmp(value - 1)),
+            (Mutation::DecrementOperand, Nop(value)) => Some(Nop(value - 1)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Mutation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(match self {
+            Mutation::SwapJmpNop => "swap",
+            Mutation::AccToNop => "acc-nop",
+            Mutation::IncrementOperand => "inc",
+            Mutation::DecrementOperand => "dec",
+        })
+    }
+}
+
+impl FromStr for Mutation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "swap" => Ok(Mutation::SwapJmpNop),
+            "acc-nop" => Ok(Mutation::AccToNop),
+            "inc" => Ok(Mutation::IncrementOperand),
+            "dec" => Ok(Mutation::DecrementOperand),
+            _ => Err(format!("Unknown mutation '{}'.", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fix {
+    pub position: usize,
+    pub mutation: Mutation,
+    pub replacement: OpCode,
+    // Accumulator value when the repaired program terminates.
+    pub accumulator: i32,
+}
+
+// Runs the program and returns the final accumulator, None if it loops or
+// jumps before the start.
+fn run(program: &[OpCode]) -> Option<i32> {
+    let mut visited = vec![false; program.len()];
+    let mut accumulator = 0;
+    let mut instruction_pointer = 0;
+    while instruction_pointer < program.len() {
+        if visited[instruction_pointer] {
+            return None;
+        }
+        visited[instruction_pointer] = true;
+        let operation = &program[instruction_pointer];
+        if let OpCode::Acc(value) = operation {
+            accumulator += value;
+        }
+        instruction_pointer = successor(instruction_pointer, operation, program.len())?;
+    }
+    Some(accumulator)
+}
+
+// All single-instruction mutations that make the looping program terminate,
+// in execution order. Only the instructions executed before the loop closes
+// affect the outcome, so only these are mutated. None of them can reach the
+// end in the unmodified program, thus the paths from the halting nodes do not
+// pass through the mutated instruction and the halting nodes of the
+// unmodified program remain valid for every single mutation.
+pub fn find_fixes(program: &[OpCode], mutations: &[Mutation]) -> Vec<Fix> {
+    let halting_nodes = determine_halting_nodes(&construct_reverse_flow_graph(program));
+    let mut fixes = vec![];
+    if halting_nodes.contains(&0) {
+        return fixes;
+    }
+
+    let mut visited = vec![false; program.len()];
+    let mut instruction_pointer = Some(0);
+    while let Some(position) = instruction_pointer.filter(|&i| i < program.len() && !visited[i]) {
+        visited[position] = true;
+        let operation = &program[position];
+        for &mutation in mutations {
+            let replacement = match mutation.apply(operation) {
+                Some(replacement) => replacement,
+                None => continue,
+            };
+            let halts = successor(position, &replacement, program.len())
+                .is_some_and(|next| halting_nodes.contains(&next));
+            if halts {
+                let mut repaired = program.to_vec();
+                repaired[position] = replacement.clone();
+                fixes.push(Fix {
+                    position,
+                    mutation,
+                    replacement,
+                    accumulator: run(&repaired).expect("Repaired program must terminate."),
+                });
+            }
+        }
+        instruction_pointer = successor(position, operation, program.len());
+    }
+    fixes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OpCode::*;
+
+    static PROGRAM: [OpCode; 9] = [
+        Nop(0),
+        Acc(1),
+        Jmp(4),
+        Acc(3),
+        Jmp(-3),
+        Acc(-99),
+        Acc(1),
+        Jmp(-4),
+        Acc(6),
+    ];
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(Mutation::SwapJmpNop.apply(&Jmp(3)), Some(Nop(3)));
+        assert_eq!(Mutation::SwapJmpNop.apply(&Acc(3)), None);
+        assert_eq!(Mutation::AccToNop.apply(&Acc(3)), Some(Nop(3)));
+        assert_eq!(Mutation::IncrementOperand.apply(&Jmp(-3)), Some(Jmp(-2)));
+        assert_eq!(Mutation::DecrementOperand.apply(&Acc(3)), Some(Acc(2)));
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        use Mutation::*;
+        for &mutation in &[SwapJmpNop, AccToNop, IncrementOperand, DecrementOperand] {
+            assert_eq!(mutation.to_string().parse(), Ok(mutation));
+        }
+        assert!("flip".parse::<Mutation>().is_err());
+    }
+
+    #[test]
+    fn test_find_fixes() {
+        assert_eq!(
+            find_fixes(&PROGRAM, &[Mutation::SwapJmpNop]),
+            vec![Fix {
+                position: 7,
+                mutation: Mutation::SwapJmpNop,
+                replacement: Nop(-4),
+                accumulator: 8,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_fixes_with_operand_changes() {
+        let mutations = [
+            Mutation::SwapJmpNop,
+            Mutation::AccToNop,
+            Mutation::IncrementOperand,
+            Mutation::DecrementOperand,
+        ];
+        let program = [Acc(1), Jmp(0)];
+        assert_eq!(
+            find_fixes(&program, &mutations),
+            vec![
+                Fix {
+                    position: 1,
+                    mutation: Mutation::SwapJmpNop,
+                    replacement: Nop(0),
+                    accumulator: 1,
+                },
+                Fix {
+                    position: 1,
+                    mutation: Mutation::IncrementOperand,
+                    replacement: Jmp(1),
+                    accumulator: 1,
+                },
+            ]
+        );
+        assert_eq!(find_fixes(&PROGRAM, &mutations).len(), 1);
+    }
+
+    #[test]
+    fn test_find_fixes_of_terminating_program() {
+        assert_eq!(
+            find_fixes(&[Nop(0), Acc(1)], &[Mutation::SwapJmpNop]),
+            vec![]
+        );
+    }
+}
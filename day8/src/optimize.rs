@@ -0,0 +1,158 @@
+use crate::{successor, OpCode};
+use serde::Serialize;
+
+// A program with the dead code removed. `origins[i]` is the position of
+// instruction i in the original program.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Optimized {
+    pub program: Vec<OpCode>,
+    pub origins: Vec<usize>,
+}
+
+// Instructions that can be executed when starting at the first one.
+fn determine_reachable_nodes(program: &[OpCode]) -> Vec<bool> {
+    let mut reachable = vec![false; program.len()];
+    let mut stack = vec![0];
+    while let Some(node_idx) = stack.pop() {
+        if node_idx >= program.len() || reachable[node_idx] {
+            continue;
+        }
+        reachable[node_idx] = true;
+        stack.extend(successor(node_idx, &program[node_idx], program.len()));
+    }
+    reachable
+}
+
+// Removes the instructions unreachable from the entry point and folds runs of
+// nops into the first one of each run. Jumps are relocated to keep their
+// targets; a jump into a run of nops lands on the kept nop, which has the same
+// effect. The optimized program loops and terminates with the same accumulator.
+// Fixes might need the removed code, though, and the arguments of folded nops
+// are lost, so fixes need to be searched on the original program.
+pub fn eliminate_dead_code(program: &[OpCode]) -> Optimized {
+    let reachable = determine_reachable_nodes(program);
+
+    // Position in the optimized program for every original position including
+    // the end of the program.
+    let mut relocation = Vec::with_capacity(program.len() + 1);
+    let mut origins = vec![];
+    for (i, operation) in program.iter().enumerate() {
+        let folded = i > 0
+            && matches!(operation, OpCode::Nop(_))
+            && matches!(program[i - 1], OpCode::Nop(_))
+            && reachable[i - 1];
+        if reachable[i] && !folded {
+            origins.push(i);
+        }
+        relocation.push(origins.len().saturating_sub(1));
+    }
+    relocation.push(origins.len());
+
+    let optimized = origins
+        .iter()
+        .enumerate()
+        .map(|(new_position, &position)| match program[position] {
+            OpCode::Jmp(value) => match successor(position, &program[position], program.len()) {
+                Some(target) => OpCode::Jmp(relocation[target] as isize - new_position as isize),
+                // Jumps before the start stay before the start.
+                None => OpCode::Jmp(value + (position - new_position) as isize),
+            },
+            ref operation => operation.clone(),
+        })
+        .collect();
+    Optimized {
+        program: optimized,
+        origins,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detect_loop;
+
+    static PROGRAM: [OpCode; 9] = [
+        OpCode::Nop(0),
+        OpCode::Acc(1),
+        OpCode::Jmp(4),
+        OpCode::Acc(3),
+        OpCode::Jmp(-3),
+        OpCode::Acc(-99),
+        OpCode::Acc(1),
+        OpCode::Jmp(-4),
+        OpCode::Acc(6),
+    ];
+
+    #[test]
+    fn test_eliminate_unreachable_instructions() {
+        let optimized = eliminate_dead_code(&PROGRAM);
+        assert_eq!(
+            optimized,
+            Optimized {
+                program: vec![
+                    OpCode::Nop(0),
+                    OpCode::Acc(1),
+                    OpCode::Jmp(3),
+                    OpCode::Acc(3),
+                    OpCode::Jmp(-3),
+                    OpCode::Acc(1),
+                    OpCode::Jmp(-3),
+                ],
+                origins: vec![0, 1, 2, 3, 4, 6, 7],
+            }
+        );
+        assert_eq!(
+            detect_loop(&optimized.program).accumulator,
+            detect_loop(&PROGRAM).accumulator
+        );
+    }
+
+    #[test]
+    fn test_fold_nops() {
+        let program = [
+            OpCode::Acc(1),
+            OpCode::Nop(0),
+            OpCode::Nop(5),
+            OpCode::Nop(-2),
+            OpCode::Acc(2),
+            OpCode::Jmp(-2),
+        ];
+        assert_eq!(
+            eliminate_dead_code(&program),
+            Optimized {
+                program: vec![
+                    OpCode::Acc(1),
+                    OpCode::Nop(0),
+                    OpCode::Acc(2),
+                    OpCode::Jmp(-2),
+                ],
+                origins: vec![0, 1, 4, 5],
+            }
+        );
+    }
+
+    #[test]
+    fn test_relocate_jumps_out_of_the_program() {
+        let program = [
+            OpCode::Jmp(2),
+            OpCode::Acc(7),
+            OpCode::Jmp(-3),
+            OpCode::Jmp(10),
+        ];
+        assert_eq!(
+            eliminate_dead_code(&program),
+            Optimized {
+                program: vec![OpCode::Jmp(1), OpCode::Jmp(-2)],
+                origins: vec![0, 2],
+            }
+        );
+        let program = [OpCode::Acc(1), OpCode::Jmp(10), OpCode::Acc(2)];
+        assert_eq!(
+            eliminate_dead_code(&program),
+            Optimized {
+                program: vec![OpCode::Acc(1), OpCode::Jmp(1)],
+                origins: vec![0, 1],
+            }
+        );
+    }
+}
@@ -0,0 +1,26 @@
+fn run(fixture: &str, args: &[&str]) -> String {
+    testutil::run(
+        env!("CARGO_BIN_EXE_day8"),
+        env!("CARGO_MANIFEST_DIR"),
+        fixture,
+        args,
+    )
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(
+        run("example.txt", &[]),
+        "loop_state: State { accumulator: 5, instruction_pointer: 1 }\n\
+         result of fixed program: 8\n"
+    );
+}
+
+#[test]
+fn test_brute_force() {
+    assert_eq!(
+        run("example.txt", &["--brute-force"]),
+        "loop_state: State { accumulator: 5, instruction_pointer: 1 }\n\
+         fix: swap at 7: jmp -4 -> nop -4, result 8\n"
+    );
+}
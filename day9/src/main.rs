@@ -1,59 +1,19 @@
-use std::collections::{HashSet, VecDeque};
+use numutil::{find_contiguous_range_with_sum, find_pair_with_sum, WindowValidateExt};
 use std::io::{self, BufRead};
 
-struct XmasProcessor {
-    preamble_len: usize,
-    window_queue: VecDeque<u64>,
-    window_tree: HashSet<u64>,
+fn is_sum_of_pair(window: &[u64], &value: &u64) -> bool {
+    find_pair_with_sum(window, value).is_some()
 }
 
-impl XmasProcessor {
-    fn new(preamble_len: usize) -> Self {
-        XmasProcessor {
-            preamble_len,
-            window_queue: VecDeque::with_capacity(preamble_len),
-            window_tree: HashSet::new(),
-        }
-    }
-
-    fn push(&mut self, value: u64) -> bool {
-        let result = if self.preamble_len <= self.window_queue.len() {
-            let result = self.window_queue.iter().any(|x| {
-                if let Some(diff) = value.checked_sub(*x) {
-                    self.window_tree.contains(&diff)
-                } else {
-                    false
-                }
-            });
-            self.window_tree
-                .remove(&self.window_queue.pop_front().unwrap());
-            result
-        } else {
-            true
-        };
-        self.window_queue.push_back(value);
-        self.window_tree.insert(value);
-        result
-    }
-}
-
-fn find_contiguous_range_with_sum(values: &[u64], target_sum: u64) -> Option<(usize, usize)> {
-    let mut window: VecDeque<(usize, u64)> = VecDeque::with_capacity(values.len() / 2);
-    let mut sum = 0;
-    for (i, value) in values.iter().enumerate() {
-        window.push_back((i, *value));
-        sum += value;
-
-        while sum > target_sum && window.len() > 0 {
-            sum -= window.pop_front().unwrap().1;
-        }
-
-        if sum == target_sum {
-            return Some((window.front().unwrap().0, window.back().unwrap().0 + 1));
-        }
-    }
-
-    None
+// The first value that is not the sum of two of the `preamble_len` values
+// before it.
+fn find_first_invalid(values: &[u64], preamble_len: usize) -> Option<u64> {
+    values
+        .iter()
+        .copied()
+        .window_validate(preamble_len, is_sum_of_pair)
+        .find(|&(_, is_valid)| !is_valid)
+        .map(|(value, _)| value)
 }
 
 fn main() {
@@ -63,18 +23,13 @@ fn main() {
         .lines()
         .map(|line| line.unwrap().parse().unwrap())
         .collect();
-    let mut processor = XmasProcessor::new(25);
-    for value in &values {
-        if !processor.push(*value) {
-            println!("First invalid value: {}", value);
-
-            if let Some((lb, ub)) = find_contiguous_range_with_sum(&values, *value) {
-                let min = values[lb..ub].iter().min().unwrap();
-                let max = values[lb..ub].iter().max().unwrap();
-                println!("Encryption weakness: {}", min + max);
-            }
+    if let Some(value) = find_first_invalid(&values, 25) {
+        println!("First invalid value: {}", value);
 
-            return;
+        if let Some(range) = find_contiguous_range_with_sum(&values, value, 2) {
+            let min = values[range.clone()].iter().min().unwrap();
+            let max = values[range].iter().max().unwrap();
+            println!("Encryption weakness: {}", min + max);
         }
     }
 }
@@ -88,9 +43,13 @@ mod tests {
     ];
 
     #[test]
-    fn test_xmas_processor_push() {
-        let mut processor = XmasProcessor::new(5);
-        let result: Vec<bool> = INPUT.iter().map(|x| processor.push(*x)).collect();
+    fn test_validate_sum_of_pair() {
+        let result: Vec<bool> = INPUT
+            .iter()
+            .copied()
+            .window_validate(5, is_sum_of_pair)
+            .map(|(_, is_valid)| is_valid)
+            .collect();
         assert_eq!(
             result,
             vec![
@@ -101,7 +60,8 @@ mod tests {
     }
 
     #[test]
-    fn test_find_contiguous_range_with_sume() {
-        assert_eq!(find_contiguous_range_with_sum(&INPUT, 127), Some((2, 6)));
+    fn test_find_first_invalid() {
+        assert_eq!(find_first_invalid(&INPUT, 5), Some(127));
+        assert_eq!(find_first_invalid(&INPUT[..14], 5), None);
     }
 }
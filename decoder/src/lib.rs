@@ -0,0 +1,414 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+
+pub mod program;
+
+pub type Address = u64;
+pub type Value = u64;
+
+// Width in bits of the masks, addresses, and values of the puzzle.
+pub const DEFAULT_WIDTH: u32 = 36;
+pub const MAX_WIDTH: u32 = Value::BITS;
+
+#[derive(Debug, PartialEq)]
+pub enum MaskParseError {
+    UnsupportedWidth(u32),
+    WidthMismatch { expected: u32, found: usize },
+    InvalidDigit(ParseIntError),
+}
+
+impl Display for MaskParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::UnsupportedWidth(width) => write!(
+                f,
+                "mask width must be between 1 and {} bits, got {}",
+                MAX_WIDTH, width
+            ),
+            Self::WidthMismatch { expected, found } => write!(
+                f,
+                "expected a mask of {} bits, found {} bits",
+                expected, found
+            ),
+            Self::InvalidDigit(err) => write!(f, "invalid mask: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MaskParseError {}
+
+impl From<ParseIntError> for MaskParseError {
+    fn from(err: ParseIntError) -> Self {
+        Self::InvalidDigit(err)
+    }
+}
+
+// Whether the value can be represented with the given number of bits.
+pub fn fits_width(value: Value, width: u32) -> bool {
+    width >= MAX_WIDTH || value >> width == 0
+}
+
+fn check_width(mask: &str, width: u32) -> Result<(), MaskParseError> {
+    if width == 0 || width > MAX_WIDTH {
+        Err(MaskParseError::UnsupportedWidth(width))
+    } else if mask.len() != width as usize {
+        Err(MaskParseError::WidthMismatch {
+            expected: width,
+            found: mask.len(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Mask {
+    pub zero_mask: Value,
+    pub one_mask: Value,
+}
+
+impl Default for Mask {
+    fn default() -> Self {
+        Mask {
+            zero_mask: u64::MAX,
+            one_mask: 0,
+        }
+    }
+}
+
+impl Mask {
+    // Parses a mask that must have exactly `width` bits.
+    pub fn parse(value: &str, width: u32) -> Result<Self, MaskParseError> {
+        check_width(value, width)?;
+        let zero_mask = value.replace('X', "1");
+        let zero_mask = Value::from_str_radix(&zero_mask, 2)?;
+        let one_mask = value.replace('X', "0");
+        let one_mask = Value::from_str_radix(&one_mask, 2)?;
+        Ok(Self {
+            zero_mask,
+            one_mask,
+        })
+    }
+
+    pub fn apply(&self, value: Value) -> Value {
+        (value & self.zero_mask) | self.one_mask
+    }
+}
+
+impl TryFrom<&str> for Mask {
+    type Error = MaskParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value, DEFAULT_WIDTH)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Decoder {
+    pub one_mask: Address,
+    pub fluctuating_bits: Vec<usize>,
+}
+
+pub struct AddressIterator<'a> {
+    base_address: Address,
+    fluctuating_bits: &'a [usize],
+    fluctuating_mask: Address,
+    state: u128,
+}
+
+impl<'a> AddressIterator<'a> {
+    fn new(address: Address, decoder: &'a Decoder) -> Self {
+        Self {
+            base_address: address | decoder.one_mask,
+            fluctuating_bits: &decoder.fluctuating_bits,
+            fluctuating_mask: Self::bits2mask(&decoder.fluctuating_bits, u128::MAX),
+            state: 0,
+        }
+    }
+
+    fn bits2mask(bits: &[usize], bit_selector: u128) -> Address {
+        bits.iter().enumerate().fold(0, |mask, (i, bit)| {
+            if bit_selector & (1 << i) != 0 {
+                mask | (1 << bit)
+            } else {
+                mask
+            }
+        })
+    }
+}
+
+impl<'a> Iterator for AddressIterator<'a> {
+    type Item = Address;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state < 1 << self.fluctuating_bits.len() {
+            let mask = Self::bits2mask(self.fluctuating_bits, self.state);
+            self.state += 1;
+            Some((self.base_address & !self.fluctuating_mask) | mask)
+        } else {
+            None
+        }
+    }
+}
+
+impl Decoder {
+    // Parses a mask that must have exactly `width` bits.
+    pub fn parse(value: &str, width: u32) -> Result<Self, MaskParseError> {
+        check_width(value, width)?;
+        let one_mask = value.replace('X', "0");
+        let one_mask = Value::from_str_radix(&one_mask, 2)?;
+        Ok(Self {
+            one_mask,
+            fluctuating_bits: value
+                .as_bytes()
+                .iter()
+                .rev()
+                .enumerate()
+                .filter_map(|(i, &c)| if c == b'X' { Some(i) } else { None })
+                .collect(),
+        })
+    }
+
+    pub fn iter_addresses(&self, base_address: Address) -> AddressIterator<'_> {
+        AddressIterator::new(base_address, self)
+    }
+
+    pub fn address_set(&self, base_address: Address) -> AddressSet {
+        let floating = AddressIterator::bits2mask(&self.fluctuating_bits, u128::MAX);
+        AddressSet {
+            fixed: (base_address | self.one_mask) & !floating,
+            floating,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AddressSet {
+    pub fixed: Address,
+    pub floating: Address,
+}
+
+impl AddressSet {
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let fixed_in_both = !self.floating & !other.floating;
+        if (self.fixed ^ other.fixed) & fixed_in_both != 0 {
+            return None;
+        }
+        let floating = self.floating & other.floating;
+        Some(Self {
+            fixed: (self.fixed | other.fixed) & !floating,
+            floating,
+        })
+    }
+
+    // With all 64 bits floating, there is one address more than a u64 holds.
+    pub fn n_addresses(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(&self, address: Address) -> bool {
+        address & !self.floating == self.fixed
+    }
+}
+
+impl TryFrom<&str> for Decoder {
+    type Error = MaskParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value, DEFAULT_WIDTH)
+    }
+}
+
+pub fn decode_write(
+    decoder: &Decoder,
+    address: Address,
+    value: Value,
+) -> impl Iterator<Item = (Address, Value)> + '_ {
+    decoder
+        .iter_addresses(address)
+        .map(move |address| (address, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_parse_mask() {
+        assert_eq!(
+            Mask::try_from("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").unwrap(),
+            Mask {
+                zero_mask: 0x0ffffffffd,
+                one_mask: 0b1000000
+            }
+        );
+    }
+
+    #[test]
+    fn test_mask_apply() {
+        let mask = Mask::try_from("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").unwrap();
+        assert_eq!(mask.apply(11), 73);
+        assert_eq!(mask.apply(101), 101);
+        assert_eq!(mask.apply(0), 64);
+    }
+
+    #[test]
+    fn test_parse_mask_checks_width() {
+        assert_eq!(
+            Mask::try_from("X1X"),
+            Err(MaskParseError::WidthMismatch {
+                expected: 36,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Mask::parse("X1X", 3),
+            Ok(Mask {
+                zero_mask: 0b111,
+                one_mask: 0b010
+            })
+        );
+        assert_eq!(Mask::parse("", 0), Err(MaskParseError::UnsupportedWidth(0)));
+        assert!(matches!(
+            Mask::parse("X1X2", 4),
+            Err(MaskParseError::InvalidDigit(_))
+        ));
+    }
+
+    #[test]
+    fn test_mask_apply_64_bits() {
+        let mut mask = "X".repeat(64);
+        mask.replace_range(0..1, "0");
+        let mask = Mask::parse(&mask, 64).unwrap();
+        assert_eq!(mask.apply(u64::MAX), u64::MAX >> 1);
+        assert_eq!(
+            Decoder::parse(&"1".repeat(64), 64).unwrap().one_mask,
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_all_64_bits_floating() {
+        let decoder = Decoder::parse(&"X".repeat(64), 64).unwrap();
+        assert_eq!(decoder.address_set(42).n_addresses(), 1 << 64);
+        assert_eq!(
+            decoder.iter_addresses(42).take(3).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_fits_width() {
+        assert!(fits_width((1 << 36) - 1, 36));
+        assert!(!fits_width(1 << 36, 36));
+        assert!(fits_width(u64::MAX, 64));
+    }
+
+    #[test]
+    fn test_parse_decoder() {
+        assert_eq!(
+            Decoder::try_from("000000000000000000000000000000X0XX11").unwrap(),
+            Decoder {
+                one_mask: 0b11,
+                fluctuating_bits: vec![2, 3, 5],
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_write() {
+        let decoder = Decoder::try_from("000000000000000000000000000000X1001X").unwrap();
+        let writes: HashSet<(Address, Value)> = decode_write(&decoder, 42, 100).collect();
+        assert_eq!(
+            writes,
+            [(26, 100), (27, 100), (58, 100), (59, 100)]
+                .iter()
+                .copied()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn test_address_set_intersection() {
+        let a = AddressSet {
+            fixed: 0b0001,
+            floating: 0b1010,
+        };
+        let b = AddressSet {
+            fixed: 0b1001,
+            floating: 0b0010,
+        };
+        let c = AddressSet {
+            fixed: 0b0000,
+            floating: 0b1110,
+        };
+        assert_eq!(
+            a.intersection(&b),
+            Some(AddressSet {
+                fixed: 0b1001,
+                floating: 0b0010,
+            })
+        );
+        assert_eq!(a.intersection(&c), None);
+    }
+
+    fn mask_strategy() -> impl Strategy<Value = String> {
+        proptest::collection::vec(prop_oneof![Just('0'), Just('1'), Just('X')], 36)
+            .prop_filter("at most 12 floating bits", |mask| {
+                mask.iter().filter(|&&c| c == 'X').count() <= 12
+            })
+            .prop_map(|mask| mask.into_iter().collect())
+    }
+
+    proptest! {
+        #[test]
+        fn address_set_matches_decoded_addresses(
+            mask in mask_strategy(),
+            address in 0..(1u64 << 36),
+        ) {
+            let decoder = Decoder::try_from(mask.as_str()).unwrap();
+            let set = decoder.address_set(address);
+            let n_addresses = decoder
+                .iter_addresses(address)
+                .inspect(|&decoded| assert!(set.contains(decoded)))
+                .count();
+            prop_assert_eq!(set.n_addresses(), n_addresses as u128);
+        }
+
+        #[test]
+        fn decode_write_yields_two_to_the_floating_bits_distinct_addresses(
+            mask in mask_strategy(),
+            address in 0..(1u64 << 36),
+            value: Value,
+        ) {
+            let decoder = Decoder::try_from(mask.as_str()).unwrap();
+            let n_floating = mask.chars().filter(|&c| c == 'X').count();
+            let addresses: HashSet<Address> = decode_write(&decoder, address, value)
+                .map(|(address, _)| address)
+                .collect();
+            prop_assert_eq!(addresses.len(), 1 << n_floating);
+        }
+
+        #[test]
+        fn decode_write_addresses_share_forced_bits(
+            mask in mask_strategy(),
+            address in 0..(1u64 << 36),
+            value: Value,
+        ) {
+            let decoder = Decoder::try_from(mask.as_str()).unwrap();
+            let floating_bits =
+                Value::from_str_radix(&mask.replace('1', "0").replace('X', "1"), 2).unwrap();
+            for (decoded_address, decoded_value) in decode_write(&decoder, address, value) {
+                prop_assert_eq!(decoded_value, value);
+                prop_assert_eq!(decoded_address & decoder.one_mask, decoder.one_mask);
+                prop_assert_eq!(
+                    decoded_address & !floating_bits & !decoder.one_mask,
+                    address & !floating_bits & !decoder.one_mask
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use super::{fits_width, Address, Decoder, Mask, MaskParseError, Value};
+use nom::{error::VerboseError, IResult};
+use parsing::parse_full;
+use std::fmt::{Display, Formatter};
+
+// The statements of the initialization program, generic over how the mask is
+// interpreted.
+#[derive(Debug, PartialEq)]
+pub enum OpCode<M> {
+    SetMask(M),
+    SetMem(Address, Value),
+    AddMem(Address, Value),
+    Clear,
+}
+
+pub trait ParseMask: Sized {
+    fn parse_mask(value: &str, width: u32) -> Result<Self, MaskParseError>;
+}
+
+impl ParseMask for Mask {
+    fn parse_mask(value: &str, width: u32) -> Result<Self, MaskParseError> {
+        Self::parse(value, width)
+    }
+}
+
+impl ParseMask for Decoder {
+    fn parse_mask(value: &str, width: u32) -> Result<Self, MaskParseError> {
+        Self::parse(value, width)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OpCodeParseError {
+    pub offending_text: String,
+    pub expected: String,
+}
+
+impl OpCodeParseError {
+    pub fn new(offending_text: &str, expected: &str) -> Self {
+        Self {
+            offending_text: String::from(offending_text),
+            expected: String::from(expected),
+        }
+    }
+}
+
+impl<'a> From<nom::Err<VerboseError<&'a str>>> for OpCodeParseError {
+    fn from(err: nom::Err<VerboseError<&'a str>>) -> Self {
+        use nom::error::VerboseErrorKind;
+        match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => {
+                let offending_text = err.errors.first().map(|(input, _)| *input).unwrap_or("");
+                let expected = match err.errors.first() {
+                    Some((_, VerboseErrorKind::Char(c))) => format!("'{}'", c),
+                    first => err
+                        .errors
+                        .iter()
+                        .find_map(|(_, kind)| match kind {
+                            VerboseErrorKind::Context(context) => Some(String::from(*context)),
+                            _ => None,
+                        })
+                        .unwrap_or_else(|| match first {
+                            Some((_, VerboseErrorKind::Nom(kind))) => {
+                                String::from(kind.description())
+                            }
+                            _ => String::from("valid statement"),
+                        }),
+                };
+                Self::new(offending_text, &expected)
+            }
+            nom::Err::Incomplete(_) => Self::new("", "more input"),
+        }
+    }
+}
+
+impl Display for OpCodeParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.offending_text.is_empty() {
+            write!(f, "expected {}, found end of line", self.expected)
+        } else {
+            write!(
+                f,
+                "expected {}, found '{}'",
+                self.expected, self.offending_text
+            )
+        }
+    }
+}
+
+impl std::error::Error for OpCodeParseError {}
+
+#[derive(Debug, PartialEq)]
+pub struct ProgramParseError {
+    pub line: usize,
+    pub statement: String,
+    pub error: OpCodeParseError,
+}
+
+impl Display for ProgramParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "line {}: {} in statement '{}'",
+            self.line, self.error, self.statement
+        )
+    }
+}
+
+impl std::error::Error for ProgramParseError {}
+
+type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+fn parse_number(input: &str) -> ParseResult<'_, u64> {
+    use nom::{character::complete::alphanumeric1, combinator::map_res, error::context};
+    context("an unsigned integer", map_res(alphanumeric1, str::parse))(input)
+}
+
+fn parse_address(input: &str) -> ParseResult<'_, Address> {
+    use nom::{character::complete::char, sequence::delimited};
+    delimited(char('['), parse_number, char(']'))(input)
+}
+
+fn parse_assignment(input: &str) -> ParseResult<'_, char> {
+    use nom::{
+        character::complete::{char, space0},
+        sequence::delimited,
+    };
+    delimited(space0, char('='), space0)(input)
+}
+
+fn parse_end_of_statement(input: &str) -> ParseResult<'_, ()> {
+    use nom::{
+        character::complete::{char, not_line_ending, space0},
+        combinator::{eof, map, opt},
+        error::context,
+        sequence::{preceded, tuple},
+    };
+    let comment = preceded(char('#'), not_line_ending);
+    map(
+        tuple((space0, opt(comment), context("end of statement", eof))),
+        |_| (),
+    )(input)
+}
+
+impl<M: ParseMask> OpCode<M> {
+    // Masks must have exactly `width` bits and addresses and values must fit
+    // into them.
+    pub fn parse_statement(input: &str, width: u32) -> Result<Option<Self>, OpCodeParseError> {
+        use nom::{
+            branch::alt,
+            bytes::complete::tag,
+            character::complete::{alphanumeric1, space0},
+            combinator::{cut, map, map_res},
+            error::context,
+            sequence::{preceded, separated_pair, terminated},
+        };
+        let mask = context(
+            "mask of 0, 1, and X",
+            map_res(alphanumeric1, |mask| M::parse_mask(mask, width)),
+        );
+        let statement = context(
+            "'mask', 'mem', 'add', or 'clear'",
+            alt((
+                map(
+                    preceded(tag("mask"), cut(preceded(parse_assignment, mask))),
+                    OpCode::SetMask,
+                ),
+                map(
+                    preceded(
+                        tag("mem"),
+                        cut(separated_pair(
+                            parse_address,
+                            parse_assignment,
+                            parse_number,
+                        )),
+                    ),
+                    |(address, value)| OpCode::SetMem(address, value),
+                ),
+                map(
+                    preceded(
+                        tag("add"),
+                        cut(separated_pair(
+                            parse_address,
+                            parse_assignment,
+                            parse_number,
+                        )),
+                    ),
+                    |(address, value)| OpCode::AddMem(address, value),
+                ),
+                map(tag("clear"), |_| OpCode::Clear),
+            )),
+        );
+        let line = preceded(
+            space0,
+            alt((
+                map(parse_end_of_statement, |_| None),
+                map(terminated(statement, cut(parse_end_of_statement)), Some),
+            )),
+        );
+        let op_code = parse_full(line, input)?;
+        if let Some(OpCode::SetMem(address, value)) | Some(OpCode::AddMem(address, value)) = op_code
+        {
+            if let Some(operand) = [address, value]
+                .iter()
+                .find(|&&operand| !fits_width(operand, width))
+            {
+                return Err(OpCodeParseError::new(
+                    &operand.to_string(),
+                    &format!("a value of at most {} bits", width),
+                ));
+            }
+        }
+        Ok(op_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_WIDTH;
+
+    fn parse(input: &str, width: u32) -> Result<Option<OpCode<Mask>>, OpCodeParseError> {
+        OpCode::parse_statement(input, width)
+    }
+
+    #[test]
+    fn test_opcode_parse_set_mask_statement() {
+        let opcode = parse("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX", DEFAULT_WIDTH).unwrap();
+        assert_eq!(
+            opcode,
+            Some(OpCode::SetMask(Mask {
+                one_mask: 0,
+                zero_mask: 0x0fffffffff
+            }))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_set_decoder_statement() {
+        let opcode =
+            OpCode::parse_statement("mask = 000000000000000000000000000000X0XX11", DEFAULT_WIDTH)
+                .unwrap();
+        assert_eq!(
+            opcode,
+            Some(OpCode::SetMask(Decoder {
+                one_mask: 0b11,
+                fluctuating_bits: vec![2, 3, 5],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_set_mem_statement() {
+        let opcode = parse("mem[42] = 23", DEFAULT_WIDTH).unwrap();
+        assert_eq!(opcode, Some(OpCode::SetMem(42, 23)));
+    }
+
+    #[test]
+    fn test_opcode_parse_add_mem_statement() {
+        let opcode = parse("add[42] = 23", DEFAULT_WIDTH).unwrap();
+        assert_eq!(opcode, Some(OpCode::AddMem(42, 23)));
+    }
+
+    #[test]
+    fn test_opcode_parse_clear_statement() {
+        let opcode = parse("clear", DEFAULT_WIDTH).unwrap();
+        assert_eq!(opcode, Some(OpCode::Clear));
+    }
+
+    #[test]
+    fn test_opcode_parse_blank_lines_and_comments() {
+        assert_eq!(parse("", DEFAULT_WIDTH).unwrap(), None);
+        assert_eq!(parse("  ", DEFAULT_WIDTH).unwrap(), None);
+        assert_eq!(parse("# comment", DEFAULT_WIDTH).unwrap(), None);
+        assert_eq!(
+            parse("  mem[42] = 23  # comment", DEFAULT_WIDTH).unwrap(),
+            Some(OpCode::SetMem(42, 23))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_invalid_keyword() {
+        assert_eq!(
+            parse("mov[42] = 23", DEFAULT_WIDTH),
+            Err(OpCodeParseError::new(
+                "mov[42] = 23",
+                "'mask', 'mem', 'add', or 'clear'"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_missing_assignment() {
+        assert_eq!(
+            parse("mem[42] 23", DEFAULT_WIDTH),
+            Err(OpCodeParseError::new("23", "'='"))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_invalid_value() {
+        assert_eq!(
+            parse("mem[42] = 0x23", DEFAULT_WIDTH),
+            Err(OpCodeParseError::new("0x23", "an unsigned integer"))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_mask_width() {
+        assert_eq!(
+            parse("mask = X1X", DEFAULT_WIDTH),
+            Err(OpCodeParseError::new("X1X", "mask of 0, 1, and X"))
+        );
+        assert!(parse("mask = X1X", 3).is_ok());
+        assert_eq!(
+            parse("mem[8] = 68719476736", DEFAULT_WIDTH),
+            Err(OpCodeParseError::new(
+                "68719476736",
+                "a value of at most 36 bits"
+            ))
+        );
+        assert_eq!(
+            parse("add[8] = 8", 3),
+            Err(OpCodeParseError::new("8", "a value of at most 3 bits"))
+        );
+        assert_eq!(
+            parse("mem[8] = 68719476736", 64).unwrap(),
+            Some(OpCode::SetMem(8, 1 << 36))
+        );
+    }
+
+    #[test]
+    fn test_opcode_parse_trailing_characters() {
+        assert_eq!(
+            parse("clear 42", DEFAULT_WIDTH),
+            Err(OpCodeParseError::new("42", "end of statement"))
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use graph::{hopcroft_karp, ByAddress, DirectedGraph, FrozenGraph};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    Rule(usize),
+    Field(usize),
+    Other(&'static str),
+}
+
+// Like a day16 rule, which is expensive to hash.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Rule {
+    field: String,
+    valid_ranges: Vec<(u64, u64)>,
+}
+
+fn rule(i: usize) -> Rule {
+    Rule {
+        field: format!("field {}", i),
+        valid_ranges: vec![(i as u64, 2 * i as u64), (3 * i as u64, 4 * i as u64)],
+    }
+}
+
+// Shaped like the day16 notes: rule i is valid for i + 1 fields, so there is
+// exactly one assignment and it can only be found by propagating constraints.
+fn staircase_candidates(n: usize) -> Vec<Vec<usize>> {
+    let field = |i: usize| (i * 7919) % n;
+    (0..n).map(|rule| (0..=rule).map(field).collect()).collect()
+}
+
+fn max_flow_matching(candidates: &[Vec<usize>]) -> usize {
+    let start = Rc::new(Node::Other("start"));
+    let end = Rc::new(Node::Other("end"));
+    let rules: Vec<Rc<Node>> = (0..candidates.len())
+        .map(|i| Rc::new(Node::Rule(i)))
+        .collect();
+    let fields: Vec<Rc<Node>> = (0..candidates.len())
+        .map(|i| Rc::new(Node::Field(i)))
+        .collect();
+    let mut graph = DirectedGraph::new();
+    for (rule, rule_candidates) in candidates.iter().enumerate() {
+        graph.add_edge(&start, &rules[rule]);
+        for &field in rule_candidates {
+            graph.add_edge(&rules[rule], &fields[field]);
+        }
+    }
+    for field in &fields {
+        graph.add_edge(field, &end);
+    }
+    graph.max_flow(&start, &end).adjancency[&start].len()
+}
+
+// The day16 assignment as flow graph with nodes of the given type. Every
+// node is created once, so that the graph is the same whether nodes are
+// compared by value or by address.
+fn rule_flow_graph<T: Debug + Eq + Hash>(
+    n: usize,
+    new_node: impl Fn(Rule) -> T,
+) -> (DirectedGraph<T>, Rc<T>, Rc<T>) {
+    let start = Rc::new(new_node(rule(2 * n)));
+    let end = Rc::new(new_node(rule(2 * n + 1)));
+    let fields: Vec<Rc<T>> = (n..2 * n).map(|i| Rc::new(new_node(rule(i)))).collect();
+    let mut graph = DirectedGraph::new();
+    for (i, candidates) in staircase_candidates(n).into_iter().enumerate() {
+        let rule_node = Rc::new(new_node(rule(i)));
+        graph.add_edge(&start, &rule_node);
+        for field in candidates {
+            graph.add_edge(&rule_node, &fields[field]);
+        }
+    }
+    for field in &fields {
+        graph.add_edge(field, &end);
+    }
+    (graph, start, end)
+}
+
+fn bench_node_hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rule_nodes");
+    let n = 50;
+    let (graph, start, end) = rule_flow_graph(n, |rule| rule);
+    group.bench_function(BenchmarkId::new("by_value", n), |b| {
+        b.iter(|| graph.max_flow(&start, &end))
+    });
+    let (graph, start, end) = rule_flow_graph(n, ByAddress::new);
+    group.bench_function(BenchmarkId::new("by_address", n), |b| {
+        b.iter(|| graph.max_flow(&start, &end))
+    });
+    let frozen = FrozenGraph::from(&graph);
+    group.bench_function(BenchmarkId::new("frozen", n), |b| {
+        b.iter(|| frozen.max_flow(&start, &end))
+    });
+    group.finish();
+}
+
+fn bench_bipartite_matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day16_assignment");
+    for &n in &[20, 100, 200] {
+        let candidates = staircase_candidates(n);
+        group.bench_with_input(BenchmarkId::new("max_flow", n), &candidates, |b, c| {
+            b.iter(|| max_flow_matching(c))
+        });
+        group.bench_with_input(BenchmarkId::new("hopcroft_karp", n), &candidates, |b, c| {
+            b.iter(|| hopcroft_karp(c, n))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_bipartite_matching, bench_node_hashing);
+criterion_main!(benches);
@@ -0,0 +1,374 @@
+use super::{Graph, GraphMut, IndexedGraph, Path};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Display, Formatter};
+
+type Neighbours<'a, N> = Box<dyn Iterator<Item = N> + 'a>;
+
+// Depth-first search for a path from start to target, visiting the neighbours
+// in the order given by the graph.
+pub fn dfs<G: Graph>(graph: &G, start: &G::Node, target: &G::Node) -> Option<Path<G::Node>> {
+    let mut visited = HashSet::new();
+    visited.insert(start.clone());
+    let mut stack: Vec<(G::Node, Neighbours<'_, G::Node>)> =
+        vec![(start.clone(), graph.neighbours(start))];
+
+    while let Some((current, neighbours)) = stack.last_mut() {
+        if current == target {
+            return Some(stack.into_iter().map(|(node, _)| node).collect());
+        }
+        match neighbours.find(|node| !visited.contains(node)) {
+            Some(next) => {
+                visited.insert(next.clone());
+                let neighbours = graph.neighbours(&next);
+                stack.push((next, neighbours));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    None
+}
+
+// Breadth-first search for a path from start to target with the least number
+// of edges. Among several such paths, the one found first in the order of the
+// neighbours given by the graph is returned.
+pub fn bfs<G: Graph>(graph: &G, start: &G::Node, target: &G::Node) -> Option<Path<G::Node>> {
+    let mut predecessors: HashMap<G::Node, Option<G::Node>> = HashMap::new();
+    predecessors.insert(start.clone(), None);
+    let mut queue = VecDeque::new();
+    queue.push_back(start.clone());
+
+    while let Some(current) = queue.pop_front() {
+        if &current == target {
+            let mut path = vec![current];
+            while let Some(Some(predecessor)) = path.last().map(|node| &predecessors[node]) {
+                path.push(predecessor.clone());
+            }
+            path.reverse();
+            return Some(Path::from(path));
+        }
+        for next in graph.neighbours(&current) {
+            if !predecessors.contains_key(&next) {
+                predecessors.insert(next.clone(), Some(current.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// The nodes that could not be sorted topologically, because they are on a
+// cycle or only reachable through one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CycleError<N> {
+    pub remaining: Vec<N>,
+}
+
+impl<N: Debug> Display for CycleError<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "graph has a cycle, cannot sort the nodes {:?}",
+            self.remaining
+        )
+    }
+}
+
+impl<N: Debug> std::error::Error for CycleError<N> {}
+
+// Orders the nodes so that every edge points forward, using Kahn's algorithm.
+// Nodes without remaining incoming edges are taken in the order given by the
+// graph. Graphs with a cycle have no such order.
+pub fn topological_sort<G: Graph>(graph: &G) -> Result<Vec<G::Node>, CycleError<G::Node>> {
+    let nodes: Vec<G::Node> = graph.nodes().collect();
+    let mut in_degree: HashMap<G::Node, usize> =
+        nodes.iter().map(|node| (node.clone(), 0)).collect();
+    for node in &nodes {
+        for neighbour in graph.neighbours(node) {
+            *in_degree.entry(neighbour).or_insert(0) += 1;
+        }
+    }
+
+    let mut ready: VecDeque<G::Node> = nodes
+        .iter()
+        .filter(|&node| in_degree[node] == 0)
+        .cloned()
+        .collect();
+    let mut sorted = Vec::with_capacity(nodes.len());
+    while let Some(node) = ready.pop_front() {
+        for neighbour in graph.neighbours(&node) {
+            let degree = in_degree.get_mut(&neighbour).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push_back(neighbour);
+            }
+        }
+        sorted.push(node);
+    }
+
+    if sorted.len() == nodes.len() {
+        Ok(sorted)
+    } else {
+        Err(CycleError {
+            remaining: nodes
+                .into_iter()
+                .filter(|node| in_degree[node] > 0)
+                .collect(),
+        })
+    }
+}
+
+// Groups the nodes connected when ignoring the direction of the edges. The
+// components and the nodes within them are in the order given by the graph,
+// ordering the components by their first node.
+pub fn weakly_connected_components<G: Graph>(graph: &G) -> Vec<Vec<G::Node>> {
+    let nodes: Vec<G::Node> = graph.nodes().collect();
+    let index: HashMap<&G::Node, usize> = nodes.iter().enumerate().map(|(i, n)| (n, i)).collect();
+
+    let mut parents: Vec<usize> = (0..nodes.len()).collect();
+    for (i, node) in nodes.iter().enumerate() {
+        for neighbour in graph.neighbours(node) {
+            let (a, b) = (find(&mut parents, i), find(&mut parents, index[&neighbour]));
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut components: Vec<Vec<G::Node>> = vec![];
+    let mut component_of_root = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let root = find(&mut parents, i);
+        let component = *component_of_root.entry(root).or_insert_with(|| {
+            components.push(vec![]);
+            components.len() - 1
+        });
+        components[component].push(node.clone());
+    }
+    components
+}
+
+// Union-find with each node pointing towards the representative of its set.
+// Returns the representative and shortens the path to it on the way.
+fn find(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+// The state after running the Ford-Fulkerson algorithm with unit capacities
+// on the nodes of a graph, referred to by their index in `nodes`.
+struct UnitFlow<N> {
+    nodes: Vec<N>,
+    start: usize,
+    residual: IndexedGraph,
+    flow: BTreeSet<(usize, usize)>,
+}
+
+fn unit_flow<G: Graph>(graph: &G, start: &G::Node, end: &G::Node) -> Option<UnitFlow<G::Node>> {
+    let nodes: Vec<G::Node> = graph.nodes().collect();
+    let index: HashMap<&G::Node, usize> = nodes.iter().enumerate().map(|(i, n)| (n, i)).collect();
+    let (start, end) = (*index.get(start)?, *index.get(end)?);
+
+    let mut residual = IndexedGraph::new(nodes.len());
+    for (i, node) in nodes.iter().enumerate() {
+        for neighbour in graph.neighbours(node) {
+            residual.add_edge(&i, &index[&neighbour]);
+        }
+    }
+    let mut flow = BTreeSet::new();
+    while let Some(path) = find_augmenting_path(&residual, start, end) {
+        for (&from, &to) in path.edges() {
+            residual.remove_edge(&from, &to);
+            residual.add_edge(&to, &from);
+            if !flow.remove(&(to, from)) {
+                flow.insert((from, to));
+            }
+        }
+    }
+    Some(UnitFlow {
+        nodes,
+        start,
+        residual,
+        flow,
+    })
+}
+
+// Using Ford-Fulkerson algorithm with unit capacities. Returns the edges
+// carrying flow in the order of the nodes of the graph, so that the result is
+// deterministic if the graph iterates its nodes and neighbours in a
+// deterministic order.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn max_flow<G: Graph>(graph: &G, start: &G::Node, end: &G::Node) -> Vec<(G::Node, G::Node)> {
+    let UnitFlow { nodes, flow, .. } = match unit_flow(graph, start, end) {
+        Some(unit_flow) => unit_flow,
+        None => return vec![],
+    };
+    flow.into_iter()
+        .map(|(from, to)| (nodes[from].clone(), nodes[to].clone()))
+        .collect()
+}
+
+// A minimum set of edges whose removal disconnects end from start, with unit
+// capacities. These are the saturated edges leaving the nodes that can still
+// be reached from start in the residual graph of a maximum flow. The edges are
+// returned in the same order as by max_flow.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn min_cut<G: Graph>(graph: &G, start: &G::Node, end: &G::Node) -> Vec<(G::Node, G::Node)> {
+    let UnitFlow {
+        nodes,
+        start,
+        residual,
+        flow,
+    } = match unit_flow(graph, start, end) {
+        Some(unit_flow) => unit_flow,
+        None => return vec![],
+    };
+    let source_side = reachable(&residual, &start);
+    flow.into_iter()
+        .filter(|(from, to)| source_side.contains(from) && !source_side.contains(to))
+        .map(|(from, to)| (nodes[from].clone(), nodes[to].clone()))
+        .collect()
+}
+
+fn reachable<G: Graph>(graph: &G, start: &G::Node) -> HashSet<G::Node> {
+    let mut visited = HashSet::new();
+    visited.insert(start.clone());
+    let mut stack = vec![start.clone()];
+    while let Some(current) = stack.pop() {
+        for next in graph.neighbours(&current) {
+            if visited.insert(next.clone()) {
+                stack.push(next);
+            }
+        }
+    }
+    visited
+}
+
+// A function of its own, so that each search gets a span recording the length
+// of the path found.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "augmenting_path", skip_all, fields(length))
+)]
+fn find_augmenting_path(residual: &IndexedGraph, start: usize, end: usize) -> Option<Path<usize>> {
+    let path = dfs(residual, &start, &end);
+    #[cfg(feature = "tracing")]
+    if let Some(path) = &path {
+        tracing::Span::current().record("length", path.len() - 1);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortedNeighbours;
+
+    fn example() -> IndexedGraph {
+        let mut graph = IndexedGraph::new(6);
+        for &(from, to) in &[(0, 2), (0, 1), (1, 3), (1, 4), (2, 3), (3, 5), (4, 5)] {
+            graph.add_edge(&from, &to);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_dfs() {
+        let graph = example();
+        assert_eq!(dfs(&graph, &0, &5), Some(Path::from(vec![0, 2, 3, 5])));
+        assert_eq!(
+            dfs(&SortedNeighbours(&graph), &0, &5),
+            Some(Path::from(vec![0, 1, 3, 5]))
+        );
+        assert_eq!(dfs(&graph, &0, &0), Some(Path::from(vec![0])));
+        assert_eq!(dfs(&graph, &5, &0), None);
+    }
+
+    #[test]
+    fn test_bfs() {
+        let graph = example();
+        assert_eq!(bfs(&graph, &0, &5), Some(Path::from(vec![0, 2, 3, 5])));
+        assert_eq!(
+            bfs(&SortedNeighbours(&graph), &0, &5),
+            Some(Path::from(vec![0, 1, 3, 5]))
+        );
+        assert_eq!(bfs(&graph, &0, &0), Some(Path::from(vec![0])));
+        assert_eq!(bfs(&graph, &5, &0), None);
+    }
+
+    #[test]
+    fn test_bfs_finds_fewest_edges() {
+        let mut graph = IndexedGraph::new(5);
+        for &(from, to) in &[(0, 1), (1, 2), (2, 3), (3, 4), (0, 3)] {
+            graph.add_edge(&from, &to);
+        }
+        assert_eq!(dfs(&graph, &0, &4), Some(Path::from(vec![0, 1, 2, 3, 4])));
+        assert_eq!(bfs(&graph, &0, &4), Some(Path::from(vec![0, 3, 4])));
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        assert_eq!(topological_sort(&example()), Ok(vec![0, 2, 1, 3, 4, 5]));
+        assert_eq!(
+            topological_sort(&SortedNeighbours(&example())),
+            Ok(vec![0, 1, 2, 4, 3, 5])
+        );
+        assert_eq!(topological_sort(&IndexedGraph::new(0)), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_topological_sort_with_cycle() {
+        let mut graph = example();
+        graph.add_edge(&3, &1);
+        assert_eq!(
+            topological_sort(&graph),
+            Err(CycleError {
+                remaining: vec![1, 3, 4, 5]
+            })
+        );
+        let mut graph = IndexedGraph::new(2);
+        graph.add_edge(&1, &1);
+        assert_eq!(
+            topological_sort(&graph),
+            Err(CycleError { remaining: vec![1] })
+        );
+    }
+
+    #[test]
+    fn test_weakly_connected_components() {
+        let mut graph = IndexedGraph::new(7);
+        for &(from, to) in &[(0, 3), (4, 3), (1, 5), (6, 5), (2, 2)] {
+            graph.add_edge(&from, &to);
+        }
+        assert_eq!(
+            weakly_connected_components(&graph),
+            vec![vec![0, 3, 4], vec![1, 5, 6], vec![2]]
+        );
+        assert_eq!(weakly_connected_components(&example()).len(), 1);
+        assert!(weakly_connected_components(&IndexedGraph::new(0)).is_empty());
+    }
+
+    #[test]
+    fn test_max_flow() {
+        assert_eq!(
+            max_flow(&example(), &0, &5),
+            vec![(0, 1), (0, 2), (1, 4), (2, 3), (3, 5), (4, 5)]
+        );
+        assert_eq!(max_flow(&example(), &0, &7), vec![]);
+    }
+
+    #[test]
+    fn test_min_cut() {
+        assert_eq!(min_cut(&example(), &0, &5), vec![(0, 1), (0, 2)]);
+        assert_eq!(min_cut(&example(), &5, &0), vec![]);
+        assert_eq!(min_cut(&example(), &0, &7), vec![]);
+
+        let mut graph = example();
+        for (from, to) in min_cut(&example(), &0, &5) {
+            graph.remove_edge(&from, &to);
+        }
+        assert_eq!(dfs(&graph, &0, &5), None);
+    }
+}
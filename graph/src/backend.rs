@@ -0,0 +1,40 @@
+use itertools::Itertools;
+use std::hash::Hash;
+
+// Interface shared by the graph storages, so that the algorithms only need to
+// be written once. Nodes are handed out by value and should be cheap to clone,
+// like an Rc or an integer id.
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    fn nodes(&self) -> Box<dyn Iterator<Item = Self::Node> + '_>;
+    fn neighbours(&self, node: &Self::Node) -> Box<dyn Iterator<Item = Self::Node> + '_>;
+}
+
+// Graphs that can be changed after construction. The frozen graph is not one
+// of them.
+pub trait GraphMut: Graph {
+    fn add_edge(&mut self, from: &Self::Node, to: &Self::Node);
+    fn remove_edge(&mut self, from: &Self::Node, to: &Self::Node);
+}
+
+// Presents the nodes and neighbours of a graph in ascending order. Backends
+// iterating hash sets return the nodes in arbitrary order, so that algorithms
+// may produce a different (equally valid) result on each run. Running them on
+// this view makes the result deterministic at the cost of sorting.
+pub struct SortedNeighbours<'a, G>(pub &'a G);
+
+impl<'a, G: Graph> Graph for SortedNeighbours<'a, G>
+where
+    G::Node: Ord,
+{
+    type Node = G::Node;
+
+    fn nodes(&self) -> Box<dyn Iterator<Item = Self::Node> + '_> {
+        Box::new(self.0.nodes().sorted())
+    }
+
+    fn neighbours(&self, node: &Self::Node) -> Box<dyn Iterator<Item = Self::Node> + '_> {
+        Box::new(self.0.neighbours(node).sorted())
+    }
+}
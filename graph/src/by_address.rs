@@ -0,0 +1,95 @@
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::ptr;
+use std::rc::Rc;
+
+// Compares and hashes a node by the address of its Rc instead of its value.
+// Graph operations hash their nodes all the time, which gets expensive for
+// large node values. With this wrapper only a pointer is hashed. Wrappers of
+// the same Rc are equal, but equal values in different Rcs become distinct
+// nodes, so every value has to be put into an Rc exactly once.
+#[derive(Debug)]
+pub struct ByAddress<T>(pub Rc<T>);
+
+impl<T> ByAddress<T> {
+    pub fn new(value: T) -> Self {
+        Self(Rc::new(value))
+    }
+}
+
+impl<T> Clone for ByAddress<T> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<T> PartialEq for ByAddress<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Eq for ByAddress<T> {}
+
+impl<T> Hash for ByAddress<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(Rc::as_ptr(&self.0), state);
+    }
+}
+
+impl<T> Deref for ByAddress<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DirectedGraph;
+
+    #[test]
+    fn test_compares_by_address() {
+        let a = ByAddress::new(String::from("node"));
+        let b = ByAddress::new(String::from("node"));
+        assert_eq!(a, a.clone());
+        assert_eq!(a, ByAddress(Rc::clone(&a.0)));
+        assert_ne!(a, b);
+        assert_eq!(*a, *b);
+    }
+
+    #[test]
+    fn test_graph_with_nodes_by_address() {
+        let nodes: Vec<Rc<ByAddress<Vec<u32>>>> = (0..3)
+            .map(|_| Rc::new(ByAddress::new(vec![1, 2, 3])))
+            .collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[1], &nodes[2]);
+        assert_eq!(graph.adjancency.len(), 2);
+        assert_eq!(
+            graph.dfs(&nodes[0], &nodes[2]).map(|path| path.len()),
+            Some(3)
+        );
+        assert_eq!(graph.dfs(&nodes[2], &nodes[0]), None);
+    }
+
+    #[test]
+    fn test_lookup_with_separate_wrapper() {
+        let values: Vec<Rc<String>> = (0..2).map(|i| Rc::new(i.to_string())).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(
+            &Rc::new(ByAddress(Rc::clone(&values[0]))),
+            &Rc::new(ByAddress(Rc::clone(&values[1]))),
+        );
+        let start = Rc::new(ByAddress(Rc::clone(&values[0])));
+        let end = Rc::new(ByAddress(Rc::clone(&values[1])));
+        assert!(graph.adjancency.contains_key(&start));
+        assert_eq!(graph.dfs(&start, &end).map(|path| path.len()), Some(2));
+        assert!(!graph
+            .adjancency
+            .contains_key(&Rc::new(ByAddress::new(String::from("0")))));
+    }
+}
@@ -0,0 +1,209 @@
+use super::{bfs, Graph, GraphMut, IndexedGraph};
+use std::collections::{BTreeMap, HashMap};
+
+// A flow from a start to an end node: its total value and the amount on each
+// edge carrying any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flow<N> {
+    pub value: u64,
+    pub edges: Vec<(N, N, u64)>,
+}
+
+impl<N> Default for Flow<N> {
+    fn default() -> Self {
+        Self {
+            value: 0,
+            edges: vec![],
+        }
+    }
+}
+
+// Residual network on the nodes 0..n. Every edge has its reverse edge, but
+// only edges with capacity left are neighbours.
+struct ResidualNetwork {
+    edges: IndexedGraph,
+    capacities: HashMap<(usize, usize), u64>,
+}
+
+impl ResidualNetwork {
+    fn new(n_nodes: usize) -> Self {
+        Self {
+            edges: IndexedGraph::new(n_nodes),
+            capacities: HashMap::new(),
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, capacity: u64) {
+        self.edges.add_edge(&from, &to);
+        self.edges.add_edge(&to, &from);
+        *self.capacities.entry((from, to)).or_insert(0) += capacity;
+        self.capacities.entry((to, from)).or_insert(0);
+    }
+
+    // Pushes as much flow as possible along a shortest path with capacity
+    // left and returns the amount pushed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "augmenting_path", skip_all, fields(length, amount))
+    )]
+    fn augment(&mut self, start: usize, end: usize) -> Option<u64> {
+        let path = bfs(self, &start, &end)?;
+        let amount = path
+            .edges()
+            .map(|(from, to)| self.capacities[&(*from, *to)])
+            .min()?;
+        for (&from, &to) in path.edges() {
+            *self.capacities.get_mut(&(from, to)).unwrap() -= amount;
+            *self.capacities.get_mut(&(to, from)).unwrap() += amount;
+        }
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            span.record("length", path.len() - 1);
+            span.record("amount", amount);
+        }
+        Some(amount)
+    }
+}
+
+impl Graph for ResidualNetwork {
+    type Node = usize;
+
+    fn nodes(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        self.edges.nodes()
+    }
+
+    fn neighbours(&self, &node: &usize) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(
+            self.edges
+                .neighbours(&node)
+                .filter(move |&neighbour| self.capacities[&(node, neighbour)] > 0),
+        )
+    }
+}
+
+// Using the Edmonds-Karp algorithm, i.e. Ford-Fulkerson augmenting along
+// shortest paths, so that the number of augmentations does not depend on the
+// capacities. The capacity of each edge of the graph is given by the
+// `capacity` function. The edges carrying flow are returned in the order of
+// the nodes of the graph. If there are edges in both directions between two
+// nodes, only the net flow is returned.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn max_flow_with_capacities<G: Graph>(
+    graph: &G,
+    start: &G::Node,
+    end: &G::Node,
+    capacity: impl Fn(&G::Node, &G::Node) -> u64,
+) -> Flow<G::Node> {
+    let nodes: Vec<G::Node> = graph.nodes().collect();
+    let index: HashMap<&G::Node, usize> = nodes.iter().enumerate().map(|(i, n)| (n, i)).collect();
+    let (start, end) = match (index.get(start), index.get(end)) {
+        (Some(&start), Some(&end)) => (start, end),
+        _ => return Flow::default(),
+    };
+
+    let mut residual = ResidualNetwork::new(nodes.len());
+    let mut capacities = BTreeMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        for neighbour in graph.neighbours(node) {
+            let edge_capacity = capacity(node, &neighbour);
+            residual.add_edge(i, index[&neighbour], edge_capacity);
+            capacities.insert((i, index[&neighbour]), edge_capacity);
+        }
+    }
+    let mut value = 0;
+    while let Some(amount) = residual.augment(start, end) {
+        value += amount;
+    }
+
+    let edges = capacities
+        .into_iter()
+        .filter_map(|((from, to), edge_capacity)| {
+            let amount = edge_capacity.saturating_sub(residual.capacities[&(from, to)]);
+            if amount > 0 {
+                Some((nodes[from].clone(), nodes[to].clone(), amount))
+            } else {
+                None
+            }
+        })
+        .collect();
+    Flow { value, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::max_flow;
+
+    fn example() -> (IndexedGraph, HashMap<(usize, usize), u64>) {
+        let capacities: HashMap<(usize, usize), u64> = [
+            ((0, 1), 16),
+            ((0, 2), 13),
+            ((1, 3), 12),
+            ((2, 1), 4),
+            ((2, 4), 14),
+            ((3, 2), 9),
+            ((3, 5), 20),
+            ((4, 3), 7),
+            ((4, 5), 4),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        let mut graph = IndexedGraph::new(6);
+        for &(from, to) in capacities.keys() {
+            graph.add_edge(&from, &to);
+        }
+        (graph, capacities)
+    }
+
+    #[test]
+    fn test_max_flow_with_capacities() {
+        let (graph, capacities) = example();
+        let flow = max_flow_with_capacities(&graph, &0, &5, |from, to| capacities[&(*from, *to)]);
+        assert_eq!(flow.value, 23);
+
+        let mut balance = vec![0i64; 6];
+        for &(from, to, amount) in &flow.edges {
+            assert!(amount <= capacities[&(from, to)]);
+            balance[from] -= amount as i64;
+            balance[to] += amount as i64;
+        }
+        assert_eq!(balance, vec![-23, 0, 0, 0, 0, 23]);
+    }
+
+    #[test]
+    fn test_unit_capacities_match_max_flow() {
+        let mut graph = IndexedGraph::new(6);
+        for &(from, to) in &[(0, 2), (0, 1), (1, 3), (1, 4), (2, 3), (3, 5), (4, 5)] {
+            graph.add_edge(&from, &to);
+        }
+        let flow = max_flow_with_capacities(&graph, &0, &5, |_, _| 1);
+        assert_eq!(flow.value, 2);
+        assert_eq!(
+            flow.edges
+                .into_iter()
+                .map(|(from, to, _)| (from, to))
+                .collect::<Vec<_>>(),
+            max_flow(&graph, &0, &5)
+        );
+    }
+
+    #[test]
+    fn test_no_flow() {
+        let (graph, capacities) = example();
+        let capacity = |from: &usize, to: &usize| capacities[&(*from, *to)];
+        assert_eq!(
+            max_flow_with_capacities(&graph, &5, &0, capacity),
+            Flow::default()
+        );
+        assert_eq!(
+            max_flow_with_capacities(&graph, &0, &0, capacity),
+            Flow::default()
+        );
+        assert_eq!(
+            max_flow_with_capacities(&graph, &0, &7, capacity),
+            Flow::default()
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use super::{bfs, dfs, max_flow, DirectedGraph, Graph, Path};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+
+// Collects nodes and edges to build a FrozenGraph. Nodes are interned on
+// insertion, so that the frozen graph only deals with integer ids.
+#[derive(Debug)]
+pub struct GraphBuilder<T: Eq + Hash> {
+    ids: HashMap<Rc<T>, usize>,
+    nodes: Vec<Rc<T>>,
+    edges: Vec<(usize, usize)>,
+}
+
+// An immutable graph storing the edges in compressed sparse row layout: the
+// neighbours of node i are targets[offsets[i]..offsets[i + 1]]. Algorithms
+// keep their working state separate instead of mutating a clone of the graph.
+// As Graph, the nodes are the ids. These follow the insertion order and
+// neighbours are visited in ascending id order, so the results are
+// deterministic as long as the nodes are added in a deterministic order.
+#[derive(Debug)]
+pub struct FrozenGraph<T: Eq + Hash> {
+    ids: HashMap<Rc<T>, usize>,
+    nodes: Vec<Rc<T>>,
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+}
+
+impl<T: Eq + Hash> Default for GraphBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash> GraphBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            nodes: vec![],
+            edges: vec![],
+        }
+    }
+
+    // Returns the id of the node, adding it if necessary.
+    pub fn add_node(&mut self, node: &Rc<T>) -> usize {
+        if let Some(&id) = self.ids.get(node) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.ids.insert(Rc::clone(node), id);
+        self.nodes.push(Rc::clone(node));
+        id
+    }
+
+    pub fn add_edge(&mut self, from: &Rc<T>, to: &Rc<T>) {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        self.edges.push((from, to));
+    }
+
+    pub fn freeze(self) -> FrozenGraph<T> {
+        let mut edges = self.edges;
+        edges.sort_unstable();
+        edges.dedup();
+        let mut offsets = vec![0; self.nodes.len() + 1];
+        for &(from, _) in &edges {
+            offsets[from + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        FrozenGraph {
+            ids: self.ids,
+            nodes: self.nodes,
+            offsets,
+            targets: edges.into_iter().map(|(_, to)| to).collect(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> From<&DirectedGraph<T>> for FrozenGraph<T> {
+    fn from(graph: &DirectedGraph<T>) -> Self {
+        let mut builder = GraphBuilder::new();
+        for (from, edges) in &graph.adjancency {
+            builder.add_node(from);
+            for to in edges {
+                builder.add_edge(from, to);
+            }
+        }
+        builder.freeze()
+    }
+}
+
+impl<T: Debug + Eq + Hash> FrozenGraph<T> {
+    pub fn n_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn id(&self, node: &T) -> Option<usize> {
+        self.ids.get(node).copied()
+    }
+
+    pub fn node(&self, id: usize) -> &Rc<T> {
+        &self.nodes[id]
+    }
+
+    // Ids of the neighbours in ascending order.
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        &self.targets[self.offsets[id]..self.offsets[id + 1]]
+    }
+
+    pub fn dfs(&self, start: &T, target: &T) -> Option<Path<Rc<T>>> {
+        let path = dfs(self, &self.id(start)?, &self.id(target)?)?;
+        Some(
+            path.into_iter()
+                .map(|id| Rc::clone(&self.nodes[id]))
+                .collect(),
+        )
+    }
+
+    pub fn bfs(&self, start: &T, target: &T) -> Option<Path<Rc<T>>> {
+        let path = bfs(self, &self.id(start)?, &self.id(target)?)?;
+        Some(
+            path.into_iter()
+                .map(|id| Rc::clone(&self.nodes[id]))
+                .collect(),
+        )
+    }
+
+    pub fn max_flow(&self, start: &T, end: &T) -> DirectedGraph<T> {
+        let mut flow = DirectedGraph::with_capacity(self.n_nodes(), 1);
+        if let (Some(start), Some(end)) = (self.id(start), self.id(end)) {
+            for (from, to) in max_flow(self, &start, &end) {
+                flow.add_edge(&self.nodes[from], &self.nodes[to]);
+            }
+        }
+        flow
+    }
+}
+
+impl<T: Eq + Hash> Graph for FrozenGraph<T> {
+    type Node = usize;
+
+    fn nodes(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(0..self.nodes.len())
+    }
+
+    fn neighbours(&self, &id: &usize) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(
+            self.targets[self.offsets[id]..self.offsets[id + 1]]
+                .iter()
+                .copied(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> (Vec<Rc<u32>>, GraphBuilder<u32>) {
+        let nodes: Vec<Rc<u32>> = (0..6).map(Rc::new).collect();
+        let mut builder = GraphBuilder::new();
+        for &(from, to) in &[(0, 1), (0, 2), (1, 3), (1, 4), (2, 3), (3, 5), (4, 5)] {
+            builder.add_edge(&nodes[from], &nodes[to]);
+        }
+        (nodes, builder)
+    }
+
+    #[test]
+    fn test_freeze() {
+        let (nodes, mut builder) = example();
+        builder.add_edge(&nodes[0], &nodes[1]);
+        let graph = builder.freeze();
+        assert_eq!(graph.n_nodes(), 6);
+        assert_eq!(graph.n_edges(), 7);
+        let id = |n: u32| graph.id(&n).unwrap();
+        let neighbours: Vec<u32> = graph
+            .neighbours(id(1))
+            .iter()
+            .map(|&n| **graph.node(n))
+            .collect();
+        assert_eq!(neighbours, vec![3, 4]);
+        assert!(graph.neighbours(id(5)).is_empty());
+        assert_eq!(graph.id(&7), None);
+    }
+
+    #[test]
+    fn test_dfs() {
+        let (_, builder) = example();
+        let graph = builder.freeze();
+        let path = graph.dfs(&0, &5).unwrap();
+        assert_eq!(path.start().map(|n| **n), Some(0));
+        assert_eq!(path.end().map(|n| **n), Some(5));
+        assert_eq!(path.edges().count(), path.len() - 1);
+        assert_eq!(graph.dfs(&5, &0), None);
+    }
+
+    #[test]
+    fn test_bfs() {
+        let (_, builder) = example();
+        let graph = builder.freeze();
+        let path: Vec<u32> = graph.bfs(&0, &5).unwrap().into_iter().map(|n| *n).collect();
+        assert_eq!(path, vec![0, 1, 3, 5]);
+        assert_eq!(graph.bfs(&5, &0), None);
+    }
+
+    #[test]
+    fn test_max_flow_matches_directed_graph() {
+        let (nodes, builder) = example();
+        let graph = builder.freeze();
+        let mut directed = DirectedGraph::new();
+        for id in 0..graph.n_nodes() {
+            for &n in graph.neighbours(id) {
+                directed.add_edge(graph.node(id), graph.node(n));
+            }
+        }
+        let flow = graph.max_flow(&0, &5);
+        assert_eq!(flow, directed.max_flow(&nodes[0], &nodes[5]));
+        assert_eq!(FrozenGraph::from(&directed).max_flow(&0, &5), flow);
+    }
+}
@@ -0,0 +1,84 @@
+use super::{Graph, GraphMut};
+
+// A graph on the nodes 0..n_nodes with the neighbours of each node in a
+// vector, in the order the edges were added. This is the representation
+// hopcroft_karp takes and what the algorithms use for their working state.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexedGraph {
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl IndexedGraph {
+    pub fn new(n_nodes: usize) -> Self {
+        Self {
+            adjacency: vec![vec![]; n_nodes],
+        }
+    }
+
+    pub fn n_nodes(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn adjacency(&self) -> &[Vec<usize>] {
+        &self.adjacency
+    }
+}
+
+impl From<Vec<Vec<usize>>> for IndexedGraph {
+    fn from(adjacency: Vec<Vec<usize>>) -> Self {
+        Self { adjacency }
+    }
+}
+
+impl Graph for IndexedGraph {
+    type Node = usize;
+
+    fn nodes(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(0..self.n_nodes())
+    }
+
+    fn neighbours(&self, node: &usize) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(self.adjacency[*node].iter().copied())
+    }
+}
+
+// Edges to nodes outside of the graph add the missing nodes.
+impl GraphMut for IndexedGraph {
+    fn add_edge(&mut self, &from: &usize, &to: &usize) {
+        let n_nodes = from.max(to) + 1;
+        if self.adjacency.len() < n_nodes {
+            self.adjacency.resize(n_nodes, vec![]);
+        }
+        if !self.adjacency[from].contains(&to) {
+            self.adjacency[from].push(to);
+        }
+    }
+
+    fn remove_edge(&mut self, &from: &usize, &to: &usize) {
+        if let Some(edges) = self.adjacency.get_mut(from) {
+            edges.retain(|&n| n != to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edges() {
+        let mut graph = IndexedGraph::new(2);
+        graph.add_edge(&0, &1);
+        graph.add_edge(&0, &3);
+        graph.add_edge(&0, &1);
+        assert_eq!(graph.n_nodes(), 4);
+        assert_eq!(graph.adjacency(), &[vec![1, 3], vec![], vec![], vec![]]);
+        graph.remove_edge(&0, &1);
+        graph.remove_edge(&5, &1);
+        assert_eq!(graph.neighbours(&0).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(
+            graph,
+            IndexedGraph::from(vec![vec![3], vec![], vec![], vec![]])
+        );
+    }
+}
@@ -1,28 +1,91 @@
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 use std::rc::Rc;
 
-type Path<T> = Vec<Rc<T>>;
+mod algorithms;
+mod backend;
+mod by_address;
+mod flow;
+mod frozen;
+mod indexed;
+mod matching;
+mod path;
+mod undirected;
 
-#[derive(Debug, Clone, PartialEq)]
+pub use algorithms::{
+    bfs, dfs, max_flow, min_cut, topological_sort, weakly_connected_components, CycleError,
+};
+pub use backend::{Graph, GraphMut, SortedNeighbours};
+pub use by_address::ByAddress;
+pub use flow::{max_flow_with_capacities, Flow};
+pub use frozen::{FrozenGraph, GraphBuilder};
+pub use indexed::IndexedGraph;
+pub use matching::{bipartite_matching, hopcroft_karp};
+pub use path::Path;
+pub use undirected::UndirectedGraph;
+
+#[derive(Debug)]
 pub struct DirectedGraph<T: Eq + Hash> {
     pub adjancency: HashMap<Rc<T>, HashSet<Rc<T>>>,
+    // Initial capacity of the edge set of a newly added node.
+    degree_hint: usize,
+}
+
+// Only the Rcs are cloned, so the nodes do not need to implement Clone.
+impl<T: Eq + Hash> Clone for DirectedGraph<T> {
+    fn clone(&self) -> Self {
+        Self {
+            adjancency: self.adjancency.clone(),
+            degree_hint: self.degree_hint,
+        }
+    }
+}
+
+impl<T: Eq + Hash> PartialEq for DirectedGraph<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.adjancency == other.adjancency
+    }
+}
+
+impl<T: Debug + Eq + Hash> Default for DirectedGraph<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Debug + Eq + Hash> DirectedGraph<T> {
     pub fn new() -> Self {
+        Self::with_capacity(0, 0)
+    }
+
+    // Preallocates for n_nodes nodes with outgoing edges, each with about
+    // avg_degree edges, to avoid rehashing while building large graphs.
+    pub fn with_capacity(n_nodes: usize, avg_degree: usize) -> Self {
         Self {
-            adjancency: HashMap::new(),
+            adjancency: HashMap::with_capacity(n_nodes),
+            degree_hint: avg_degree,
+        }
+    }
+
+    pub fn reserve(&mut self, additional_nodes: usize) {
+        self.adjancency.reserve(additional_nodes);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.adjancency.shrink_to_fit();
+        for edges in self.adjancency.values_mut() {
+            edges.shrink_to_fit();
         }
     }
 
     pub fn add_edge(&mut self, from: &Rc<T>, to: &Rc<T>) {
+        let degree_hint = self.degree_hint;
         let entry = self
             .adjancency
             .entry(Rc::clone(from))
-            .or_insert(HashSet::new());
+            .or_insert_with(|| HashSet::with_capacity(degree_hint));
         entry.insert(Rc::clone(to));
     }
 
@@ -32,65 +95,136 @@ impl<T: Debug + Eq + Hash> DirectedGraph<T> {
         }
     }
 
-    pub fn dfs(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<T>> {
-        let mut visited = HashSet::with_capacity(self.adjancency.len());
-        let mut stack = vec![(start, 0)];
+    pub fn dfs(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<Rc<T>>> {
+        dfs(self, start, target)
+    }
 
-        while let Some((current_vertex, next_neighbour)) = stack.pop() {
-            visited.insert(current_vertex);
+    // A path with the least number of edges.
+    pub fn bfs(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<Rc<T>>> {
+        bfs(self, start, target)
+    }
 
-            if current_vertex == target {
-                stack.push((current_vertex, next_neighbour));
-                return Some(stack.iter().map(|&(v, _)| Rc::clone(v)).collect());
-            }
+    pub fn max_flow(&self, start: &Rc<T>, end: &Rc<T>) -> Self {
+        Self::from_flow(max_flow(self, start, end))
+    }
 
-            let next_vertex = self
-                .adjancency
-                .get(current_vertex)
-                .map(|edges| {
-                    edges
-                        .iter()
-                        .enumerate()
-                        .skip(next_neighbour)
-                        .filter(|(_, v)| !visited.contains(v))
-                        .next()
-                })
-                .flatten();
-            if let Some((i, vertex)) = next_vertex {
-                stack.push((current_vertex, i + 1));
-                stack.push((vertex, 0));
-            }
+    pub fn topological_sort(&self) -> Result<Vec<Rc<T>>, CycleError<Rc<T>>> {
+        topological_sort(self)
+    }
+
+    pub fn weakly_connected_components(&self) -> Vec<Vec<Rc<T>>> {
+        weakly_connected_components(self)
+    }
+
+    pub fn min_cut(&self, start: &Rc<T>, end: &Rc<T>) -> HashSet<(Rc<T>, Rc<T>)> {
+        min_cut(self, start, end).into_iter().collect()
+    }
+
+    // Edges without an entry in the capacities have unit capacity, like in
+    // max_flow.
+    pub fn max_flow_with_capacities(
+        &self,
+        start: &Rc<T>,
+        end: &Rc<T>,
+        capacities: &HashMap<(Rc<T>, Rc<T>), u64>,
+    ) -> Flow<Rc<T>> {
+        max_flow_with_capacities(self, start, end, |from, to| {
+            capacities
+                .get(&(Rc::clone(from), Rc::clone(to)))
+                .copied()
+                .unwrap_or(1)
+        })
+    }
+
+    fn from_flow(flow: Vec<(Rc<T>, Rc<T>)>) -> Self {
+        let mut graph = Self::with_capacity(flow.len(), 1);
+        for (from, to) in &flow {
+            graph.add_edge(from, to);
         }
-        None
+        graph
     }
 }
 
-impl<T: Clone + Debug + Eq + Hash> DirectedGraph<T> {
-    // Using Ford-Fulkerson algorithm
-    pub fn max_flow(&self, start: &Rc<T>, end: &Rc<T>) -> Self {
-        let mut graph = self.clone();
-        let mut flow = Self::new();
-        while let Some(path) = graph.dfs(start, end) {
-            for edge in path.iter().tuple_windows::<(&Rc<T>, &Rc<T>)>() {
-                flow.add_edge(edge.0, edge.1);
-                flow.remove_edge(edge.1, edge.0);
-                graph.add_edge(edge.1, edge.0);
-                graph.remove_edge(edge.0, edge.1);
+// The hash sets iterate the neighbours in arbitrary order, so dfs and max_flow
+// may return a different (equally valid) path or flow on each run. The sorted
+// variants visit the neighbours in ascending order instead.
+impl<T: Debug + Eq + Hash + Ord> DirectedGraph<T> {
+    pub fn dfs_sorted(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<Rc<T>>> {
+        dfs(&SortedNeighbours(self), start, target)
+    }
+
+    pub fn bfs_sorted(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<Rc<T>>> {
+        bfs(&SortedNeighbours(self), start, target)
+    }
+
+    pub fn max_flow_sorted(&self, start: &Rc<T>, end: &Rc<T>) -> Self {
+        Self::from_flow(max_flow(&SortedNeighbours(self), start, end))
+    }
+}
+
+impl<T: Eq + Hash> Graph for DirectedGraph<T> {
+    type Node = Rc<T>;
+
+    // Nodes without outgoing edges only appear as neighbours.
+    fn nodes(&self) -> Box<dyn Iterator<Item = Rc<T>> + '_> {
+        let sinks = self
+            .adjancency
+            .values()
+            .flatten()
+            .filter(move |&node| !self.adjancency.contains_key(node))
+            .unique();
+        Box::new(self.adjancency.keys().chain(sinks).cloned())
+    }
+
+    fn neighbours(&self, node: &Rc<T>) -> Box<dyn Iterator<Item = Rc<T>> + '_> {
+        match self.adjancency.get(node) {
+            Some(edges) => Box::new(edges.iter().cloned()),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+impl<T: Debug + Eq + Hash> GraphMut for DirectedGraph<T> {
+    fn add_edge(&mut self, from: &Rc<T>, to: &Rc<T>) {
+        DirectedGraph::add_edge(self, from, to);
+    }
+
+    fn remove_edge(&mut self, from: &Rc<T>, to: &Rc<T>) {
+        DirectedGraph::remove_edge(self, from, to);
+    }
+}
+
+impl<T: Eq + Hash + Ord> DirectedGraph<T> {
+    // All nodes in sorted order and the matrix whose entry (i, j) tells
+    // whether there is an edge from the i-th to the j-th node.
+    pub fn to_adjacency_matrix(&self) -> (Vec<Rc<T>>, Vec<Vec<bool>>) {
+        let nodes: Vec<Rc<T>> = self
+            .adjancency
+            .iter()
+            .flat_map(|(from, edges)| std::iter::once(from).chain(edges))
+            .cloned()
+            .sorted()
+            .dedup()
+            .collect();
+        let index: HashMap<&Rc<T>, usize> = nodes.iter().enumerate().map(|(i, n)| (n, i)).collect();
+        let mut matrix = vec![vec![false; nodes.len()]; nodes.len()];
+        for (from, edges) in &self.adjancency {
+            for to in edges {
+                matrix[index[from]][index[to]] = true;
             }
         }
-        for (vertex, edges) in flow.adjancency.iter_mut() {
-            *edges = edges
-                .iter()
-                .filter(|&j| {
-                    self.adjancency
-                        .get(vertex)
-                        .and_then(|e| Some(e.contains(j)))
-                        .unwrap_or(false)
-                })
-                .cloned()
-                .collect();
+        (nodes, matrix)
+    }
+}
+
+// Prints one `node -> [neighbours]` line per node with outgoing edges, all
+// sorted so that the output is deterministic.
+impl<T: Display + Eq + Hash + Ord> Display for DirectedGraph<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        for (from, edges) in self.adjancency.iter().sorted_by_key(|(from, _)| *from) {
+            writeln!(f, "{} -> [{}]", from, edges.iter().sorted().join(", "))?;
         }
-        flow
+        Ok(())
     }
 }
 
@@ -118,6 +252,100 @@ mod tests {
         assert_eq!(graph.dfs(&nodes[0], &nodes[5]), None);
     }
 
+    #[test]
+    fn test_bfs() {
+        let nodes: Vec<Rc<u32>> = (0..6).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[1], &nodes[2]);
+        graph.add_edge(&nodes[2], &nodes[4]);
+        graph.add_edge(&nodes[1], &nodes[3]);
+        graph.add_edge(&nodes[3], &nodes[2]);
+        graph.add_edge(&nodes[0], &nodes[3]);
+        let path = graph.bfs(&nodes[0], &nodes[4]).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.start(), Some(&nodes[0]));
+        assert_eq!(path.end(), Some(&nodes[4]));
+        assert_eq!(
+            graph.bfs_sorted(&nodes[0], &nodes[4]),
+            Some(Path::from(vec![
+                Rc::clone(&nodes[0]),
+                Rc::clone(&nodes[1]),
+                Rc::clone(&nodes[2]),
+                Rc::clone(&nodes[4])
+            ]))
+        );
+        assert_eq!(graph.bfs(&nodes[0], &nodes[5]), None);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let nodes: Vec<Rc<u32>> = (0..3).map(Rc::new).collect();
+        let mut graph = DirectedGraph::with_capacity(3, 2);
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[0], &nodes[2]);
+        assert!(graph.adjancency.capacity() >= 3);
+        assert!(graph.adjancency[&nodes[0]].capacity() >= 2);
+
+        let mut expected = DirectedGraph::new();
+        expected.add_edge(&nodes[0], &nodes[1]);
+        expected.add_edge(&nodes[0], &nodes[2]);
+        assert_eq!(graph, expected);
+
+        graph.reserve(100);
+        assert!(graph.adjancency.capacity() >= 101);
+        graph.shrink_to_fit();
+        assert!(graph.adjancency.capacity() < 100);
+        assert_eq!(graph, expected);
+    }
+
+    #[test]
+    fn test_to_adjacency_matrix() {
+        let nodes: Vec<Rc<u32>> = (0..3).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[2], &nodes[0]);
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[0], &nodes[2]);
+        assert_eq!(
+            graph.to_adjacency_matrix(),
+            (
+                nodes,
+                vec![
+                    vec![false, true, true],
+                    vec![false, false, false],
+                    vec![true, false, false],
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let nodes: Vec<Rc<u32>> = (0..4).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[3], &nodes[0]);
+        graph.add_edge(&nodes[0], &nodes[2]);
+        graph.add_edge(&nodes[0], &nodes[1]);
+        assert_eq!(graph.to_string(), "0 -> [1, 2]\n3 -> [0]\n");
+    }
+
+    #[test]
+    fn test_nodes() {
+        let nodes: Vec<Rc<u32>> = (0..4).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[2], &nodes[1]);
+        graph.add_edge(&nodes[1], &nodes[3]);
+        graph.add_edge(&nodes[2], &nodes[3]);
+        assert_eq!(Graph::nodes(&graph).sorted().collect::<Vec<_>>(), nodes);
+        assert_eq!(
+            SortedNeighbours(&graph)
+                .neighbours(&nodes[2])
+                .collect::<Vec<_>>(),
+            vec![Rc::clone(&nodes[1]), Rc::clone(&nodes[3])]
+        );
+    }
+
     #[test]
     fn test_max_flow() {
         let nodes: Vec<Rc<u32>> = (0..6).map(Rc::new).collect();
@@ -139,4 +367,127 @@ mod tests {
         expected_flow.add_edge(&nodes[4], &nodes[5]);
         assert_eq!(flow, expected_flow);
     }
+
+    #[test]
+    fn test_topological_sort() {
+        let nodes: Vec<Rc<u32>> = (0..4).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[2], &nodes[0]);
+        graph.add_edge(&nodes[0], &nodes[3]);
+        graph.add_edge(&nodes[2], &nodes[1]);
+        graph.add_edge(&nodes[1], &nodes[3]);
+        let sorted = graph.topological_sort().unwrap();
+        assert_eq!(sorted.first(), Some(&nodes[2]));
+        assert_eq!(sorted.last(), Some(&nodes[3]));
+        assert_eq!(sorted.len(), 4);
+
+        graph.add_edge(&nodes[3], &nodes[2]);
+        let err = graph.topological_sort().unwrap_err();
+        assert_eq!(err.remaining.len(), 4);
+    }
+
+    #[test]
+    fn test_weakly_connected_components() {
+        let nodes: Vec<Rc<u32>> = (0..5).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[2], &nodes[1]);
+        graph.add_edge(&nodes[3], &nodes[4]);
+        let components: Vec<Vec<Rc<u32>>> = graph
+            .weakly_connected_components()
+            .into_iter()
+            .map(|component| component.into_iter().sorted().collect())
+            .sorted()
+            .collect();
+        assert_eq!(components, vec![nodes[0..3].to_vec(), nodes[3..5].to_vec()]);
+    }
+
+    #[test]
+    fn test_min_cut() {
+        let nodes: Vec<Rc<u32>> = (0..5).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[0], &nodes[2]);
+        graph.add_edge(&nodes[1], &nodes[3]);
+        graph.add_edge(&nodes[2], &nodes[3]);
+        graph.add_edge(&nodes[3], &nodes[4]);
+        let cut = graph.min_cut(&nodes[0], &nodes[4]);
+        assert_eq!(
+            cut,
+            [(Rc::clone(&nodes[3]), Rc::clone(&nodes[4]))]
+                .iter()
+                .cloned()
+                .collect()
+        );
+        assert!(graph.min_cut(&nodes[4], &nodes[0]).is_empty());
+    }
+
+    #[test]
+    fn test_max_flow_with_capacities() {
+        let nodes: Vec<Rc<u32>> = (0..4).map(Rc::new).collect();
+        let mut graph = DirectedGraph::new();
+        graph.add_edge(&nodes[0], &nodes[1]);
+        graph.add_edge(&nodes[0], &nodes[2]);
+        graph.add_edge(&nodes[1], &nodes[3]);
+        graph.add_edge(&nodes[2], &nodes[3]);
+        let mut capacities = HashMap::new();
+        capacities.insert((Rc::clone(&nodes[0]), Rc::clone(&nodes[1])), 5);
+        capacities.insert((Rc::clone(&nodes[1]), Rc::clone(&nodes[3])), 3);
+        let flow = graph.max_flow_with_capacities(&nodes[0], &nodes[3], &capacities);
+        assert_eq!(flow.value, 4);
+        let mut edges = flow.edges;
+        edges.sort();
+        assert_eq!(
+            edges,
+            vec![
+                (Rc::clone(&nodes[0]), Rc::clone(&nodes[1]), 3),
+                (Rc::clone(&nodes[0]), Rc::clone(&nodes[2]), 1),
+                (Rc::clone(&nodes[1]), Rc::clone(&nodes[3]), 3),
+                (Rc::clone(&nodes[2]), Rc::clone(&nodes[3]), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dfs_sorted() {
+        let nodes: Vec<Rc<u32>> = (0..4).map(Rc::new).collect();
+        for _ in 0..10 {
+            let mut graph = DirectedGraph::new();
+            graph.add_edge(&nodes[0], &nodes[2]);
+            graph.add_edge(&nodes[0], &nodes[1]);
+            graph.add_edge(&nodes[2], &nodes[3]);
+            graph.add_edge(&nodes[1], &nodes[3]);
+            assert_eq!(
+                graph.dfs_sorted(&nodes[0], &nodes[3]),
+                Some(Path::from(vec![
+                    Rc::clone(&nodes[0]),
+                    Rc::clone(&nodes[1]),
+                    Rc::clone(&nodes[3])
+                ]))
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_flow_sorted() {
+        let nodes: Vec<Rc<u32>> = (0..5).map(Rc::new).collect();
+        let mut expected_flow = DirectedGraph::new();
+        expected_flow.add_edge(&nodes[0], &nodes[1]);
+        expected_flow.add_edge(&nodes[1], &nodes[3]);
+        expected_flow.add_edge(&nodes[3], &nodes[4]);
+        for _ in 0..10 {
+            let mut graph = DirectedGraph::new();
+            graph.add_edge(&nodes[0], &nodes[2]);
+            graph.add_edge(&nodes[0], &nodes[1]);
+            graph.add_edge(&nodes[1], &nodes[3]);
+            graph.add_edge(&nodes[2], &nodes[3]);
+            graph.add_edge(&nodes[3], &nodes[4]);
+            let flow = graph.max_flow_sorted(&nodes[0], &nodes[4]);
+            assert_eq!(flow, expected_flow);
+            assert_eq!(
+                graph.max_flow(&nodes[0], &nodes[4]).adjancency[&nodes[0]].len(),
+                1
+            );
+        }
+    }
 }
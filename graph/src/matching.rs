@@ -0,0 +1,184 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::rc::Rc;
+
+const INFINITY: usize = usize::MAX;
+
+struct HopcroftKarp<'a> {
+    adjacency: &'a [Vec<usize>],
+    match_left: Vec<Option<usize>>,
+    match_right: Vec<Option<usize>>,
+    distance: Vec<usize>,
+}
+
+impl<'a> HopcroftKarp<'a> {
+    fn new(adjacency: &'a [Vec<usize>], n_right: usize) -> Self {
+        Self {
+            adjacency,
+            match_left: vec![None; adjacency.len()],
+            match_right: vec![None; n_right],
+            distance: vec![INFINITY; adjacency.len()],
+        }
+    }
+
+    fn layer(&mut self) -> bool {
+        let mut queue = VecDeque::with_capacity(self.adjacency.len());
+        for (left, matched) in self.match_left.iter().enumerate() {
+            if matched.is_none() {
+                self.distance[left] = 0;
+                queue.push_back(left);
+            } else {
+                self.distance[left] = INFINITY;
+            }
+        }
+
+        let mut found_augmenting_path = false;
+        while let Some(left) = queue.pop_front() {
+            for &right in &self.adjacency[left] {
+                match self.match_right[right] {
+                    None => found_augmenting_path = true,
+                    Some(next) if self.distance[next] == INFINITY => {
+                        self.distance[next] = self.distance[left] + 1;
+                        queue.push_back(next);
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+        found_augmenting_path
+    }
+
+    // Augments along a maximal set of shortest augmenting paths. Returns
+    // false if there are none left.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    fn phase(&mut self) -> bool {
+        if !self.layer() {
+            return false;
+        }
+        for left in 0..self.adjacency.len() {
+            if self.match_left[left].is_none() {
+                self.augment(left);
+            }
+        }
+        true
+    }
+
+    fn augment(&mut self, left: usize) -> bool {
+        for &right in &self.adjacency[left] {
+            let can_augment = match self.match_right[right] {
+                None => true,
+                Some(next) => self.distance[next] == self.distance[left] + 1 && self.augment(next),
+            };
+            if can_augment {
+                self.match_left[left] = Some(right);
+                self.match_right[right] = Some(left);
+                return true;
+            }
+        }
+        self.distance[left] = INFINITY;
+        false
+    }
+}
+
+// Maximum bipartite matching using the Hopcroft-Karp algorithm. `adjacency[i]`
+// lists the right vertices that left vertex `i` may be matched with. Returns
+// the matched right vertex for each left vertex.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn hopcroft_karp(adjacency: &[Vec<usize>], n_right: usize) -> Vec<Option<usize>> {
+    let mut state = HopcroftKarp::new(adjacency, n_right);
+    while state.phase() {}
+    state.match_left
+}
+
+// Maximum matching of left to right nodes along the given edges, using
+// hopcroft_karp. Edges with a node that is not among the left or right nodes
+// are ignored. Returns the matched pairs in the order of the left nodes.
+pub fn bipartite_matching<L: Eq + Hash, R: Eq + Hash>(
+    left: &[Rc<L>],
+    right: &[Rc<R>],
+    edges: &[(Rc<L>, Rc<R>)],
+) -> Vec<(Rc<L>, Rc<R>)> {
+    let left_index: HashMap<&Rc<L>, usize> = left.iter().enumerate().map(|(i, n)| (n, i)).collect();
+    let right_index: HashMap<&Rc<R>, usize> =
+        right.iter().enumerate().map(|(i, n)| (n, i)).collect();
+    let mut adjacency = vec![vec![]; left.len()];
+    for (from, to) in edges {
+        if let (Some(&from), Some(&to)) = (left_index.get(from), right_index.get(to)) {
+            if !adjacency[from].contains(&to) {
+                adjacency[from].push(to);
+            }
+        }
+    }
+    hopcroft_karp(&adjacency, right.len())
+        .into_iter()
+        .enumerate()
+        .filter_map(|(l, r)| Some((Rc::clone(&left[l]), Rc::clone(&right[r?]))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hopcroft_karp() {
+        let adjacency = vec![vec![0, 1], vec![0], vec![1, 2], vec![2, 3]];
+        let matching = hopcroft_karp(&adjacency, 4);
+        assert_eq!(matching, vec![Some(1), Some(0), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_hopcroft_karp_with_unmatchable_vertex() {
+        let adjacency = vec![vec![0], vec![0], vec![1]];
+        let matching = hopcroft_karp(&adjacency, 2);
+        assert_eq!(matching.iter().filter(|m| m.is_some()).count(), 2);
+        assert_eq!(matching[2], Some(1));
+    }
+
+    #[test]
+    fn test_hopcroft_karp_needs_augmenting_path() {
+        let adjacency = vec![vec![0], vec![0, 1], vec![1, 2]];
+        assert_eq!(
+            hopcroft_karp(&adjacency, 3),
+            vec![Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_bipartite_matching() {
+        let left: Vec<Rc<&str>> = ["a", "b", "c"].iter().copied().map(Rc::new).collect();
+        let right: Vec<Rc<u32>> = (0..3).map(Rc::new).collect();
+        let edges = vec![
+            (Rc::clone(&left[0]), Rc::clone(&right[0])),
+            (Rc::clone(&left[1]), Rc::clone(&right[0])),
+            (Rc::clone(&left[1]), Rc::clone(&right[1])),
+            (Rc::clone(&left[2]), Rc::clone(&right[1])),
+            (Rc::clone(&left[2]), Rc::clone(&right[1])),
+            (Rc::new("d"), Rc::clone(&right[2])),
+        ];
+        let matching: Vec<(&str, u32)> = bipartite_matching(&left, &right, &edges)
+            .into_iter()
+            .map(|(l, r)| (*l, *r))
+            .collect();
+        assert_eq!(matching, vec![("a", 0), ("b", 1)]);
+    }
+
+    #[test]
+    fn test_bipartite_matching_is_maximum() {
+        let left: Vec<Rc<u32>> = (0..3).map(Rc::new).collect();
+        let right: Vec<Rc<u32>> = (10..13).map(Rc::new).collect();
+        let edges: Vec<(Rc<u32>, Rc<u32>)> = [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)]
+            .iter()
+            .map(|&(l, r)| (Rc::clone(&left[l]), Rc::clone(&right[r])))
+            .collect();
+        let matching = bipartite_matching(&left, &right, &edges);
+        assert_eq!(
+            matching,
+            vec![
+                (Rc::clone(&left[0]), Rc::clone(&right[0])),
+                (Rc::clone(&left[1]), Rc::clone(&right[1])),
+                (Rc::clone(&left[2]), Rc::clone(&right[2])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+
+// The nodes of a path in the order they are visited, from the start to the end
+// node. A path found by a search consists of at least the start node.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Path<N> {
+    nodes: Vec<N>,
+}
+
+impl<N> Path<N> {
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    pub fn into_nodes(self) -> Vec<N> {
+        self.nodes
+    }
+
+    // Number of nodes, the path has one edge less.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn start(&self) -> Option<&N> {
+        self.nodes.first()
+    }
+
+    pub fn end(&self) -> Option<&N> {
+        self.nodes.last()
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (&N, &N)> + '_ {
+        self.nodes.windows(2).map(|edge| (&edge[0], &edge[1]))
+    }
+}
+
+impl<N: PartialEq> Path<N> {
+    pub fn contains(&self, node: &N) -> bool {
+        self.nodes.contains(node)
+    }
+}
+
+impl<N> From<Vec<N>> for Path<N> {
+    fn from(nodes: Vec<N>) -> Self {
+        Self { nodes }
+    }
+}
+
+impl<N> FromIterator<N> for Path<N> {
+    fn from_iter<I: IntoIterator<Item = N>>(iter: I) -> Self {
+        Self {
+            nodes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<N> IntoIterator for Path<N> {
+    type Item = N;
+    type IntoIter = std::vec::IntoIter<N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.into_iter()
+    }
+}
+
+// Prints the nodes separated by arrows, e.g. `0 -> 1 -> 3`.
+impl<N: Display> Display for Path<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.nodes.iter().join(" -> "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edges() {
+        let path = Path::from(vec![0, 1, 3, 4]);
+        assert_eq!(path.len(), 4);
+        assert_eq!(
+            path.edges().collect::<Vec<_>>(),
+            vec![(&0, &1), (&1, &3), (&3, &4)]
+        );
+        assert_eq!(Path::from(vec![0]).edges().count(), 0);
+    }
+
+    #[test]
+    fn test_contains() {
+        let path: Path<u32> = (0..3).collect();
+        assert!(path.contains(&2));
+        assert!(!path.contains(&3));
+        assert_eq!((path.start(), path.end()), (Some(&0), Some(&2)));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Path::from(vec![0, 1, 3]).to_string(), "0 -> 1 -> 3");
+        assert_eq!(Path::from(vec![0]).to_string(), "0");
+    }
+}
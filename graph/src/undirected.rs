@@ -0,0 +1,194 @@
+use super::{bfs, dfs, weakly_connected_components, Graph, GraphMut, Path};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+
+// A graph whose edges connect both ways. Every edge is stored in the edge sets
+// of both of its nodes, so that the traversals shared with the directed
+// graphs through the Graph trait follow it in either direction.
+#[derive(Debug)]
+pub struct UndirectedGraph<T: Eq + Hash> {
+    adjacency: HashMap<Rc<T>, HashSet<Rc<T>>>,
+}
+
+impl<T: Eq + Hash> Clone for UndirectedGraph<T> {
+    fn clone(&self) -> Self {
+        Self {
+            adjacency: self.adjacency.clone(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> PartialEq for UndirectedGraph<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.adjacency == other.adjacency
+    }
+}
+
+impl<T: Debug + Eq + Hash> Default for UndirectedGraph<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug + Eq + Hash> UndirectedGraph<T> {
+    pub fn new() -> Self {
+        Self {
+            adjacency: HashMap::new(),
+        }
+    }
+
+    // Nodes are added with their edges, this is only needed for nodes
+    // without any.
+    pub fn add_node(&mut self, node: &Rc<T>) {
+        self.adjacency.entry(Rc::clone(node)).or_default();
+    }
+
+    pub fn add_edge(&mut self, a: &Rc<T>, b: &Rc<T>) {
+        for (from, to) in &[(a, b), (b, a)] {
+            self.adjacency
+                .entry(Rc::clone(from))
+                .or_default()
+                .insert(Rc::clone(to));
+        }
+    }
+
+    // Keeps the nodes, even if they have no edges left.
+    pub fn remove_edge(&mut self, a: &Rc<T>, b: &Rc<T>) {
+        for (from, to) in &[(a, b), (b, a)] {
+            if let Some(edges) = self.adjacency.get_mut(*from) {
+                edges.remove(*to);
+            }
+        }
+    }
+
+    pub fn contains_edge(&self, a: &Rc<T>, b: &Rc<T>) -> bool {
+        self.adjacency.get(a).is_some_and(|edges| edges.contains(b))
+    }
+
+    pub fn n_nodes(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    // Each edge is stored twice, except for loops.
+    pub fn n_edges(&self) -> usize {
+        let n_loops = self
+            .adjacency
+            .iter()
+            .filter(|(node, edges)| edges.contains(*node))
+            .count();
+        (self.adjacency.values().map(HashSet::len).sum::<usize>() + n_loops) / 2
+    }
+
+    // Number of edges at the node. A loop counts once.
+    pub fn degree(&self, node: &Rc<T>) -> usize {
+        self.adjacency.get(node).map_or(0, HashSet::len)
+    }
+
+    pub fn dfs(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<Rc<T>>> {
+        dfs(self, start, target)
+    }
+
+    pub fn bfs(&self, start: &Rc<T>, target: &Rc<T>) -> Option<Path<Rc<T>>> {
+        bfs(self, start, target)
+    }
+
+    pub fn connected_components(&self) -> Vec<Vec<Rc<T>>> {
+        weakly_connected_components(self)
+    }
+}
+
+impl<T: Eq + Hash> Graph for UndirectedGraph<T> {
+    type Node = Rc<T>;
+
+    fn nodes(&self) -> Box<dyn Iterator<Item = Rc<T>> + '_> {
+        Box::new(self.adjacency.keys().cloned())
+    }
+
+    fn neighbours(&self, node: &Rc<T>) -> Box<dyn Iterator<Item = Rc<T>> + '_> {
+        match self.adjacency.get(node) {
+            Some(edges) => Box::new(edges.iter().cloned()),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+impl<T: Debug + Eq + Hash> GraphMut for UndirectedGraph<T> {
+    fn add_edge(&mut self, from: &Rc<T>, to: &Rc<T>) {
+        UndirectedGraph::add_edge(self, from, to);
+    }
+
+    fn remove_edge(&mut self, from: &Rc<T>, to: &Rc<T>) {
+        UndirectedGraph::remove_edge(self, from, to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    fn example() -> (Vec<Rc<u32>>, UndirectedGraph<u32>) {
+        let nodes: Vec<Rc<u32>> = (0..6).map(Rc::new).collect();
+        let mut graph = UndirectedGraph::new();
+        for &(a, b) in &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 4)] {
+            graph.add_edge(&nodes[a], &nodes[b]);
+        }
+        graph.add_node(&nodes[5]);
+        (nodes, graph)
+    }
+
+    #[test]
+    fn test_edges_are_symmetric() {
+        let (nodes, mut graph) = example();
+        assert!(graph.contains_edge(&nodes[1], &nodes[0]));
+        assert!(graph.contains_edge(&nodes[0], &nodes[1]));
+        assert!(!graph.contains_edge(&nodes[0], &nodes[3]));
+        assert_eq!(graph.n_nodes(), 6);
+        assert_eq!(graph.n_edges(), 5);
+
+        graph.remove_edge(&nodes[1], &nodes[0]);
+        assert!(!graph.contains_edge(&nodes[0], &nodes[1]));
+        assert_eq!(graph.n_edges(), 4);
+        assert_eq!(graph.n_nodes(), 6);
+    }
+
+    #[test]
+    fn test_degree() {
+        let (nodes, graph) = example();
+        let degrees: Vec<usize> = nodes.iter().map(|node| graph.degree(node)).collect();
+        assert_eq!(degrees, vec![2, 2, 2, 1, 2, 0]);
+        assert_eq!(graph.degree(&Rc::new(7)), 0);
+    }
+
+    #[test]
+    fn test_traversal_in_both_directions() {
+        let (nodes, graph) = example();
+        assert_eq!(
+            graph.bfs(&nodes[2], &nodes[1]).map(|path| path.len()),
+            Some(2)
+        );
+        assert!(graph.dfs(&nodes[4], &nodes[3]).is_some());
+        assert_eq!(graph.dfs(&nodes[0], &nodes[3]), None);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let (nodes, graph) = example();
+        let components: Vec<Vec<Rc<u32>>> = graph
+            .connected_components()
+            .into_iter()
+            .map(|component| component.into_iter().sorted().collect())
+            .sorted()
+            .collect();
+        assert_eq!(
+            components,
+            vec![
+                nodes[0..3].to_vec(),
+                nodes[3..5].to_vec(),
+                nodes[5..6].to_vec()
+            ]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use super::Axial;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// A step to one of the six neighbours of a hexagon.
+pub trait Direction: Copy + Sized {
+    fn offset(self) -> Axial;
+
+    // All directions in counter-clockwise order.
+    fn all() -> [Self; 6];
+
+    fn opposite(self) -> Self {
+        let offset = -self.offset();
+        *Self::all()
+            .iter()
+            .find(|direction| direction.offset() == offset)
+            .unwrap()
+    }
+}
+
+// Directions for hexagons with a pointy top, i.e. with east and west
+// neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointyDirection {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+
+// Directions for hexagons with a flat top, i.e. with north and south
+// neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlatDirection {
+    North,
+    NorthEast,
+    SouthEast,
+    South,
+    SouthWest,
+    NorthWest,
+}
+
+impl Direction for PointyDirection {
+    fn offset(self) -> Axial {
+        use PointyDirection::*;
+        match self {
+            East => Axial::new(1, 0),
+            SouthEast => Axial::new(1, -1),
+            SouthWest => Axial::new(0, -1),
+            West => Axial::new(-1, 0),
+            NorthWest => Axial::new(-1, 1),
+            NorthEast => Axial::new(0, 1),
+        }
+    }
+
+    fn all() -> [Self; 6] {
+        use PointyDirection::*;
+        [East, NorthEast, NorthWest, West, SouthWest, SouthEast]
+    }
+}
+
+impl Direction for FlatDirection {
+    fn offset(self) -> Axial {
+        use FlatDirection::*;
+        match self {
+            North => Axial::new(0, 1),
+            NorthEast => Axial::new(1, 0),
+            SouthEast => Axial::new(1, -1),
+            South => Axial::new(0, -1),
+            SouthWest => Axial::new(-1, 0),
+            NorthWest => Axial::new(-1, 1),
+        }
+    }
+
+    fn all() -> [Self; 6] {
+        use FlatDirection::*;
+        [NorthEast, North, NorthWest, SouthWest, South, SouthEast]
+    }
+}
+
+impl PointyDirection {
+    // Parses a path of directions without delimiters like `esenee`. The
+    // parser stops after the first error.
+    pub fn parse_path<I: Iterator<Item = char>>(input: I) -> DirectionParser<I> {
+        DirectionParser {
+            input,
+            position: 0,
+            failed: false,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        use PointyDirection::*;
+        match self {
+            East => "e",
+            SouthEast => "se",
+            SouthWest => "sw",
+            West => "w",
+            NorthWest => "nw",
+            NorthEast => "ne",
+        }
+    }
+}
+
+impl FlatDirection {
+    fn as_str(self) -> &'static str {
+        use FlatDirection::*;
+        match self {
+            North => "n",
+            NorthEast => "ne",
+            SouthEast => "se",
+            South => "s",
+            SouthWest => "sw",
+            NorthWest => "nw",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownDirection(pub String);
+
+impl Display for UnknownDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "unknown direction '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownDirection {}
+
+impl FromStr for PointyDirection {
+    type Err = UnknownDirection;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|direction| direction.as_str() == s)
+            .ok_or_else(|| UnknownDirection(String::from(s)))
+    }
+}
+
+impl FromStr for FlatDirection {
+    type Err = UnknownDirection;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|direction| direction.as_str() == s)
+            .ok_or_else(|| UnknownDirection(String::from(s)))
+    }
+}
+
+impl Display for PointyDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Display for FlatDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Position is the index of the offending character, counted in chars.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectionParseError {
+    pub position: usize,
+    pub found: Option<char>,
+}
+
+impl Display for DirectionParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self.found {
+            Some(c) => write!(f, "unexpected '{}' at position {}", c, self.position),
+            None => write!(f, "unexpected end of input at position {}", self.position),
+        }
+    }
+}
+
+impl std::error::Error for DirectionParseError {}
+
+pub struct DirectionParser<I>
+where
+    I: Iterator<Item = char>,
+{
+    input: I,
+    position: usize,
+    failed: bool,
+}
+
+impl<I> DirectionParser<I>
+where
+    I: Iterator<Item = char>,
+{
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.input.next();
+        if c.is_some() {
+            self.position += 1;
+        }
+        c
+    }
+
+    fn error(&mut self, found: Option<char>) -> DirectionParseError {
+        self.failed = true;
+        DirectionParseError {
+            position: if found.is_some() {
+                self.position - 1
+            } else {
+                self.position
+            },
+            found,
+        }
+    }
+}
+
+impl<I> Iterator for DirectionParser<I>
+where
+    I: Iterator<Item = char>,
+{
+    type Item = Result<PointyDirection, DirectionParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use PointyDirection::*;
+        if self.failed {
+            return None;
+        }
+        let direction = match self.next_char()? {
+            'e' => East,
+            'w' => West,
+            's' => match self.next_char() {
+                Some('e') => SouthEast,
+                Some('w') => SouthWest,
+                c => return Some(Err(self.error(c))),
+            },
+            'n' => match self.next_char() {
+                Some('e') => NorthEast,
+                Some('w') => NorthWest,
+                c => return Some(Err(self.error(c))),
+            },
+            c => return Some(Err(self.error(Some(c)))),
+        };
+        Some(Ok(direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path() {
+        use PointyDirection::*;
+        assert_eq!(
+            PointyDirection::parse_path("sesenwnee".chars()).collect::<Result<Vec<_>, _>>(),
+            Ok(vec![SouthEast, SouthEast, NorthWest, NorthEast, East])
+        );
+    }
+
+    #[test]
+    fn test_parse_path_reports_position() {
+        use PointyDirection::*;
+        assert_eq!(
+            PointyDirection::parse_path("esxe".chars()).collect::<Vec<_>>(),
+            vec![
+                Ok(East),
+                Err(DirectionParseError {
+                    position: 2,
+                    found: Some('x')
+                })
+            ]
+        );
+        assert_eq!(
+            PointyDirection::parse_path("ex".chars()).collect::<Vec<_>>(),
+            vec![
+                Ok(East),
+                Err(DirectionParseError {
+                    position: 1,
+                    found: Some('x')
+                })
+            ]
+        );
+        assert_eq!(
+            PointyDirection::parse_path("en".chars()).last(),
+            Some(Err(DirectionParseError {
+                position: 2,
+                found: None
+            }))
+        );
+    }
+
+    #[test]
+    fn test_from_str_roundtrip() {
+        for direction in PointyDirection::all().iter() {
+            assert_eq!(direction.to_string().parse(), Ok(*direction));
+        }
+        for direction in FlatDirection::all().iter() {
+            assert_eq!(direction.to_string().parse(), Ok(*direction));
+        }
+        assert_eq!(
+            "n".parse::<PointyDirection>(),
+            Err(UnknownDirection(String::from("n")))
+        );
+    }
+
+    #[test]
+    fn test_opposite() {
+        assert_eq!(PointyDirection::East.opposite(), PointyDirection::West);
+        assert_eq!(
+            FlatDirection::NorthEast.opposite(),
+            FlatDirection::SouthWest
+        );
+    }
+
+    #[test]
+    fn test_all_is_counter_clockwise() {
+        let offsets: Vec<Axial> = PointyDirection::all().iter().map(|d| d.offset()).collect();
+        assert_eq!(offsets, crate::NEIGHBOUR_OFFSETS.to_vec());
+        let offsets: Vec<Axial> = FlatDirection::all().iter().map(|d| d.offset()).collect();
+        assert_eq!(offsets, crate::NEIGHBOUR_OFFSETS.to_vec());
+    }
+}
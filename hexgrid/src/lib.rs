@@ -0,0 +1,240 @@
+use std::ops::{Add, Neg, Sub};
+
+mod direction;
+mod pathfinding;
+
+pub use direction::{
+    Direction, DirectionParseError, DirectionParser, FlatDirection, PointyDirection,
+    UnknownDirection,
+};
+pub use pathfinding::find_path;
+
+// Axial coordinates of a hexagon. Going along the q axis and then the r axis
+// turns by 60 degrees. The orientation of the axes on screen is given by the
+// direction type used to move between hexagons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Axial {
+    pub q: isize,
+    pub r: isize,
+}
+
+// Cube coordinates of a hexagon, fulfilling q + r + s = 0. Distances and
+// rotations are simpler to express in these.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cube {
+    pub q: isize,
+    pub r: isize,
+    pub s: isize,
+}
+
+// Offsets to the six neighbours in counter-clockwise order.
+const NEIGHBOUR_OFFSETS: [Axial; 6] = [
+    Axial::new(1, 0),
+    Axial::new(0, 1),
+    Axial::new(-1, 1),
+    Axial::new(-1, 0),
+    Axial::new(0, -1),
+    Axial::new(1, -1),
+];
+
+impl Axial {
+    pub const fn new(q: isize, r: isize) -> Self {
+        Self { q, r }
+    }
+
+    pub fn neighbour(self, direction: impl Direction) -> Self {
+        self + direction.offset()
+    }
+
+    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+        NEIGHBOUR_OFFSETS.iter().map(move |&offset| self + offset)
+    }
+
+    // Follows the path starting at this hexagon and returns where it ends.
+    pub fn walk<D: Direction>(self, path: impl IntoIterator<Item = D>) -> Self {
+        path.into_iter()
+            .fold(self, |index, direction| index.neighbour(direction))
+    }
+}
+
+impl Cube {
+    pub fn new(q: isize, r: isize, s: isize) -> Option<Self> {
+        if q + r + s == 0 {
+            Some(Self { q, r, s })
+        } else {
+            None
+        }
+    }
+
+    // Rounds fractional cube coordinates to the hexagon containing them.
+    // The component with the largest rounding error is recomputed from the
+    // others to restore q + r + s = 0.
+    fn round(q: f64, r: f64, s: f64) -> Self {
+        let (mut rq, mut rr, mut rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        } else {
+            rs = -rq - rr;
+        }
+        Self {
+            q: rq as isize,
+            r: rr as isize,
+            s: rs as isize,
+        }
+    }
+}
+
+// Number of steps between two hexagons.
+pub fn distance(a: Axial, b: Axial) -> usize {
+    let d = Cube::from(a - b);
+    (d.q.unsigned_abs() + d.r.unsigned_abs() + d.s.unsigned_abs()) / 2
+}
+
+// The hexagons on a straight line from a to b, including both. Consecutive
+// hexagons are neighbours.
+pub fn line(a: Axial, b: Axial) -> Vec<Axial> {
+    let n = distance(a, b);
+    if n == 0 {
+        return vec![a];
+    }
+    // Nudge the start off the hexagon edges, so that points exactly between
+    // two hexagons are rounded consistently.
+    let (a, b) = (Cube::from(a), Cube::from(b));
+    let start = (a.q as f64 + 1e-6, a.r as f64 + 2e-6, a.s as f64 - 3e-6);
+    (0..=n)
+        .map(|i| {
+            let t = i as f64 / n as f64;
+            Axial::from(Cube::round(
+                start.0 + (b.q - a.q) as f64 * t,
+                start.1 + (b.r - a.r) as f64 * t,
+                start.2 + (b.s - a.s) as f64 * t,
+            ))
+        })
+        .collect()
+}
+
+impl From<(isize, isize)> for Axial {
+    fn from((q, r): (isize, isize)) -> Self {
+        Self::new(q, r)
+    }
+}
+
+impl From<Axial> for (isize, isize) {
+    fn from(index: Axial) -> Self {
+        (index.q, index.r)
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(index: Axial) -> Self {
+        Self {
+            q: index.q,
+            r: index.r,
+            s: -index.q - index.r,
+        }
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(index: Cube) -> Self {
+        Self::new(index.q, index.r)
+    }
+}
+
+impl Add for Axial {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.q + rhs.q, self.r + rhs.r)
+    }
+}
+
+impl Sub for Axial {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.q - rhs.q, self.r - rhs.r)
+    }
+}
+
+impl Neg for Axial {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.q, -self.r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cube_conversion_roundtrip() {
+        let index = Axial::new(3, -5);
+        let cube = Cube::from(index);
+        assert_eq!(cube, Cube::new(3, -5, 2).unwrap());
+        assert_eq!(Axial::from(cube), index);
+    }
+
+    #[test]
+    fn test_cube_new_checks_constraint() {
+        assert_eq!(Cube::new(1, 1, 1), None);
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance(Axial::new(0, 0), Axial::new(0, 0)), 0);
+        assert_eq!(distance(Axial::new(0, 0), Axial::new(3, 0)), 3);
+        assert_eq!(distance(Axial::new(0, 0), Axial::new(2, -3)), 3);
+        assert_eq!(distance(Axial::new(-1, 4), Axial::new(2, 1)), 3);
+        assert_eq!(distance(Axial::new(-1, -1), Axial::new(1, 1)), 4);
+    }
+
+    #[test]
+    fn test_line() {
+        let a = Axial::new(-2, 1);
+        let b = Axial::new(3, -4);
+        let hexagons = line(a, b);
+        assert_eq!(hexagons.len(), distance(a, b) + 1);
+        assert_eq!(hexagons.first(), Some(&a));
+        assert_eq!(hexagons.last(), Some(&b));
+        for pair in hexagons.windows(2) {
+            assert_eq!(distance(pair[0], pair[1]), 1);
+        }
+        assert_eq!(
+            line(Axial::new(0, 0), Axial::new(2, 0)),
+            vec![Axial::new(0, 0), Axial::new(1, 0), Axial::new(2, 0)]
+        );
+        assert_eq!(line(a, a), vec![a]);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let index = Axial::new(2, 3);
+        let neighbours: Vec<Axial> = index.neighbours().collect();
+        assert_eq!(neighbours.len(), 6);
+        for direction in PointyDirection::all().iter() {
+            assert!(neighbours.contains(&index.neighbour(*direction)));
+        }
+        for direction in FlatDirection::all().iter() {
+            assert!(neighbours.contains(&index.neighbour(*direction)));
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        use PointyDirection::*;
+        assert_eq!(
+            Axial::default().walk(vec![NorthWest, West, SouthWest, East, East]),
+            Axial::default()
+        );
+        assert_eq!(
+            Axial::default().walk(vec![East, SouthEast, West]),
+            Axial::default().neighbour(SouthEast)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use super::{distance, Axial};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+// Shortest path from start to goal, including both, that avoids blocked
+// hexagons. The grid is infinite, so paths longer than max_length are not
+// considered to guarantee termination if the goal is enclosed.
+pub fn find_path(
+    start: Axial,
+    goal: Axial,
+    is_blocked: impl Fn(Axial) -> bool,
+    max_length: usize,
+) -> Option<Vec<Axial>> {
+    if is_blocked(start) || is_blocked(goal) {
+        return None;
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Axial, Axial> = HashMap::new();
+    let mut path_length: HashMap<Axial, usize> = HashMap::new();
+    open.push(Reverse((distance(start, goal), start)));
+    path_length.insert(start, 0);
+
+    while let Some(Reverse((_, current))) = open.pop() {
+        if current == goal {
+            let mut path = vec![current];
+            while let Some(&previous) = came_from.get(path.last().unwrap()) {
+                path.push(previous);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let length = path_length[&current] + 1;
+        for neighbour in current.neighbours() {
+            if is_blocked(neighbour) || length + distance(neighbour, goal) > max_length {
+                continue;
+            }
+            if path_length
+                .get(&neighbour)
+                .is_none_or(|&known| length < known)
+            {
+                path_length.insert(neighbour, length);
+                came_from.insert(neighbour, current);
+                open.push(Reverse((length + distance(neighbour, goal), neighbour)));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_find_path_unobstructed() {
+        let start = Axial::new(0, 0);
+        let goal = Axial::new(3, -1);
+        let path = find_path(start, goal, |_| false, 10).unwrap();
+        assert_eq!(path.len(), distance(start, goal) + 1);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goal));
+    }
+
+    #[test]
+    fn test_find_path_around_wall() {
+        let wall: HashSet<Axial> = (-3..=3).map(|r| Axial::new(1, r)).collect();
+        let start = Axial::new(0, 0);
+        let goal = Axial::new(2, 0);
+        let path = find_path(start, goal, |index| wall.contains(&index), 20).unwrap();
+        assert!(path.iter().all(|index| !wall.contains(index)));
+        assert!(path.windows(2).all(|pair| distance(pair[0], pair[1]) == 1));
+        // Going around either end of the wall takes nine steps.
+        assert_eq!(path.len(), 10);
+    }
+
+    #[test]
+    fn test_find_path_enclosed_goal() {
+        let goal = Axial::new(5, 5);
+        let ring: HashSet<Axial> = goal.neighbours().collect();
+        assert_eq!(
+            find_path(Axial::new(0, 0), goal, |index| ring.contains(&index), 100),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_path_blocked_start() {
+        let start = Axial::new(0, 0);
+        assert_eq!(find_path(start, Axial::new(1, 0), |i| i == start, 10), None);
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+pub mod rle;
+pub mod sparse;
+
+// Numbers of active neighbours for which an inactive cell becomes active
+// (birth) and an active cell stays active (survival).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rules {
+    pub birth: Vec<usize>,
+    pub survival: Vec<usize>,
+}
+
+// Conway's Game of Life.
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            birth: vec![3],
+            survival: vec![2, 3],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RulesParseError(pub String);
+
+impl Display for RulesParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Invalid rules '{}', expected a format like 'B3/S2,3' without B0.",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for RulesParseError {}
+
+// An inactive cell without active neighbours cannot become active, otherwise
+// the infinite space would become active all at once.
+impl TryFrom<&str> for Rules {
+    type Error = RulesParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let error = || RulesParseError(String::from(input));
+        let parse_counts = |counts: &str| -> Result<Vec<usize>, RulesParseError> {
+            if counts.is_empty() {
+                return Ok(vec![]);
+            }
+            counts
+                .split(',')
+                .map(|n| n.parse().map_err(|_| error()))
+                .collect()
+        };
+        let (birth, survival) = input.split_once('/').ok_or_else(error)?;
+        let rules = Self {
+            birth: parse_counts(birth.strip_prefix('B').ok_or_else(error)?)?,
+            survival: parse_counts(survival.strip_prefix('S').ok_or_else(error)?)?,
+        };
+        if rules.birth.contains(&0) {
+            return Err(error());
+        }
+        Ok(rules)
+    }
+}
+
+impl Rules {
+    pub fn is_active_next(&self, is_active: bool, n_active_neighbours: usize) -> bool {
+        if is_active {
+            self.survival.contains(&n_active_neighbours)
+        } else {
+            self.birth.contains(&n_active_neighbours)
+        }
+    }
+}
+
+// Common interface of the representations of a space of cells, so that they
+// can be swapped for each other.
+pub trait LifeEngine {
+    type Cell;
+
+    fn next_state(self, rules: &Rules) -> Self;
+
+    #[cfg_attr(feature = "profile", tracing::instrument(skip(self, rules)))]
+    fn advance(self, n_generations: usize, rules: &Rules) -> Self
+    where
+        Self: Sized,
+    {
+        self.advance_observed(n_generations, rules, &mut |_, _| {})
+    }
+
+    // Calls observe with the generation and the number of active cells after
+    // each generation.
+    fn advance_observed(
+        self,
+        n_generations: usize,
+        rules: &Rules,
+        observe: &mut dyn FnMut(usize, usize),
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        (1..=n_generations).fold(self, |engine, generation| {
+            let engine = engine.next_state(rules);
+            observe(generation, engine.n_active());
+            engine
+        })
+    }
+
+    fn active_cells(&self) -> HashSet<Self::Cell>;
+    fn n_active(&self) -> usize;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(Rules::try_from("B3/S2,3"), Ok(Rules::default()));
+        assert_eq!(
+            Rules::try_from("B/S4"),
+            Ok(Rules {
+                birth: vec![],
+                survival: vec![4],
+            })
+        );
+        assert_eq!(
+            Rules::try_from("S2,3"),
+            Err(RulesParseError(String::from("S2,3")))
+        );
+        assert_eq!(
+            Rules::try_from("S1/B2"),
+            Err(RulesParseError(String::from("S1/B2")))
+        );
+        assert_eq!(
+            Rules::try_from("B3/S2;3"),
+            Err(RulesParseError(String::from("B3/S2;3")))
+        );
+        assert_eq!(
+            Rules::try_from("B0,3/S2,3"),
+            Err(RulesParseError(String::from("B0,3/S2,3")))
+        );
+    }
+}
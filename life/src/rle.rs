@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter, Write};
+
+// Golly wraps pattern lines at 70 characters.
+const MAX_LINE_LEN: usize = 70;
+
+// A two-dimensional slice of the cube in the run length encoded pattern
+// format used by Golly and other Life tools. Cells are given as (row, column)
+// and coords are the positions of the slice along the remaining dimensions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slice {
+    pub coords: Vec<i64>,
+    pub cells: HashSet<(i64, i64)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RleParseError {
+    InvalidCharacter(char),
+    InvalidRunLength(String),
+    MissingTerminator,
+}
+
+impl Display for RleParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::InvalidCharacter(c) => write!(f, "Invalid character '{}' in RLE pattern.", c),
+            Self::InvalidRunLength(n) => write!(f, "Invalid run length '{}' in RLE pattern.", n),
+            Self::MissingTerminator => f.write_str("RLE pattern is not terminated by '!'."),
+        }
+    }
+}
+
+impl std::error::Error for RleParseError {}
+
+// Parses a sequence of RLE patterns, one per slice. The position of a slice is
+// given by a comment like "#C z=1, w=-2" using the names of the coordinates,
+// slices without one are placed at the origin. This allows to load a single
+// pattern exported from other tools, which will end up at z=0.
+pub fn parse(input: &str, coord_names: &[&str]) -> Result<Vec<Slice>, RleParseError> {
+    let mut slices = vec![];
+    let mut coords = vec![0; coord_names.len()];
+    let mut cells = HashSet::new();
+    let mut in_pattern = false;
+    let (mut row, mut column) = (0, 0);
+    let mut run_length = String::new();
+
+    for line in input.lines().map(str::trim) {
+        if !in_pattern {
+            if let Some(comment) = line.strip_prefix("#C") {
+                if let Some(parsed) = parse_coords(comment, coord_names) {
+                    coords = parsed;
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') || line.starts_with('x') {
+                continue;
+            }
+            in_pattern = true;
+        }
+
+        for c in line.chars() {
+            if c.is_ascii_digit() {
+                run_length.push(c);
+                continue;
+            }
+            let n = if run_length.is_empty() {
+                1
+            } else {
+                run_length
+                    .parse::<i64>()
+                    .map_err(|_| RleParseError::InvalidRunLength(run_length.clone()))?
+            };
+            run_length.clear();
+            match c {
+                'b' => column += n,
+                'o' => {
+                    cells.extend((column..column + n).map(|column| (row, column)));
+                    column += n;
+                }
+                '$' => {
+                    row += n;
+                    column = 0;
+                }
+                '!' => {
+                    slices.push(Slice {
+                        coords,
+                        cells: std::mem::take(&mut cells),
+                    });
+                    coords = vec![0; coord_names.len()];
+                    in_pattern = false;
+                    row = 0;
+                    column = 0;
+                    break;
+                }
+                c if c.is_whitespace() => {}
+                c => return Err(RleParseError::InvalidCharacter(c)),
+            }
+        }
+    }
+
+    if in_pattern {
+        return Err(RleParseError::MissingTerminator);
+    }
+    Ok(slices)
+}
+
+fn parse_coords(comment: &str, coord_names: &[&str]) -> Option<Vec<i64>> {
+    let values: BTreeMap<&str, &str> = comment
+        .split(',')
+        .map(|assignment| assignment.trim().split_once('='))
+        .collect::<Option<_>>()?;
+    coord_names
+        .iter()
+        .map(|name| values.get(name)?.parse().ok())
+        .collect()
+}
+
+// Writes each slice as separate pattern. All slices share the same bounding
+// box, given by the lower bounds and shape as (rows, columns), so that they
+// stay aligned to each other.
+pub fn write(
+    f: &mut impl Write,
+    slices: &[Slice],
+    coord_names: &[&str],
+    lower_bounds: (i64, i64),
+    shape: (i64, i64),
+) -> Result<(), std::fmt::Error> {
+    for slice in slices {
+        let coords: Vec<String> = coord_names
+            .iter()
+            .zip(&slice.coords)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        writeln!(f, "#C {}", coords.join(", "))?;
+        writeln!(f, "x = {}, y = {}", shape.1, shape.0)?;
+
+        let mut items = vec![];
+        let mut current_row = lower_bounds.0;
+        for row in lower_bounds.0..lower_bounds.0 + shape.0 {
+            let mut row_items = vec![];
+            let mut column = lower_bounds.1;
+            while column < lower_bounds.1 + shape.1 {
+                let is_active = slice.cells.contains(&(row, column));
+                let start = column;
+                while column < lower_bounds.1 + shape.1
+                    && slice.cells.contains(&(row, column)) == is_active
+                {
+                    column += 1;
+                }
+                row_items.push((column - start, if is_active { 'o' } else { 'b' }));
+            }
+            // Trailing dead cells are implied by the end of the row.
+            if row_items.last().is_some_and(|&(_, tag)| tag == 'b') {
+                row_items.pop();
+            }
+            if row_items.is_empty() {
+                continue;
+            }
+            if row > current_row {
+                items.push((row - current_row, '$'));
+                current_row = row;
+            }
+            items.extend(row_items);
+        }
+        items.push((1, '!'));
+
+        let mut line = String::new();
+        for (n, tag) in items {
+            let item = if n == 1 {
+                tag.to_string()
+            } else {
+                format!("{}{}", n, tag)
+            };
+            if line.len() + item.len() > MAX_LINE_LEN {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            line.push_str(&item);
+        }
+        writeln!(f, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    fn glider() -> HashSet<(i64, i64)> {
+        HashSet::from_iter([(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)].iter().copied())
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+        assert_eq!(
+            parse(input, &["z"]),
+            Ok(vec![Slice {
+                coords: vec![0],
+                cells: glider(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_slices() {
+        let input = "#C z=-1\nx = 1, y = 3\n2$o!\n#C z=2\nx = 1, y = 1\no!\n";
+        assert_eq!(
+            parse(input, &["z"]),
+            Ok(vec![
+                Slice {
+                    coords: vec![-1],
+                    cells: HashSet::from_iter([(2, 0)].iter().copied()),
+                },
+                Slice {
+                    coords: vec![2],
+                    cells: HashSet::from_iter([(0, 0)].iter().copied()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("x = 1, y = 1\no?!", &["z"]),
+            Err(RleParseError::InvalidCharacter('?'))
+        );
+        assert_eq!(
+            parse("x = 1, y = 1\no$o", &["z"]),
+            Err(RleParseError::MissingTerminator)
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let slices = [
+            Slice {
+                coords: vec![0, 1],
+                cells: glider(),
+            },
+            Slice {
+                coords: vec![1, 1],
+                cells: HashSet::from_iter([(0, 0)].iter().copied()),
+            },
+        ];
+        let mut output = String::new();
+        write(&mut output, &slices, &["z", "w"], (0, 0), (3, 3)).unwrap();
+        assert_eq!(
+            output,
+            "#C z=0, w=1\nx = 3, y = 3\nbo$2bo$3o!\n#C z=1, w=1\nx = 3, y = 3\no!\n"
+        );
+        assert_eq!(parse(&output, &["z", "w"]), Ok(slices.to_vec()));
+    }
+
+    #[test]
+    fn test_write_wraps_long_lines() {
+        let slices = [Slice {
+            coords: vec![0],
+            cells: (0..100).step_by(2).map(|column| (0, column)).collect(),
+        }];
+        let mut output = String::new();
+        write(&mut output, &slices, &["z"], (0, 0), (1, 100)).unwrap();
+        assert!(output.lines().all(|line| line.len() <= MAX_LINE_LEN));
+        assert_eq!(parse(&output, &["z"]), Ok(slices.to_vec()));
+    }
+}
@@ -0,0 +1,139 @@
+use super::Rules;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+// Life on an unbounded space of any dimension or shape storing only the active
+// cells. The space is given by the neighbours of a cell, and only cells next
+// to an active cell are visited, so that the costs scale with the number of
+// active cells.
+pub fn next_generation<C, N>(
+    active: &HashSet<C>,
+    neighbours: impl Fn(&C) -> N,
+    rules: &Rules,
+) -> HashSet<C>
+where
+    C: Copy + Eq + Hash,
+    N: IntoIterator<Item = C>,
+{
+    let mut next = HashSet::with_capacity(active.len());
+    next_generation_into(active, &mut next, &mut HashMap::new(), neighbours, rules);
+    next
+}
+
+// Writes the next generation into the cleared next, so that the buffers can
+// be reused over many generations.
+#[cfg_attr(
+    feature = "profile",
+    tracing::instrument(name = "generation", skip_all)
+)]
+pub fn next_generation_into<C, N>(
+    active: &HashSet<C>,
+    next: &mut HashSet<C>,
+    n_active_neighbours: &mut HashMap<C, usize>,
+    neighbours: impl Fn(&C) -> N,
+    rules: &Rules,
+) where
+    C: Copy + Eq + Hash,
+    N: IntoIterator<Item = C>,
+{
+    n_active_neighbours.clear();
+    for cell in active {
+        for neighbour in neighbours(cell) {
+            *n_active_neighbours.entry(neighbour).or_insert(0) += 1;
+        }
+    }
+
+    next.clear();
+    next.extend(
+        n_active_neighbours
+            .iter()
+            .filter(|(cell, &n)| rules.is_active_next(active.contains(cell), n))
+            .map(|(&cell, _)| cell),
+    );
+    // Active cells without active neighbours are not in the counts.
+    if rules.is_active_next(true, 0) {
+        next.extend(
+            active
+                .iter()
+                .filter(|cell| !n_active_neighbours.contains_key(cell)),
+        );
+    }
+}
+
+// Like next_generation, but for n_generations. Calls observe with the
+// generation and the active cells after each generation.
+#[cfg_attr(
+    feature = "profile",
+    tracing::instrument(name = "advance", skip(active, neighbours, rules, observe))
+)]
+pub fn advance_observed<C, N>(
+    active: HashSet<C>,
+    n_generations: usize,
+    neighbours: impl Fn(&C) -> N,
+    rules: &Rules,
+    observe: &mut dyn FnMut(usize, &HashSet<C>),
+) -> HashSet<C>
+where
+    C: Copy + Eq + Hash,
+    N: IntoIterator<Item = C>,
+{
+    let mut active = active;
+    let mut next = HashSet::with_capacity(active.capacity());
+    let mut n_active_neighbours = HashMap::with_capacity(active.capacity());
+    for generation in 1..=n_generations {
+        next_generation_into(
+            &active,
+            &mut next,
+            &mut n_active_neighbours,
+            &neighbours,
+            rules,
+        );
+        std::mem::swap(&mut active, &mut next);
+        observe(generation, &active);
+    }
+    active
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::iter::FromIterator;
+
+    fn neighbours(&(x, y): &(i64, i64)) -> impl Iterator<Item = (i64, i64)> {
+        (x - 1..=x + 1)
+            .flat_map(move |nx| (y - 1..=y + 1).map(move |ny| (nx, ny)))
+            .filter(move |&neighbour| neighbour != (x, y))
+    }
+
+    fn blinker(vertical: bool) -> HashSet<(i64, i64)> {
+        HashSet::from_iter((-1..=1).map(|i| if vertical { (i, 0) } else { (0, i) }))
+    }
+
+    #[test]
+    fn test_next_generation() {
+        let next = next_generation(&blinker(false), neighbours, &Rules::default());
+        assert_eq!(next, blinker(true));
+    }
+
+    #[test]
+    fn test_advance_observed() {
+        let mut series = vec![];
+        let active = advance_observed(
+            blinker(false),
+            3,
+            neighbours,
+            &Rules::default(),
+            &mut |generation, active| series.push((generation, active.len())),
+        );
+        assert_eq!(active, blinker(true));
+        assert_eq!(series, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn test_survival_without_neighbours() {
+        let rules = Rules::try_from("B/S0").unwrap();
+        let active = HashSet::from_iter(vec![(0, 0), (5, 5)]);
+        assert_eq!(next_generation(&active, neighbours, &rules), active);
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+mod window;
+
+pub use window::{SlidingWindow, WindowValidate, WindowValidateExt};
+
+// Indices of two different elements summing to the target. The first index
+// is smaller than the second one.
+pub fn find_pair_with_sum(values: &[u64], target_sum: u64) -> Option<(usize, usize)> {
+    let mut seen: HashMap<u64, usize> = HashMap::with_capacity(values.len());
+    for (i, &value) in values.iter().enumerate() {
+        if let Some(&j) = target_sum
+            .checked_sub(value)
+            .and_then(|complement| seen.get(&complement))
+        {
+            return Some((j, i));
+        }
+        seen.entry(value).or_insert(i);
+    }
+    None
+}
+
+// Indices of k different elements summing to the target in ascending order.
+// Takes O(n^(k-1)) time.
+pub fn find_k_subset_with_sum(values: &[u64], k: usize, target_sum: u64) -> Option<Vec<usize>> {
+    match k {
+        0 => {
+            if target_sum == 0 {
+                Some(vec![])
+            } else {
+                None
+            }
+        }
+        1 => values
+            .iter()
+            .position(|&value| value == target_sum)
+            .map(|i| vec![i]),
+        2 => find_pair_with_sum(values, target_sum).map(|(i, j)| vec![i, j]),
+        _ => values.iter().enumerate().find_map(|(i, &value)| {
+            let rest = target_sum.checked_sub(value)?;
+            let mut subset = find_k_subset_with_sum(&values[i + 1..], k - 1, rest)?;
+            for j in subset.iter_mut() {
+                *j += i + 1;
+            }
+            subset.insert(0, i);
+            Some(subset)
+        }),
+    }
+}
+
+// Sums of all windows of the given width.
+pub fn window_sums(values: &[u64], width: usize) -> impl Iterator<Item = u64> + '_ {
+    let mut sum: u64 = values.iter().take(width.saturating_sub(1)).sum();
+    values
+        .iter()
+        .enumerate()
+        .skip(width.saturating_sub(1))
+        .filter(move |_| width > 0)
+        .map(move |(i, &value)| {
+            sum += value;
+            let window_sum = sum;
+            sum -= values[i + 1 - width];
+            window_sum
+        })
+}
+
+// First range of at least min_len contiguous elements summing to the target.
+// Uses a sliding window, which requires the values to be positive.
+pub fn find_contiguous_range_with_sum(
+    values: &[u64],
+    target_sum: u64,
+    min_len: usize,
+) -> Option<Range<usize>> {
+    let mut start = 0;
+    let mut sum = 0;
+    for (end, value) in values.iter().enumerate() {
+        sum += value;
+        while sum > target_sum && start <= end {
+            sum -= values[start];
+            start += 1;
+        }
+        if sum == target_sum && end + 1 - start >= min_len.max(1) {
+            return Some(start..end + 1);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static EXPENSES: [u64; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn test_find_pair_with_sum() {
+        assert_eq!(find_pair_with_sum(&EXPENSES, 2020), Some((0, 3)));
+        assert_eq!(find_pair_with_sum(&EXPENSES, 1), None);
+        assert_eq!(find_pair_with_sum(&[5, 10], 10), None);
+        assert_eq!(find_pair_with_sum(&[5, 5], 10), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_find_k_subset_with_sum() {
+        assert_eq!(
+            find_k_subset_with_sum(&EXPENSES, 3, 2020),
+            Some(vec![1, 2, 4])
+        );
+        assert_eq!(find_k_subset_with_sum(&EXPENSES, 2, 2020), Some(vec![0, 3]));
+        assert_eq!(find_k_subset_with_sum(&EXPENSES, 1, 366), Some(vec![2]));
+        assert_eq!(find_k_subset_with_sum(&EXPENSES, 0, 0), Some(vec![]));
+        assert_eq!(find_k_subset_with_sum(&EXPENSES, 4, 2020), None);
+    }
+
+    #[test]
+    fn test_window_sums() {
+        let values = [1, 2, 3, 4];
+        assert_eq!(window_sums(&values, 2).collect::<Vec<_>>(), vec![3, 5, 7]);
+        assert_eq!(window_sums(&values, 4).collect::<Vec<_>>(), vec![10]);
+        assert_eq!(window_sums(&values, 5).count(), 0);
+        assert_eq!(window_sums(&values, 0).count(), 0);
+    }
+
+    #[test]
+    fn test_find_contiguous_range_with_sum() {
+        let values = [
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127,
+        ];
+        assert_eq!(find_contiguous_range_with_sum(&values, 127, 2), Some(2..6));
+        assert_eq!(find_contiguous_range_with_sum(&values, 127, 1), Some(2..6));
+        assert_eq!(find_contiguous_range_with_sum(&values, 62, 1), Some(6..7));
+        assert_eq!(find_contiguous_range_with_sum(&values, 62, 2), None);
+        assert_eq!(find_contiguous_range_with_sum(&values, 1, 1), None);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+// The last `width` values of a stream, against which each new value is
+// checked before it enters the window.
+#[derive(Clone, Debug)]
+pub struct SlidingWindow<T> {
+    width: usize,
+    values: VecDeque<T>,
+}
+
+impl<T> SlidingWindow<T> {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            values: VecDeque::with_capacity(width + 1),
+        }
+    }
+
+    // Checks the value against the window and adds it, dropping the oldest
+    // value. Values arriving before the window is full are valid.
+    pub fn validate_and_push(&mut self, value: T, validate: impl FnOnce(&[T], &T) -> bool) -> bool {
+        let is_valid =
+            self.values.len() < self.width || validate(self.values.make_contiguous(), &value);
+        self.values.push_back(value);
+        if self.values.len() > self.width {
+            self.values.pop_front();
+        }
+        is_valid
+    }
+}
+
+// Iterator returned by window_validate.
+pub struct WindowValidate<I: Iterator, F> {
+    iter: I,
+    window: SlidingWindow<I::Item>,
+    validate: F,
+}
+
+impl<I, F> Iterator for WindowValidate<I, F>
+where
+    I: Iterator,
+    I::Item: Clone,
+    F: FnMut(&[I::Item], &I::Item) -> bool,
+{
+    type Item = (I::Item, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.iter.next()?;
+        let validate = &mut self.validate;
+        let is_valid = self
+            .window
+            .validate_and_push(value.clone(), |window, value| validate(window, value));
+        Some((value, is_valid))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+pub trait WindowValidateExt: Iterator + Sized {
+    // Pairs every element with whether it is valid with respect to the
+    // `preamble` elements before it. The elements of the preamble itself are
+    // valid.
+    fn window_validate<F>(self, preamble: usize, validate: F) -> WindowValidate<Self, F>
+    where
+        F: FnMut(&[Self::Item], &Self::Item) -> bool,
+    {
+        WindowValidate {
+            iter: self,
+            window: SlidingWindow::new(preamble),
+            validate,
+        }
+    }
+}
+
+impl<I: Iterator> WindowValidateExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_validate() {
+        let validated: Vec<(u32, bool)> = vec![1, 2, 4, 3, 8, 8]
+            .into_iter()
+            .window_validate(2, |window, value| window.iter().all(|w| w < value))
+            .collect();
+        assert_eq!(
+            validated,
+            vec![
+                (1, true),
+                (2, true),
+                (4, true),
+                (3, false),
+                (8, true),
+                (8, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_window_validate_without_preamble() {
+        let mut windows = vec![];
+        let n_valid = (0..3)
+            .window_validate(0, |window, _| {
+                windows.push(window.to_vec());
+                true
+            })
+            .filter(|&(_, is_valid)| is_valid)
+            .count();
+        assert_eq!(n_valid, 3);
+        assert_eq!(windows, vec![vec![]; 3]);
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let mut window = SlidingWindow::new(2);
+        assert!(window.validate_and_push(1, |_, _| false));
+        assert!(window.validate_and_push(2, |_, _| false));
+        assert!(window.validate_and_push(3, |window, &value| window == [1, 2] && value == 3));
+        assert!(window.validate_and_push(4, |window, _| window == [2, 3]));
+    }
+}
@@ -0,0 +1,33 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+// Writes an intermediate result as `dir/name.json` to help debugging wrong
+// answers.
+pub fn dump_intermediate(
+    dir: &Path,
+    name: &str,
+    value: &impl Serialize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    let writer = BufWriter::new(File::create(dir.join(format!("{}.json", name)))?);
+    serde_json::to_writer_pretty(writer, value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_dump_intermediate() {
+        let dir = env::temp_dir().join(format!("parsing-dump-{}", std::process::id()));
+        dump_intermediate(&dir, "numbers", &vec![1, 2, 3]).unwrap();
+        let dumped: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("numbers.json")).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(dumped, serde_json::json!([1, 2, 3]));
+    }
+}
@@ -0,0 +1,139 @@
+use nom::{
+    branch::alt,
+    character::complete::line_ending,
+    combinator::{eof, opt, recognize},
+    error::ParseError,
+    multi::many1,
+    sequence::{pair, terminated},
+    IResult, Parser,
+};
+
+#[cfg(feature = "dump")]
+mod dump;
+
+#[cfg(feature = "dump")]
+pub use dump::dump_intermediate;
+
+pub trait Parsable<T> {
+    fn parse(input: &str) -> IResult<&str, T>;
+}
+
+pub fn parse_full<'a, O, E, F>(parser: F, input: &'a str) -> Result<O, nom::Err<E>>
+where
+    E: ParseError<&'a str>,
+    F: Parser<&'a str, O, E>,
+{
+    let (_, output) = terminated(parser, eof)(input)?;
+    Ok(output)
+}
+
+const LINE_BREAK: [char; 2] = ['\n', '\r'];
+
+// Splits the input into the records separated by blank lines. The records
+// are slices of the input without the line ending of their last line.
+pub fn records(input: &str) -> impl Iterator<Item = &str> {
+    let mut remainder = input;
+    std::iter::from_fn(move || {
+        remainder = remainder.trim_start_matches(LINE_BREAK);
+        if remainder.is_empty() {
+            return None;
+        }
+        let mut end = 0;
+        for line in remainder.split_inclusive('\n') {
+            if line.trim_end_matches(LINE_BREAK).is_empty() {
+                break;
+            }
+            end += line.len();
+        }
+        let (record, rest) = remainder.split_at(end);
+        remainder = rest;
+        Some(record.trim_end_matches(LINE_BREAK))
+    })
+}
+
+// Parses a section of blank-line-separated input together with the blank
+// lines following it. The last section may be followed by the end of the
+// input instead.
+pub fn section<'a, O, E, F>(parser: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+where
+    E: ParseError<&'a str>,
+    F: Parser<&'a str, O, E>,
+{
+    terminated(
+        parser,
+        alt((
+            recognize(pair(line_ending, many1(line_ending))),
+            recognize(pair(opt(line_ending), eof)),
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::{
+        bytes::complete::tag,
+        character::complete::digit1,
+        combinator::map_res,
+        error::{Error, ErrorKind},
+        multi::separated_list1,
+        sequence::tuple,
+    };
+
+    struct Number(u32);
+
+    impl Parsable<Number> for Number {
+        fn parse(input: &str) -> IResult<&str, Number> {
+            map_res(digit1, |digits: &str| digits.parse().map(Number))(input)
+        }
+    }
+
+    #[test]
+    fn test_parse_full() {
+        assert_eq!(parse_full(Number::parse, "42").map(|n| n.0), Ok(42));
+    }
+
+    #[test]
+    fn test_parse_full_rejects_remainder() {
+        assert_eq!(
+            parse_full(Number::parse, "42x").map(|n| n.0),
+            Err(nom::Err::Error(Error::new("x", ErrorKind::Eof)))
+        );
+    }
+
+    #[test]
+    fn test_parse_full_propagates_parser_error() {
+        assert_eq!(
+            parse_full(Number::parse, "x").map(|n| n.0),
+            Err(nom::Err::Error(Error::new("x", ErrorKind::Digit)))
+        );
+    }
+
+    #[test]
+    fn test_records() {
+        assert_eq!(
+            records("a\nb\n\nc\n").collect::<Vec<_>>(),
+            vec!["a\nb", "c"]
+        );
+        assert_eq!(
+            records("\r\na\r\n\r\n\r\nb\r\nc").collect::<Vec<_>>(),
+            vec!["a", "b\r\nc"]
+        );
+        assert_eq!(records("\n\n").count(), 0);
+    }
+
+    #[test]
+    fn test_section() {
+        let number = |input| Number::parse(input).map(|(rest, n)| (rest, n.0));
+        let mut sections = tuple((
+            section(separated_list1(line_ending, number)),
+            section(separated_list1(tag(","), number)),
+        ));
+        assert_eq!(
+            sections("1\n2\n\n\n3,4\n"),
+            Ok(("", (vec![1, 2], vec![3, 4])))
+        );
+        assert_eq!(sections("1\n\n3"), Ok(("", (vec![1], vec![3]))));
+        assert!(sections("1\n3").is_err());
+    }
+}
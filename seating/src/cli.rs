@@ -0,0 +1,69 @@
+use super::{Rule, RuleSet, SeatingAutomaton, SeatingRule, DEFAULT_MAX_GENERATIONS};
+use std::env;
+use std::error::Error;
+use std::io::{self, BufRead};
+
+#[cfg(feature = "record")]
+fn advance_recording(
+    automaton: &mut impl SeatingAutomaton,
+    rule: &dyn Rule,
+    max_generations: usize,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut recorder = super::record::GifRecorder::create(
+        path.as_ref(),
+        automaton.n_columns(),
+        automaton.n_rows(),
+    )?;
+    automaton.advance_to_stable_state_observed(rule, max_generations, &mut |automaton| {
+        recorder.record(automaton.seats().iter())
+    })
+}
+
+#[cfg(not(feature = "record"))]
+fn advance_recording(
+    _automaton: &mut impl SeatingAutomaton,
+    _rule: &dyn Rule,
+    _max_generations: usize,
+    _path: &str,
+) -> Result<(), Box<dyn Error>> {
+    Err("--record requires the record feature.".into())
+}
+
+// Advances the automaton parsed from the lines on stdin to a stable state and
+// prints the number of occupied seats. The puzzle rule can be replaced with
+// `--rule`.
+pub fn main<A: SeatingAutomaton>(
+    parse: impl FnOnce(&[String]) -> Result<A, &'static str>,
+    puzzle_rule: SeatingRule,
+) -> Result<(), Box<dyn Error>> {
+    let mut record_path = None;
+    let mut rule: Box<dyn Rule> = Box::new(puzzle_rule);
+    let mut max_generations = DEFAULT_MAX_GENERATIONS;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => record_path = Some(args.next().ok_or("--record requires a path.")?),
+            "--rule" => {
+                let rule_set: RuleSet = args.next().ok_or("--rule requires a rule.")?.parse()?;
+                rule = Box::new(rule_set);
+            }
+            "--max-generations" => {
+                max_generations = args
+                    .next()
+                    .ok_or("--max-generations requires a number.")?
+                    .parse()?
+            }
+            _ => return Err(format!("Unknown argument '{}'.", arg).into()),
+        }
+    }
+
+    let lines = io::stdin().lock().lines().collect::<Result<Vec<_>, _>>()?;
+    let mut automaton = parse(&lines)?;
+    match &record_path {
+        Some(path) => advance_recording(&mut automaton, rule.as_ref(), max_generations, path)?,
+        None => automaton.advance_to_stable_state(rule.as_ref(), max_generations)?,
+    }
+    println!("Occupied seats: {}", automaton.n_occupied());
+    Ok(())
+}
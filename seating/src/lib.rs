@@ -0,0 +1,108 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+pub mod cli;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod rule;
+
+pub use rule::{Rule, RuleSet, SeatingRule};
+
+// Generations after which an automaton is given up on, as not every rule
+// leads to a stable state.
+pub const DEFAULT_MAX_GENERATIONS: usize = 10_000;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GridPos {
+    Floor,
+    Seat(bool),
+}
+
+impl GridPos {
+    pub fn is_occupied(&self) -> bool {
+        match self {
+            Self::Floor => false,
+            Self::Seat(is_occupied) => *is_occupied,
+        }
+    }
+}
+
+impl TryFrom<char> for GridPos {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Self, &'static str> {
+        match c {
+            '.' => Ok(GridPos::Floor),
+            'L' => Ok(GridPos::Seat(false)),
+            '#' => Ok(GridPos::Seat(true)),
+            _ => Err("Invalid seat marker."),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NotStableError {
+    pub max_generations: usize,
+}
+
+impl Display for NotStableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "No stable state reached within {} generations.",
+            self.max_generations
+        )
+    }
+}
+
+impl std::error::Error for NotStableError {}
+
+// Grid of seats that changes by a rule each generation.
+pub trait SeatingAutomaton {
+    fn advance(&mut self, rule: &dyn Rule);
+
+    // Whether the last call to advance did not change anything.
+    fn is_stable(&self) -> bool;
+
+    // The seats row by row.
+    fn seats(&self) -> &[GridPos];
+    fn n_columns(&self) -> usize;
+    fn n_rows(&self) -> usize;
+
+    fn n_occupied(&self) -> usize {
+        self.seats().iter().filter(|s| s.is_occupied()).count()
+    }
+
+    // Gives up once the automaton advanced `max_generations` times without
+    // stabilising.
+    fn advance_to_stable_state(
+        &mut self,
+        rule: &dyn Rule,
+        max_generations: usize,
+    ) -> Result<(), NotStableError> {
+        self.advance_to_stable_state_observed(rule, max_generations, &mut |_| Ok(()))
+    }
+
+    // Like advance_to_stable_state, but calls observe with the initial state
+    // and after each generation. An error of observe stops the automaton.
+    fn advance_to_stable_state_observed<E: From<NotStableError>>(
+        &mut self,
+        rule: &dyn Rule,
+        max_generations: usize,
+        observe: &mut dyn FnMut(&Self) -> Result<(), E>,
+    ) -> Result<(), E> {
+        observe(self)?;
+        for _ in 0..max_generations {
+            if self.is_stable() {
+                return Ok(());
+            }
+            self.advance(rule);
+            observe(self)?;
+        }
+        if self.is_stable() {
+            Ok(())
+        } else {
+            Err(NotStableError { max_generations }.into())
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::GridPos;
+use gif::{Encoder, Frame, Repeat};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+// Side length of a seat in pixels.
+const SCALE: usize = 4;
+// Delay between frames in hundredths of a second.
+const FRAME_DELAY: u16 = 20;
+// Colors of floor, empty seats, and occupied seats, in that order.
+const PALETTE: [u8; 9] = [0x20, 0x20, 0x20, 0x4c, 0xaf, 0x50, 0xe9, 0x1e, 0x63];
+
+// Encodes generations of a seat grid as frames of an animated GIF.
+pub struct GifRecorder {
+    encoder: Encoder<BufWriter<File>>,
+    n_columns: usize,
+    width: u16,
+    height: u16,
+}
+
+impl GifRecorder {
+    pub fn create(
+        path: &Path,
+        n_columns: usize,
+        n_rows: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let width = u16::try_from(n_columns * SCALE)?;
+        let height = u16::try_from(n_rows * SCALE)?;
+        let mut encoder =
+            Encoder::new(BufWriter::new(File::create(path)?), width, height, &PALETTE)?;
+        encoder.set_repeat(Repeat::Infinite)?;
+        Ok(Self {
+            encoder,
+            n_columns,
+            width,
+            height,
+        })
+    }
+
+    // Records the seats given row by row.
+    pub fn record<'a>(
+        &mut self,
+        seats: impl Iterator<Item = &'a GridPos>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut pixels = vec![0; usize::from(self.width) * usize::from(self.height)];
+        for (i, seat) in seats.enumerate() {
+            let (row, column) = (i / self.n_columns, i % self.n_columns);
+            let color = match seat {
+                GridPos::Floor => 0,
+                GridPos::Seat(false) => 1,
+                GridPos::Seat(true) => 2,
+            };
+            for y in row * SCALE..(row + 1) * SCALE {
+                let line = y * usize::from(self.width);
+                pixels[line + column * SCALE..line + (column + 1) * SCALE].fill(color);
+            }
+        }
+        let mut frame = Frame::from_indexed_pixels(self.width, self.height, pixels, None);
+        frame.delay = FRAME_DELAY;
+        self.encoder.write_frame(&frame)?;
+        Ok(())
+    }
+}
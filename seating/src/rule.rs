@@ -0,0 +1,323 @@
+use crate::GridPos;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+// Transition of a single grid position based on its occupied neighbours.
+pub trait Rule {
+    fn next(&self, pos: GridPos, n_occupied_neighbours: usize) -> GridPos;
+}
+
+// The puzzle rule: empty seats without occupied neighbours become occupied,
+// occupied seats with at least `tolerance` occupied neighbours become empty.
+pub struct SeatingRule {
+    pub tolerance: usize,
+}
+
+impl Rule for SeatingRule {
+    fn next(&self, pos: GridPos, n_occupied_neighbours: usize) -> GridPos {
+        match (pos, n_occupied_neighbours) {
+            (GridPos::Seat(false), 0) => GridPos::Seat(true),
+            (GridPos::Seat(true), n) if n >= self.tolerance => GridPos::Seat(false),
+            (pos, _) => pos,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl Comparison {
+    fn holds(self, lhs: usize, rhs: usize) -> bool {
+        match self {
+            Self::Equal => lhs == rhs,
+            Self::NotEqual => lhs != rhs,
+            Self::Less => lhs < rhs,
+            Self::LessEqual => lhs <= rhs,
+            Self::Greater => lhs > rhs,
+            Self::GreaterEqual => lhs >= rhs,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    State(GridPos),
+    Neighbours(Comparison, usize),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn holds(&self, pos: GridPos, n_occupied_neighbours: usize) -> bool {
+        match self {
+            Self::State(state) => pos == *state,
+            Self::Neighbours(comparison, n) => comparison.holds(n_occupied_neighbours, *n),
+            Self::Not(condition) => !condition.holds(pos, n_occupied_neighbours),
+            Self::And(lhs, rhs) => {
+                lhs.holds(pos, n_occupied_neighbours) && rhs.holds(pos, n_occupied_neighbours)
+            }
+            Self::Or(lhs, rhs) => {
+                lhs.holds(pos, n_occupied_neighbours) || rhs.holds(pos, n_occupied_neighbours)
+            }
+        }
+    }
+}
+
+// Rules given as `condition -> state` clauses separated by semicolons, e.g.
+// `occupied && neighbours >= 5 -> empty; empty && neighbours == 0 -> occupied`.
+// Conditions combine the states floor, empty, and occupied and comparisons of
+// the number of occupied neighbours with `!`, `&&`, `||`, and parentheses. The
+// first clause with a satisfied condition applies; without any the position
+// stays as it is.
+#[derive(Debug, PartialEq)]
+pub struct RuleSet {
+    clauses: Vec<(Condition, GridPos)>,
+}
+
+impl Rule for RuleSet {
+    fn next(&self, pos: GridPos, n_occupied_neighbours: usize) -> GridPos {
+        self.clauses
+            .iter()
+            .find(|(condition, _)| condition.holds(pos, n_occupied_neighbours))
+            .map_or(pos, |&(_, next)| next)
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = RuleParser {
+            tokens: Tokenizer::new(s)
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .peekable(),
+        };
+        let mut clauses = vec![parser.clause()?];
+        while parser.tokens.next_if_eq(&Token::Semicolon).is_some() {
+            if parser.tokens.peek().is_none() {
+                break;
+            }
+            clauses.push(parser.clause()?);
+        }
+        match parser.tokens.next() {
+            Some(token) => Err(format!("Unexpected {:?} in rule.", token)),
+            None => Ok(Self { clauses }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(usize),
+    Comparison(Comparison),
+    Not,
+    And,
+    Or,
+    Arrow,
+    Semicolon,
+    OpenParens,
+    CloseParens,
+}
+
+struct Tokenizer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    // Consumes characters while the predicate holds and returns the slice of
+    // the input from start to there.
+    fn take_while(&mut self, start: usize, predicate: impl Fn(char) -> bool) -> &'a str {
+        let mut end = self.input.len();
+        while let Some(&(i, c)) = self.chars.peek() {
+            if !predicate(c) {
+                end = i;
+                break;
+            }
+            self.chars.next();
+        }
+        &self.input[start..end]
+    }
+
+    fn followed_by(&mut self, expected: char) -> bool {
+        self.chars.next_if(|&(_, c)| c == expected).is_some()
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, c) = self.chars.find(|(_, c)| !c.is_whitespace())?;
+        Some(match c {
+            '(' => Ok(Token::OpenParens),
+            ')' => Ok(Token::CloseParens),
+            ';' => Ok(Token::Semicolon),
+            '&' if self.followed_by('&') => Ok(Token::And),
+            '|' if self.followed_by('|') => Ok(Token::Or),
+            '-' if self.followed_by('>') => Ok(Token::Arrow),
+            '=' if self.followed_by('=') => Ok(Token::Comparison(Comparison::Equal)),
+            '!' if self.followed_by('=') => Ok(Token::Comparison(Comparison::NotEqual)),
+            '!' => Ok(Token::Not),
+            '<' if self.followed_by('=') => Ok(Token::Comparison(Comparison::LessEqual)),
+            '<' => Ok(Token::Comparison(Comparison::Less)),
+            '>' if self.followed_by('=') => Ok(Token::Comparison(Comparison::GreaterEqual)),
+            '>' => Ok(Token::Comparison(Comparison::Greater)),
+            c if c.is_ascii_digit() => self
+                .take_while(i, |c| c.is_ascii_digit())
+                .parse()
+                .map(Token::Number)
+                .map_err(|err| format!("Invalid number in rule: {}", err)),
+            c if c.is_ascii_alphabetic() => Ok(Token::Ident(String::from(
+                self.take_while(i, |c| c.is_ascii_alphanumeric()),
+            ))),
+            c => Err(format!("Unexpected character '{}' in rule.", c)),
+        })
+    }
+}
+
+// Recursive descent with `||` binding weaker than `&&`.
+struct RuleParser<I: Iterator<Item = Token>> {
+    tokens: Peekable<I>,
+}
+
+impl<I: Iterator<Item = Token>> RuleParser<I> {
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.tokens.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {:?} in rule, got {:?}.", expected, token)),
+            None => Err(format!("Expected {:?} at the end of the rule.", expected)),
+        }
+    }
+
+    fn clause(&mut self) -> Result<(Condition, GridPos), String> {
+        let condition = self.disjunction()?;
+        self.expect(Token::Arrow)?;
+        match self.tokens.next() {
+            Some(Token::Ident(name)) => Ok((condition, parse_state(&name)?)),
+            Some(token) => Err(format!("Expected a state in rule, got {:?}.", token)),
+            None => Err(String::from("Expected a state at the end of the rule.")),
+        }
+    }
+
+    fn disjunction(&mut self) -> Result<Condition, String> {
+        let mut condition = self.conjunction()?;
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            condition = Condition::Or(Box::new(condition), Box::new(self.conjunction()?));
+        }
+        Ok(condition)
+    }
+
+    fn conjunction(&mut self) -> Result<Condition, String> {
+        let mut condition = self.operand()?;
+        while self.tokens.next_if_eq(&Token::And).is_some() {
+            condition = Condition::And(Box::new(condition), Box::new(self.operand()?));
+        }
+        Ok(condition)
+    }
+
+    fn operand(&mut self) -> Result<Condition, String> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(Condition::Not(Box::new(self.operand()?))),
+            Some(Token::OpenParens) => {
+                let condition = self.disjunction()?;
+                self.expect(Token::CloseParens)?;
+                Ok(condition)
+            }
+            Some(Token::Ident(name)) if name == "neighbours" => {
+                match (self.tokens.next(), self.tokens.next()) {
+                    (Some(Token::Comparison(comparison)), Some(Token::Number(n))) => {
+                        Ok(Condition::Neighbours(comparison, n))
+                    }
+                    _ => Err(String::from(
+                        "Expected a comparison with a number after 'neighbours' in rule.",
+                    )),
+                }
+            }
+            Some(Token::Ident(name)) => Ok(Condition::State(parse_state(&name)?)),
+            Some(token) => Err(format!("Unexpected {:?} in rule.", token)),
+            None => Err(String::from("Unexpected end of rule.")),
+        }
+    }
+}
+
+fn parse_state(name: &str) -> Result<GridPos, String> {
+    match name {
+        "floor" => Ok(GridPos::Floor),
+        "empty" => Ok(GridPos::Seat(false)),
+        "occupied" => Ok(GridPos::Seat(true)),
+        _ => Err(format!("Unknown state '{}' in rule.", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATES: [GridPos; 3] = [GridPos::Floor, GridPos::Seat(false), GridPos::Seat(true)];
+
+    #[test]
+    fn test_rule_set_matches_seating_rule() {
+        let rule_set: RuleSet =
+            "occupied && neighbours>=5 -> empty; empty && neighbours==0 -> occupied"
+                .parse()
+                .unwrap();
+        let seating_rule = SeatingRule { tolerance: 5 };
+        for &pos in &STATES {
+            for n in 0..=8 {
+                assert_eq!(rule_set.next(pos, n), seating_rule.next(pos, n));
+            }
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        let rule_set: RuleSet = "!floor && neighbours < 2 || neighbours > 6 -> floor;"
+            .parse()
+            .unwrap();
+        assert_eq!(rule_set.next(GridPos::Seat(true), 1), GridPos::Floor);
+        assert_eq!(rule_set.next(GridPos::Seat(true), 3), GridPos::Seat(true));
+        assert_eq!(rule_set.next(GridPos::Seat(false), 7), GridPos::Floor);
+
+        let parenthesized: RuleSet = "!floor && (neighbours < 2 || neighbours > 6) -> occupied"
+            .parse()
+            .unwrap();
+        assert_eq!(parenthesized.next(GridPos::Floor, 7), GridPos::Floor);
+        assert_eq!(
+            parenthesized.next(GridPos::Seat(false), 7),
+            GridPos::Seat(true)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for rule in &[
+            "",
+            "occupied",
+            "occupied -> full",
+            "neighbours 5 -> empty",
+            "(empty -> occupied",
+            "empty & occupied -> floor",
+            "empty -> occupied occupied",
+        ] {
+            assert!(rule.parse::<RuleSet>().is_err(), "{}", rule);
+        }
+    }
+}
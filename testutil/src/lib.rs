@@ -0,0 +1,22 @@
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+
+// Runs a binary with a fixture from tests/fixtures of the crate in
+// manifest_dir as stdin and returns its stdout. Pass
+// env!("CARGO_BIN_EXE_<name>") and env!("CARGO_MANIFEST_DIR") of the tested
+// crate, as these are only set when compiling its tests.
+pub fn run(binary: &str, manifest_dir: &str, fixture: &str, args: &[&str]) -> String {
+    let path = Path::new(manifest_dir).join("tests/fixtures").join(fixture);
+    let output = Command::new(binary)
+        .args(args)
+        .stdin(File::open(path).unwrap())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
@@ -0,0 +1,143 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+mod transform;
+
+pub use transform::Transform;
+
+// Integer vector in a plane with x pointing east and y pointing north.
+// Rotations are counter-clockwise, so that rotating east by 90 degrees gives
+// north.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Vec2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Vec2 {
+    pub const ZERO: Self = Self::new(0, 0);
+    pub const NORTH: Self = Self::new(0, 1);
+    pub const SOUTH: Self = Self::new(0, -1);
+    pub const EAST: Self = Self::new(1, 0);
+    pub const WEST: Self = Self::new(-1, 0);
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn rotate90(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn rotate180(self) -> Self {
+        -self
+    }
+
+    pub fn rotate270(self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+
+    // Rotates by the given number of quarter turns, negative numbers rotate
+    // clockwise.
+    pub fn rotate(self, quarter_turns: i32) -> Self {
+        match quarter_turns.rem_euclid(4) {
+            0 => self,
+            1 => self.rotate90(),
+            2 => self.rotate180(),
+            _ => self.rotate270(),
+        }
+    }
+
+    // Mirrors at the x axis.
+    pub fn reflect_x(self) -> Self {
+        Self::new(self.x, -self.y)
+    }
+
+    // Mirrors at the y axis.
+    pub fn reflect_y(self) -> Self {
+        Self::new(-self.x, self.y)
+    }
+
+    pub fn manhattan_norm(self) -> i64 {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl From<(i64, i64)> for Vec2 {
+    fn from((x, y): (i64, i64)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotations() {
+        let v = Vec2::new(10, 4);
+        assert_eq!(Vec2::EAST.rotate90(), Vec2::NORTH);
+        assert_eq!(v.rotate90(), Vec2::new(-4, 10));
+        assert_eq!(v.rotate180(), Vec2::new(-10, -4));
+        assert_eq!(v.rotate270(), Vec2::new(4, -10));
+        assert_eq!(v.rotate90().rotate270(), v);
+        for quarter_turns in -5..=5i32 {
+            let expected = (0..quarter_turns.rem_euclid(4)).fold(v, |v, _| v.rotate90());
+            assert_eq!(v.rotate(quarter_turns), expected, "{}", quarter_turns);
+        }
+    }
+
+    #[test]
+    fn test_reflections() {
+        let v = Vec2::new(3, -2);
+        assert_eq!(v.reflect_x(), Vec2::new(3, 2));
+        assert_eq!(v.reflect_y(), Vec2::new(-3, -2));
+        assert_eq!(v.reflect_x().reflect_y(), v.rotate180());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut v = Vec2::new(1, 2);
+        v += Vec2::new(3, -5) * 2;
+        assert_eq!(v, Vec2::new(7, -8));
+        assert_eq!(v - Vec2::new(7, -8), Vec2::ZERO);
+        assert_eq!(v.manhattan_norm(), 15);
+    }
+}
@@ -0,0 +1,165 @@
+use super::Vec2;
+
+// One of the 8 symmetries of a square: a reflection at the x axis if flipped,
+// followed by the given number of counter-clockwise quarter turns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Transform {
+    quarter_turns: u8,
+    flipped: bool,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self::new(0, false);
+
+    // All transforms, the rotations first.
+    pub const ALL: [Self; 8] = [
+        Self::new(0, false),
+        Self::new(1, false),
+        Self::new(2, false),
+        Self::new(3, false),
+        Self::new(0, true),
+        Self::new(1, true),
+        Self::new(2, true),
+        Self::new(3, true),
+    ];
+
+    const fn new(quarter_turns: u8, flipped: bool) -> Self {
+        Self {
+            quarter_turns: quarter_turns % 4,
+            flipped,
+        }
+    }
+
+    // Negative numbers rotate clockwise.
+    pub fn rotation(quarter_turns: i32) -> Self {
+        Self::new(quarter_turns.rem_euclid(4) as u8, false)
+    }
+
+    pub fn reflection_x() -> Self {
+        Self::new(0, true)
+    }
+
+    pub fn reflection_y() -> Self {
+        Self::new(2, true)
+    }
+
+    pub fn quarter_turns(self) -> u8 {
+        self.quarter_turns
+    }
+
+    pub fn is_flipped(self) -> bool {
+        self.flipped
+    }
+
+    pub fn apply(self, v: Vec2) -> Vec2 {
+        let v = if self.flipped { v.reflect_x() } else { v };
+        v.rotate(self.quarter_turns.into())
+    }
+
+    // The transform applying self first and then other. A rotation after a
+    // reflection equals the reflection after the opposite rotation.
+    pub fn then(self, other: Self) -> Self {
+        let quarter_turns = if other.flipped {
+            4 - self.quarter_turns
+        } else {
+            self.quarter_turns
+        };
+        Self::new(
+            quarter_turns + other.quarter_turns,
+            self.flipped != other.flipped,
+        )
+    }
+
+    // Reflections are their own inverse, even combined with a rotation.
+    pub fn inverse(self) -> Self {
+        if self.flipped {
+            self
+        } else {
+            Self::new(4 - self.quarter_turns, false)
+        }
+    }
+
+    // Transforms a grid given as rows from top to bottom, i.e. with the
+    // y axis pointing up the rows. The rows must have equal length.
+    pub fn apply_to_rows<T: Clone>(self, rows: &[Vec<T>]) -> Vec<Vec<T>> {
+        let height = rows.len() as i64;
+        let width = rows.first().map_or(0, Vec::len) as i64;
+        let corner = self.apply(Vec2::new(width - 1, 1 - height));
+        let (min_x, max_y) = (corner.x.min(0), corner.y.max(0));
+        let (new_width, new_height) = match self.quarter_turns {
+            1 | 3 => (height, width),
+            _ => (width, height),
+        };
+
+        let mut transformed: Vec<Vec<Option<T>>> =
+            vec![vec![None; new_width as usize]; new_height as usize];
+        for (row, cells) in rows.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let v = self.apply(Vec2::new(column as i64, -(row as i64)));
+                transformed[(max_y - v.y) as usize][(v.x - min_x) as usize] = Some(cell.clone());
+            }
+        }
+        transformed
+            .into_iter()
+            .map(|cells| cells.into_iter().map(Option::unwrap).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(lines: &[&str]) -> Vec<Vec<char>> {
+        lines.iter().map(|line| line.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_apply() {
+        let v = Vec2::new(3, 1);
+        assert_eq!(Transform::rotation(1).apply(v), v.rotate90());
+        assert_eq!(Transform::rotation(-1).apply(v), v.rotate270());
+        assert_eq!(Transform::reflection_x().apply(v), v.reflect_x());
+        assert_eq!(Transform::reflection_y().apply(v), v.reflect_y());
+        let images: std::collections::HashSet<Vec2> =
+            Transform::ALL.iter().map(|t| t.apply(v)).collect();
+        assert_eq!(images.len(), 8);
+    }
+
+    #[test]
+    fn test_composition_and_inverse() {
+        let v = Vec2::new(5, -2);
+        for &a in &Transform::ALL {
+            assert_eq!(a.then(a.inverse()), Transform::IDENTITY);
+            assert_eq!(a.inverse().then(a), Transform::IDENTITY);
+            for &b in &Transform::ALL {
+                assert_eq!(a.then(b).apply(v), b.apply(a.apply(v)), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_to_rows() {
+        let grid = rows(&["ab", "cd", "ef"]);
+        assert_eq!(
+            Transform::rotation(1).apply_to_rows(&grid),
+            rows(&["bdf", "ace"])
+        );
+        assert_eq!(
+            Transform::rotation(2).apply_to_rows(&grid),
+            rows(&["fe", "dc", "ba"])
+        );
+        assert_eq!(
+            Transform::reflection_x().apply_to_rows(&grid),
+            rows(&["ef", "cd", "ab"])
+        );
+        assert_eq!(
+            Transform::reflection_y().apply_to_rows(&grid),
+            rows(&["ba", "dc", "fe"])
+        );
+        for &t in &Transform::ALL {
+            assert_eq!(t.inverse().apply_to_rows(&t.apply_to_rows(&grid)), grid);
+        }
+        assert!(Transform::rotation(1).apply_to_rows::<char>(&[]).is_empty());
+    }
+}